@@ -0,0 +1,12 @@
+-- Indexes backing GET /api/orders filtering and keyset pagination
+
+-- Keyset pagination walks (created_at, id) newest first
+CREATE INDEX idx_orders_created_at_id ON orders(created_at DESC, id DESC);
+
+-- Order number prefix search (LIKE 'ORD-123%')
+CREATE INDEX idx_orders_order_number_prefix ON orders(order_number varchar_pattern_ops);
+
+-- Case-insensitive customer email lookup
+CREATE INDEX idx_orders_customer_email ON orders(LOWER(customer_email));
+
+CREATE INDEX idx_orders_payment_method ON orders(payment_method);
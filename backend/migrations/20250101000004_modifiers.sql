@@ -0,0 +1,90 @@
+-- Product modifiers: option groups (size, milk, extra shots) with priced options
+
+CREATE TABLE modifier_groups (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL,
+    is_required BOOLEAN NOT NULL DEFAULT false,
+    min_selections INTEGER NOT NULL DEFAULT 0,
+    max_selections INTEGER NOT NULL DEFAULT 1,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (min_selections >= 0),
+    CHECK (max_selections >= min_selections),
+    CHECK (NOT is_required OR min_selections >= 1)
+);
+
+CREATE TABLE modifier_options (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    group_id UUID NOT NULL REFERENCES modifier_groups(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    price_delta_cents BIGINT NOT NULL DEFAULT 0, -- Added to the item unit price
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_modifier_options_group_id ON modifier_options(group_id);
+
+-- Groups can be attached to a single product or to a whole category
+CREATE TABLE product_modifier_groups (
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    group_id UUID NOT NULL REFERENCES modifier_groups(id) ON DELETE CASCADE,
+    PRIMARY KEY (product_id, group_id)
+);
+
+CREATE TABLE category_modifier_groups (
+    category_id UUID NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+    group_id UUID NOT NULL REFERENCES modifier_groups(id) ON DELETE CASCADE,
+    PRIMARY KEY (category_id, group_id)
+);
+
+CREATE INDEX idx_product_modifier_groups_group_id ON product_modifier_groups(group_id);
+CREATE INDEX idx_category_modifier_groups_group_id ON category_modifier_groups(group_id);
+
+-- Modifiers chosen for an order line, denormalized for historical record
+CREATE TABLE order_item_modifiers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    order_item_id UUID NOT NULL REFERENCES order_items(id) ON DELETE CASCADE,
+    modifier_option_id UUID REFERENCES modifier_options(id) ON DELETE SET NULL,
+    group_name VARCHAR(255) NOT NULL,
+    option_name VARCHAR(255) NOT NULL,
+    price_delta_cents BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_order_item_modifiers_order_item_id ON order_item_modifiers(order_item_id);
+
+CREATE TRIGGER update_modifier_groups_updated_at BEFORE UPDATE ON modifier_groups
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_modifier_options_updated_at BEFORE UPDATE ON modifier_options
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Sample coffee modifiers
+INSERT INTO modifier_groups (id, name, is_required, min_selections, max_selections, sort_order) VALUES
+    ('30000000-0000-0000-0000-000000000001', 'Size', true, 1, 1, 1),
+    ('30000000-0000-0000-0000-000000000002', 'Milk', false, 0, 1, 2),
+    ('30000000-0000-0000-0000-000000000003', 'Extra Shots', false, 0, 3, 3);
+
+INSERT INTO modifier_options (group_id, name, price_delta_cents, sort_order) VALUES
+    ('30000000-0000-0000-0000-000000000001', 'Small', 0, 1),
+    ('30000000-0000-0000-0000-000000000001', 'Medium', 50, 2),
+    ('30000000-0000-0000-0000-000000000001', 'Large', 100, 3),
+    ('30000000-0000-0000-0000-000000000002', 'Whole Milk', 0, 1),
+    ('30000000-0000-0000-0000-000000000002', 'Oat Milk', 60, 2),
+    ('30000000-0000-0000-0000-000000000002', 'Almond Milk', 60, 3),
+    ('30000000-0000-0000-0000-000000000003', 'Extra Shot', 75, 1),
+    ('30000000-0000-0000-0000-000000000003', 'Decaf Shot', 75, 2);
+
+INSERT INTO category_modifier_groups (category_id, group_id) VALUES
+    ('10000000-0000-0000-0000-000000000001', '30000000-0000-0000-0000-000000000001');
+
+INSERT INTO product_modifier_groups (product_id, group_id) VALUES
+    ('20000000-0000-0000-0000-000000000003', '30000000-0000-0000-0000-000000000002'),
+    ('20000000-0000-0000-0000-000000000003', '30000000-0000-0000-0000-000000000003'),
+    ('20000000-0000-0000-0000-000000000004', '30000000-0000-0000-0000-000000000002'),
+    ('20000000-0000-0000-0000-000000000004', '30000000-0000-0000-0000-000000000003'),
+    ('20000000-0000-0000-0000-000000000005', '30000000-0000-0000-0000-000000000002');
@@ -0,0 +1,41 @@
+-- Product variants: sizes and flavors sharing a parent product
+
+CREATE TABLE product_variants (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL, -- e.g. "500ml", "Blueberry"
+    sku VARCHAR(255) UNIQUE,
+    barcode VARCHAR(255),
+    price_cents BIGINT NOT NULL, -- Price in cents
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_variants_product_id ON product_variants(product_id);
+CREATE INDEX idx_product_variants_sku ON product_variants(sku);
+CREATE INDEX idx_product_variants_barcode ON product_variants(barcode);
+
+CREATE TRIGGER update_product_variants_updated_at BEFORE UPDATE ON product_variants
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Inventory is held per variant when a product has variants
+ALTER TABLE inventory ADD COLUMN variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE;
+ALTER TABLE inventory DROP CONSTRAINT inventory_product_id_key;
+CREATE UNIQUE INDEX idx_inventory_product_unique ON inventory(product_id) WHERE variant_id IS NULL;
+CREATE UNIQUE INDEX idx_inventory_variant_unique ON inventory(variant_id) WHERE variant_id IS NOT NULL;
+
+ALTER TABLE order_items ADD COLUMN variant_id UUID REFERENCES product_variants(id) ON DELETE SET NULL;
+CREATE INDEX idx_order_items_variant_id ON order_items(variant_id);
+
+-- Sample variants
+INSERT INTO product_variants (id, product_id, name, sku, barcode, price_cents, sort_order) VALUES
+    ('21000000-0000-0000-0000-000000000001', '20000000-0000-0000-0000-000000000013', '330ml', 'BTL-OJ-330', '1234567891001', 350, 1),
+    ('21000000-0000-0000-0000-000000000002', '20000000-0000-0000-0000-000000000013', '500ml', 'BTL-OJ-500', '1234567891002', 475, 2),
+    ('21000000-0000-0000-0000-000000000003', '20000000-0000-0000-0000-000000000011', 'Blueberry', 'PAST-MUF-BLU', '1234567891003', 295, 1),
+    ('21000000-0000-0000-0000-000000000004', '20000000-0000-0000-0000-000000000011', 'Chocolate', 'PAST-MUF-CHO', '1234567891004', 325, 2);
+
+INSERT INTO inventory (product_id, variant_id, quantity, reorder_level, reorder_quantity)
+SELECT product_id, id, 50, 10, 25 FROM product_variants;
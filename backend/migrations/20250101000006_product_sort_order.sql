@@ -0,0 +1,5 @@
+-- Manual ordering of products within the catalog
+
+ALTER TABLE products ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_products_sort_order ON products(sort_order);
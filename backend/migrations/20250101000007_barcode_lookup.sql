@@ -0,0 +1,38 @@
+-- Barcode lookup by normalized GTIN
+
+-- Replace the sample barcodes with ones that carry valid GS1 check digits
+UPDATE products SET barcode = v.barcode
+FROM (VALUES
+    ('20000000-0000-0000-0000-000000000001', '1234567890012'),
+    ('20000000-0000-0000-0000-000000000002', '1234567890029'),
+    ('20000000-0000-0000-0000-000000000003', '1234567890036'),
+    ('20000000-0000-0000-0000-000000000004', '1234567890043'),
+    ('20000000-0000-0000-0000-000000000005', '1234567890050'),
+    ('20000000-0000-0000-0000-000000000006', '1234567890067'),
+    ('20000000-0000-0000-0000-000000000007', '1234567890074'),
+    ('20000000-0000-0000-0000-000000000008', '1234567890081'),
+    ('20000000-0000-0000-0000-000000000009', '1234567890098'),
+    ('20000000-0000-0000-0000-000000000010', '1234567890104'),
+    ('20000000-0000-0000-0000-000000000011', '1234567890111'),
+    ('20000000-0000-0000-0000-000000000012', '1234567890128'),
+    ('20000000-0000-0000-0000-000000000013', '1234567890135'),
+    ('20000000-0000-0000-0000-000000000014', '1234567890142')
+) AS v(id, barcode)
+WHERE products.id = v.id::uuid;
+
+UPDATE product_variants SET barcode = v.barcode
+FROM (VALUES
+    ('21000000-0000-0000-0000-000000000001', '1234567891019'),
+    ('21000000-0000-0000-0000-000000000002', '1234567891026'),
+    ('21000000-0000-0000-0000-000000000003', '1234567891033'),
+    ('21000000-0000-0000-0000-000000000004', '1234567891040')
+) AS v(id, barcode)
+WHERE product_variants.id = v.id::uuid;
+
+-- Stored barcodes are compared zero-padded to 14 digits (GTIN-14 form)
+CREATE INDEX idx_products_barcode_gtin ON products(LPAD(barcode, 14, '0'));
+CREATE INDEX idx_product_variants_barcode_gtin ON product_variants(LPAD(barcode, 14, '0'));
+
+-- Weighed items are matched on the 7-digit "2x IIIII" prefix of their scale label
+CREATE INDEX idx_products_barcode_scale_prefix ON products(LEFT(LPAD(barcode, 13, '0'), 7))
+    WHERE LEFT(LPAD(barcode, 13, '0'), 1) = '2';
@@ -0,0 +1,18 @@
+-- Ranked, typo-tolerant product search
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Full-text document weighted name (A) > description (B) > SKU (C).
+-- The 'simple' configuration avoids stemming product names and SKUs.
+ALTER TABLE products ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', coalesce(name, '')), 'A') ||
+    setweight(to_tsvector('simple', coalesce(description, '')), 'B') ||
+    setweight(to_tsvector('simple', coalesce(sku, '')), 'C')
+) STORED;
+
+CREATE INDEX idx_products_search_vector ON products USING GIN (search_vector);
+
+-- Trigram indexes for typo tolerance ("capucino" -> "Cappuccino")
+CREATE INDEX idx_products_name_trgm ON products USING GIN (name gin_trgm_ops);
+CREATE INDEX idx_products_description_trgm ON products USING GIN (description gin_trgm_ops);
+CREATE INDEX idx_products_sku_trgm ON products USING GIN (sku gin_trgm_ops);
@@ -0,0 +1,25 @@
+-- Price history and scheduled price changes
+
+CREATE TABLE product_prices (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    price_cents BIGINT NOT NULL, -- Price in cents
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    effective_from TIMESTAMPTZ NOT NULL,
+    effective_to TIMESTAMPTZ, -- NULL = open-ended
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (price_cents >= 0),
+    CHECK (effective_to IS NULL OR effective_to > effective_from)
+);
+
+CREATE INDEX idx_product_prices_product_effective
+    ON product_prices(product_id, variant_id, effective_from DESC);
+
+-- Seed history with the current catalog prices
+INSERT INTO product_prices (product_id, price_cents, currency, effective_from)
+SELECT id, price_cents, currency, created_at FROM products;
+
+INSERT INTO product_prices (product_id, variant_id, price_cents, currency, effective_from)
+SELECT product_id, id, price_cents, currency, created_at FROM product_variants;
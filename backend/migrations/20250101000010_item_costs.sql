@@ -0,0 +1,10 @@
+-- Per-item cost tracking for margin reporting
+
+ALTER TABLE products ADD COLUMN cost_cents BIGINT CHECK (cost_cents >= 0);
+ALTER TABLE product_variants ADD COLUMN cost_cents BIGINT CHECK (cost_cents >= 0);
+
+-- Cost snapshot at sale time; NULL when the item had no cost recorded
+ALTER TABLE order_items ADD COLUMN unit_cost_cents BIGINT;
+ALTER TABLE order_items ADD COLUMN total_cost_cents BIGINT;
+
+CREATE INDEX idx_orders_completed_at ON orders(completed_at) WHERE status = 'completed';
@@ -0,0 +1,55 @@
+-- Availability windows for time-of-day menus and seasonal items
+--
+-- A product or category with no windows is always available. Otherwise it is
+-- available while any of its windows is open, and a product must also pass its
+-- category's windows. Windows are evaluated in their own time zone.
+
+CREATE TABLE availability_windows (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    product_id UUID REFERENCES products(id) ON DELETE CASCADE,
+    category_id UUID REFERENCES categories(id) ON DELETE CASCADE,
+    -- ISO days of week (1 = Monday ... 7 = Sunday); NULL means every day
+    days_of_week SMALLINT[] CHECK (days_of_week <@ ARRAY[1, 2, 3, 4, 5, 6, 7]::SMALLINT[]),
+    -- Daily window; a start after the end spans midnight. NULL means all day
+    start_time TIME,
+    end_time TIME,
+    -- Inclusive date range for seasonal items
+    start_date DATE,
+    end_date DATE,
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((product_id IS NULL) <> (category_id IS NULL)),
+    CHECK ((start_time IS NULL) = (end_time IS NULL)),
+    CHECK (start_date IS NULL OR end_date IS NULL OR start_date <= end_date)
+);
+
+CREATE INDEX idx_availability_windows_product_id ON availability_windows(product_id);
+CREATE INDEX idx_availability_windows_category_id ON availability_windows(category_id);
+
+CREATE OR REPLACE FUNCTION availability_window_open(w availability_windows, at TIMESTAMPTZ)
+RETURNS BOOLEAN AS $$
+    SELECT (w.start_date IS NULL OR (at AT TIME ZONE w.timezone)::DATE >= w.start_date)
+       AND (w.end_date IS NULL OR (at AT TIME ZONE w.timezone)::DATE <= w.end_date)
+       AND (w.days_of_week IS NULL
+            OR EXTRACT(ISODOW FROM at AT TIME ZONE w.timezone)::SMALLINT = ANY(w.days_of_week))
+       AND (w.start_time IS NULL
+            OR CASE
+                WHEN w.start_time <= w.end_time THEN
+                    (at AT TIME ZONE w.timezone)::TIME >= w.start_time
+                    AND (at AT TIME ZONE w.timezone)::TIME < w.end_time
+                ELSE
+                    (at AT TIME ZONE w.timezone)::TIME >= w.start_time
+                    OR (at AT TIME ZONE w.timezone)::TIME < w.end_time
+               END)
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION is_available(p_product_id UUID, p_category_id UUID, at TIMESTAMPTZ)
+RETURNS BOOLEAN AS $$
+    SELECT (NOT EXISTS (SELECT 1 FROM availability_windows w WHERE w.product_id = p_product_id)
+            OR EXISTS (SELECT 1 FROM availability_windows w
+                       WHERE w.product_id = p_product_id AND availability_window_open(w, at)))
+       AND (p_category_id IS NULL
+            OR NOT EXISTS (SELECT 1 FROM availability_windows w WHERE w.category_id = p_category_id)
+            OR EXISTS (SELECT 1 FROM availability_windows w
+                       WHERE w.category_id = p_category_id AND availability_window_open(w, at)))
+$$ LANGUAGE sql STABLE;
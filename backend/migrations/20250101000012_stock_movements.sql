@@ -0,0 +1,41 @@
+-- Append-only ledger of every inventory change
+--
+-- Each row records a signed quantity change and the resulting balance of the
+-- inventory row, which is updated in the same transaction.
+
+CREATE TABLE stock_movements (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    inventory_id UUID NOT NULL REFERENCES inventory(id),
+    product_id UUID NOT NULL REFERENCES products(id),
+    variant_id UUID REFERENCES product_variants(id),
+    movement_type VARCHAR(20) NOT NULL
+        CHECK (movement_type IN ('sale', 'refund', 'restock', 'adjustment', 'transfer', 'waste')),
+    quantity_delta INTEGER NOT NULL CHECK (quantity_delta <> 0),
+    balance_after INTEGER NOT NULL,
+    reason TEXT,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    order_id UUID REFERENCES orders(id),
+    -- clock_timestamp() keeps movements from one transaction in order
+    created_at TIMESTAMPTZ NOT NULL DEFAULT clock_timestamp()
+);
+
+CREATE INDEX idx_stock_movements_inventory_id ON stock_movements(inventory_id, created_at DESC);
+CREATE INDEX idx_stock_movements_product_id ON stock_movements(product_id, created_at DESC);
+CREATE INDEX idx_stock_movements_order_id ON stock_movements(order_id) WHERE order_id IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION reject_stock_movement_changes()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'stock_movements is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER stock_movements_append_only BEFORE UPDATE OR DELETE ON stock_movements
+    FOR EACH ROW EXECUTE FUNCTION reject_stock_movement_changes();
+
+-- Opening balance so every row's history sums to its current quantity
+INSERT INTO stock_movements (inventory_id, product_id, variant_id, movement_type,
+                             quantity_delta, balance_after, reason)
+SELECT id, product_id, variant_id, 'adjustment', quantity, quantity, 'Opening balance'
+FROM inventory
+WHERE quantity <> 0;
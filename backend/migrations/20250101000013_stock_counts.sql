@@ -0,0 +1,45 @@
+-- Cycle counts and reason-coded stock adjustments
+
+ALTER TABLE stock_movements ADD COLUMN reason_code VARCHAR(20)
+    CHECK (reason_code IN ('shrinkage', 'spoilage', 'damage', 'theft', 'miscount', 'found', 'other'));
+
+CREATE TABLE stock_counts (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    status VARCHAR(20) NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'pending_approval', 'posted', 'cancelled')),
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    submitted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    approved_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    submitted_at TIMESTAMPTZ,
+    posted_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stock_counts_status ON stock_counts(status);
+
+CREATE TABLE stock_count_lines (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    stock_count_id UUID NOT NULL REFERENCES stock_counts(id) ON DELETE CASCADE,
+    inventory_id UUID NOT NULL REFERENCES inventory(id),
+    product_id UUID NOT NULL REFERENCES products(id),
+    variant_id UUID REFERENCES product_variants(id),
+    -- System quantity when the line was counted; the variance is applied as a
+    -- delta on posting, so sales made in between are not lost
+    expected_quantity INTEGER NOT NULL,
+    counted_quantity INTEGER NOT NULL CHECK (counted_quantity >= 0),
+    reason_code VARCHAR(20)
+        CHECK (reason_code IN ('shrinkage', 'spoilage', 'damage', 'theft', 'miscount', 'found', 'other')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(stock_count_id, inventory_id)
+);
+
+CREATE INDEX idx_stock_count_lines_stock_count_id ON stock_count_lines(stock_count_id);
+
+CREATE TRIGGER update_stock_counts_updated_at BEFORE UPDATE ON stock_counts
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_stock_count_lines_updated_at BEFORE UPDATE ON stock_count_lines
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,98 @@
+-- Stock locations, terminals bound to a location, and transfers between locations
+
+CREATE TABLE locations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL,
+    kind VARCHAR(20) NOT NULL DEFAULT 'store' CHECK (kind IN ('store', 'storeroom')),
+    -- Where stock goes when no location is given
+    is_default BOOLEAN NOT NULL DEFAULT false,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_locations_single_default ON locations(is_default) WHERE is_default;
+
+CREATE TABLE terminals (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL,
+    location_id UUID NOT NULL REFERENCES locations(id),
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_terminals_location_id ON terminals(location_id);
+
+INSERT INTO locations (id, name, kind, is_default) VALUES
+    ('40000000-0000-0000-0000-000000000001', 'Main Store', 'store', true);
+
+INSERT INTO terminals (id, name, location_id) VALUES
+    ('41000000-0000-0000-0000-000000000001', 'Front Counter', '40000000-0000-0000-0000-000000000001');
+
+-- Existing stock belongs to the default location
+ALTER TABLE inventory ADD COLUMN location_id UUID REFERENCES locations(id);
+UPDATE inventory SET location_id = '40000000-0000-0000-0000-000000000001';
+ALTER TABLE inventory ALTER COLUMN location_id SET NOT NULL;
+
+DROP INDEX idx_inventory_product_unique;
+DROP INDEX idx_inventory_variant_unique;
+CREATE UNIQUE INDEX idx_inventory_product_unique
+    ON inventory(product_id, location_id) WHERE variant_id IS NULL;
+CREATE UNIQUE INDEX idx_inventory_variant_unique
+    ON inventory(variant_id, location_id) WHERE variant_id IS NOT NULL;
+CREATE INDEX idx_inventory_location_id ON inventory(location_id);
+
+ALTER TABLE orders ADD COLUMN location_id UUID REFERENCES locations(id);
+ALTER TABLE orders ADD COLUMN terminal_id UUID REFERENCES terminals(id);
+UPDATE orders SET location_id = '40000000-0000-0000-0000-000000000001';
+
+ALTER TABLE stock_counts ADD COLUMN location_id UUID REFERENCES locations(id);
+UPDATE stock_counts SET location_id = '40000000-0000-0000-0000-000000000001';
+ALTER TABLE stock_counts ALTER COLUMN location_id SET NOT NULL;
+
+-- Where a purchase order is delivered
+ALTER TABLE purchase_orders ADD COLUMN location_id UUID REFERENCES locations(id);
+UPDATE purchase_orders SET location_id = '40000000-0000-0000-0000-000000000001';
+ALTER TABLE purchase_orders ALTER COLUMN location_id SET NOT NULL;
+
+CREATE SEQUENCE stock_transfer_number_seq;
+
+CREATE TABLE stock_transfers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    transfer_number VARCHAR(50) UNIQUE NOT NULL
+        DEFAULT 'TR-' || LPAD(nextval('stock_transfer_number_seq')::TEXT, 6, '0'),
+    from_location_id UUID NOT NULL REFERENCES locations(id),
+    to_location_id UUID NOT NULL REFERENCES locations(id),
+    status VARCHAR(20) NOT NULL DEFAULT 'draft'
+        CHECK (status IN ('draft', 'in_transit', 'received', 'cancelled')),
+    notes TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    sent_at TIMESTAMPTZ,
+    received_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (from_location_id <> to_location_id)
+);
+
+CREATE INDEX idx_stock_transfers_status ON stock_transfers(status);
+
+CREATE TABLE stock_transfer_lines (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    transfer_id UUID NOT NULL REFERENCES stock_transfers(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id),
+    variant_id UUID REFERENCES product_variants(id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stock_transfer_lines_transfer_id ON stock_transfer_lines(transfer_id);
+
+CREATE TRIGGER update_locations_updated_at BEFORE UPDATE ON locations
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_terminals_updated_at BEFORE UPDATE ON terminals
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_stock_transfers_updated_at BEFORE UPDATE ON stock_transfers
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,83 @@
+-- Ingredients and recipes
+--
+-- Ingredients are products flagged `is_ingredient`, so they share inventory,
+-- the stock ledger, counts, purchasing and locations with everything else.
+-- Their stock is held in whole units of `unit` (grams, millilitres or each).
+--
+-- A product with a recipe is not stocked itself: selling it depletes the
+-- ingredients instead. A variant with its own recipe lines uses those instead
+-- of the product's. Modifier option lines are added on top of the recipe and
+-- may be negative to swap an ingredient out (e.g. oat milk replacing milk).
+
+ALTER TABLE products
+    ADD COLUMN is_ingredient BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN unit VARCHAR(10) NOT NULL DEFAULT 'each' CHECK (unit IN ('each', 'g', 'ml'));
+
+CREATE INDEX idx_products_is_ingredient ON products(is_ingredient) WHERE is_ingredient = true;
+
+CREATE TABLE recipe_lines (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    product_id UUID REFERENCES products(id) ON DELETE CASCADE,
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    modifier_option_id UUID REFERENCES modifier_options(id) ON DELETE CASCADE,
+    ingredient_id UUID NOT NULL REFERENCES products(id),
+    -- In the ingredient's unit, per item sold
+    quantity INTEGER NOT NULL CHECK (quantity <> 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((product_id IS NULL) <> (modifier_option_id IS NULL)),
+    CHECK (variant_id IS NULL OR product_id IS NOT NULL),
+    CHECK (modifier_option_id IS NOT NULL OR quantity > 0),
+    CHECK (ingredient_id IS DISTINCT FROM product_id)
+);
+
+CREATE UNIQUE INDEX idx_recipe_lines_product
+    ON recipe_lines(product_id, ingredient_id) WHERE product_id IS NOT NULL AND variant_id IS NULL;
+CREATE UNIQUE INDEX idx_recipe_lines_variant
+    ON recipe_lines(variant_id, ingredient_id) WHERE variant_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_recipe_lines_modifier
+    ON recipe_lines(modifier_option_id, ingredient_id) WHERE modifier_option_id IS NOT NULL;
+CREATE INDEX idx_recipe_lines_ingredient_id ON recipe_lines(ingredient_id);
+
+-- Whether selling a product depletes ingredients rather than its own stock
+CREATE OR REPLACE FUNCTION is_recipe_tracked(p_product_id UUID)
+RETURNS BOOLEAN AS $$
+    SELECT EXISTS (SELECT 1 FROM recipe_lines WHERE product_id = p_product_id);
+$$ LANGUAGE sql STABLE;
+
+-- Sample ingredients for the Latte
+INSERT INTO products (id, name, description, price_cents, cost_cents, sku, is_ingredient, unit) VALUES
+    ('42000000-0000-0000-0000-000000000001', 'Espresso Beans', 'House blend, whole bean', 0, 3, 'ING-BEANS', true, 'g'),
+    ('42000000-0000-0000-0000-000000000002', 'Whole Milk', NULL, 0, 0, 'ING-MILK', true, 'ml'),
+    ('42000000-0000-0000-0000-000000000003', 'Oat Milk', NULL, 0, 1, 'ING-OAT', true, 'ml'),
+    ('42000000-0000-0000-0000-000000000004', '12oz Cup', 'Paper cup with lid', 0, 12, 'ING-CUP12', true, 'each');
+
+INSERT INTO inventory (product_id, location_id, quantity, reorder_level, reorder_quantity)
+SELECT p.id, l.id, s.quantity, s.reorder_level, s.reorder_quantity
+FROM (VALUES
+    ('42000000-0000-0000-0000-000000000001'::UUID, 5000, 1000, 5000),
+    ('42000000-0000-0000-0000-000000000002'::UUID, 20000, 4000, 20000),
+    ('42000000-0000-0000-0000-000000000003'::UUID, 8000, 2000, 8000),
+    ('42000000-0000-0000-0000-000000000004'::UUID, 500, 100, 500)
+) AS s(product_id, quantity, reorder_level, reorder_quantity)
+JOIN products p ON p.id = s.product_id
+JOIN locations l ON l.is_default = true;
+
+INSERT INTO stock_movements (inventory_id, product_id, variant_id, movement_type,
+                             quantity_delta, balance_after, reason)
+SELECT id, product_id, variant_id, 'adjustment', quantity, quantity, 'Opening balance'
+FROM inventory
+WHERE product_id IN (SELECT id FROM products WHERE is_ingredient = true);
+
+INSERT INTO recipe_lines (product_id, ingredient_id, quantity) VALUES
+    ('20000000-0000-0000-0000-000000000003', '42000000-0000-0000-0000-000000000001', 18),
+    ('20000000-0000-0000-0000-000000000003', '42000000-0000-0000-0000-000000000002', 240),
+    ('20000000-0000-0000-0000-000000000003', '42000000-0000-0000-0000-000000000004', 1);
+
+INSERT INTO recipe_lines (modifier_option_id, ingredient_id, quantity)
+SELECT o.id, r.ingredient_id::UUID, r.quantity
+FROM modifier_options o
+JOIN (VALUES
+    ('Oat Milk', '42000000-0000-0000-0000-000000000002', -240),
+    ('Oat Milk', '42000000-0000-0000-0000-000000000003', 240),
+    ('Extra Shot', '42000000-0000-0000-0000-000000000001', 18)
+) AS r(option_name, ingredient_id, quantity) ON r.option_name = o.name;
@@ -0,0 +1,43 @@
+-- Lots and expiry dates for perishable stock
+--
+-- A lot is a batch of one stock row received together. Decreases take from
+-- lots first-expiry-first-out; stock received without a lot (and stock held
+-- before lots existed) is treated as expiring last. Lot quantities therefore
+-- never add up to more than the stock row's quantity.
+
+CREATE TABLE stock_lots (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    inventory_id UUID NOT NULL REFERENCES inventory(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    lot_number VARCHAR(100),
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Last day the lot may be sold; it is written off as waste the day after
+    expires_on DATE,
+    initial_quantity INTEGER NOT NULL CHECK (initial_quantity > 0),
+    quantity INTEGER NOT NULL CHECK (quantity >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stock_lots_fefo
+    ON stock_lots(inventory_id, expires_on NULLS LAST, received_at) WHERE quantity > 0;
+CREATE INDEX idx_stock_lots_expires_on ON stock_lots(expires_on) WHERE quantity > 0;
+
+-- Which lots each movement drew from or added to, signed like the movement
+CREATE TABLE stock_lot_allocations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    movement_id UUID NOT NULL REFERENCES stock_movements(id),
+    lot_id UUID NOT NULL REFERENCES stock_lots(id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL CHECK (quantity <> 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stock_lot_allocations_movement_id ON stock_lot_allocations(movement_id);
+CREATE INDEX idx_stock_lot_allocations_lot_id ON stock_lot_allocations(lot_id);
+
+-- Lets received transfers recreate the lots that were sent
+ALTER TABLE stock_transfer_lines ADD COLUMN sent_movement_id UUID REFERENCES stock_movements(id);
+
+CREATE TRIGGER update_stock_lots_updated_at BEFORE UPDATE ON stock_lots
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,10 @@
+-- Supplier lead times for demand-based reorder suggestions
+--
+-- The lead time is the number of days between placing an order and the stock
+-- arriving; reorder points must cover forecast demand over that period.
+
+ALTER TABLE suppliers
+    ADD COLUMN lead_time_days INTEGER NOT NULL DEFAULT 7 CHECK (lead_time_days >= 0);
+
+-- Demand forecasting reads order lines by the day the order was placed
+CREATE INDEX idx_orders_location_created_at ON orders(location_id, created_at);
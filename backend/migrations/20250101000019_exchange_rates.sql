@@ -0,0 +1,50 @@
+-- Exchange rates and foreign currency tenders
+--
+-- A rate is the number of units of `currency` that one unit of
+-- `base_currency` buys (USD → CAD 1.36), the way rate feeds publish them.
+-- The latest rate already in effect is current; older rows are kept so every
+-- tender can point at the rate it used.
+--
+-- A tender records the cash handed over for an order, the rate snapshot it was
+-- converted at and what it was worth in the order's currency. Change is always
+-- given in the order's currency.
+
+CREATE TABLE exchange_rates (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    base_currency VARCHAR(3) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    rate NUMERIC(20, 10) NOT NULL CHECK (rate > 0),
+    source VARCHAR(20) NOT NULL DEFAULT 'manual' CHECK (source IN ('manual', 'import')),
+    effective_from TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (base_currency <> currency)
+);
+
+CREATE INDEX idx_exchange_rates_pair
+    ON exchange_rates(base_currency, currency, effective_from DESC);
+
+CREATE TABLE order_tenders (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    currency VARCHAR(3) NOT NULL,
+    -- Minor units of `currency` handed over
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    -- NULL when tendered in the order's own currency
+    exchange_rate_id UUID REFERENCES exchange_rates(id),
+    -- Rate snapshot; 1 when tendered in the order's own currency
+    rate NUMERIC(20, 10) NOT NULL CHECK (rate > 0),
+    -- `amount` converted to the order's currency
+    base_amount_cents BIGINT NOT NULL CHECK (base_amount_cents >= 0),
+    -- Given back in the order's currency
+    change_cents BIGINT NOT NULL DEFAULT 0 CHECK (change_cents >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_order_tenders_order_id ON order_tenders(order_id);
+CREATE INDEX idx_order_tenders_currency ON order_tenders(currency, created_at);
+
+-- Starting rates for the currencies tourists most often pay in
+INSERT INTO exchange_rates (base_currency, currency, rate) VALUES
+    ('USD', 'CAD', 1.36),
+    ('USD', 'EUR', 0.92);
@@ -0,0 +1,9 @@
+-- Cash rounding for jurisdictions without pennies
+--
+-- Cash totals are rounded to the smallest coin (CASH_ROUNDING_INCREMENT) when
+-- an order is paid in cash. The difference is kept as its own line so that
+-- subtotal + tax + cash rounding = total, and end-of-day reports can show how
+-- much rounding gave away or took in.
+
+ALTER TABLE orders
+    ADD COLUMN cash_rounding_cents BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,71 @@
+-- Typed order status, payment method, role and currency columns
+--
+-- These columns are read straight into the enums in `shared` (OrderStatus,
+-- PaymentMethod, Role, CurrencyCode), which refuse values they don't know.
+-- Existing rows are normalised first: case and stray whitespace are cleaned
+-- up and common spellings mapped. Anything that still doesn't match, unknown
+-- roles included, makes the CHECK constraints below fail, stopping the
+-- migration so the row can be fixed by hand rather than rewritten into
+-- something it wasn't.
+
+-- Roles
+UPDATE users SET role = LOWER(TRIM(role)) WHERE role <> LOWER(TRIM(role));
+
+ALTER TABLE users
+    ADD CONSTRAINT users_role_check CHECK (role IN ('admin', 'manager', 'cashier'));
+
+-- Order status and payment method
+UPDATE orders SET status = LOWER(TRIM(status)) WHERE status <> LOWER(TRIM(status));
+UPDATE orders SET status = 'cancelled' WHERE status = 'canceled';
+
+UPDATE orders
+SET payment_method = NULLIF(REPLACE(REPLACE(LOWER(TRIM(payment_method)), ' ', '_'), '-', '_'), '')
+WHERE payment_method IS NOT NULL;
+UPDATE orders
+SET payment_method = CASE payment_method
+        WHEN 'card' THEN 'credit_card'
+        WHEN 'credit' THEN 'credit_card'
+        WHEN 'debit' THEN 'debit_card'
+        WHEN 'gift' THEN 'gift_card'
+    END
+WHERE payment_method IN ('card', 'credit', 'debit', 'gift');
+
+ALTER TABLE orders
+    ADD CONSTRAINT orders_status_check
+        CHECK (status IN ('draft', 'pending', 'processing', 'completed', 'cancelled', 'refunded')),
+    ADD CONSTRAINT orders_payment_method_check
+        CHECK (payment_method IN ('cash', 'credit_card', 'debit_card', 'gift_card', 'crypto'));
+
+-- Currencies
+UPDATE products SET currency = UPPER(TRIM(currency)) WHERE currency <> UPPER(TRIM(currency));
+UPDATE product_variants SET currency = UPPER(TRIM(currency)) WHERE currency <> UPPER(TRIM(currency));
+UPDATE product_prices SET currency = UPPER(TRIM(currency)) WHERE currency <> UPPER(TRIM(currency));
+UPDATE orders SET currency = UPPER(TRIM(currency)) WHERE currency <> UPPER(TRIM(currency));
+UPDATE order_items SET currency = UPPER(TRIM(currency)) WHERE currency <> UPPER(TRIM(currency));
+UPDATE order_tenders SET currency = UPPER(TRIM(currency)) WHERE currency <> UPPER(TRIM(currency));
+UPDATE exchange_rates
+SET base_currency = UPPER(TRIM(base_currency)), currency = UPPER(TRIM(currency))
+WHERE base_currency <> UPPER(TRIM(base_currency)) OR currency <> UPPER(TRIM(currency));
+
+ALTER TABLE products
+    ADD CONSTRAINT products_currency_check CHECK (currency IN
+        ('USD', 'EUR', 'GBP', 'CAD', 'AUD', 'CHF', 'MXN', 'JPY', 'KRW', 'KWD', 'BHD'));
+ALTER TABLE product_variants
+    ADD CONSTRAINT product_variants_currency_check CHECK (currency IN
+        ('USD', 'EUR', 'GBP', 'CAD', 'AUD', 'CHF', 'MXN', 'JPY', 'KRW', 'KWD', 'BHD'));
+ALTER TABLE product_prices
+    ADD CONSTRAINT product_prices_currency_check CHECK (currency IN
+        ('USD', 'EUR', 'GBP', 'CAD', 'AUD', 'CHF', 'MXN', 'JPY', 'KRW', 'KWD', 'BHD'));
+ALTER TABLE orders
+    ADD CONSTRAINT orders_currency_check CHECK (currency IN
+        ('USD', 'EUR', 'GBP', 'CAD', 'AUD', 'CHF', 'MXN', 'JPY', 'KRW', 'KWD', 'BHD'));
+ALTER TABLE order_items
+    ADD CONSTRAINT order_items_currency_check CHECK (currency IN
+        ('USD', 'EUR', 'GBP', 'CAD', 'AUD', 'CHF', 'MXN', 'JPY', 'KRW', 'KWD', 'BHD'));
+ALTER TABLE order_tenders
+    ADD CONSTRAINT order_tenders_currency_check CHECK (currency IN
+        ('USD', 'EUR', 'GBP', 'CAD', 'AUD', 'CHF', 'MXN', 'JPY', 'KRW', 'KWD', 'BHD'));
+ALTER TABLE exchange_rates
+    ADD CONSTRAINT exchange_rates_currency_check CHECK (
+        base_currency IN ('USD', 'EUR', 'GBP', 'CAD', 'AUD', 'CHF', 'MXN', 'JPY', 'KRW', 'KWD', 'BHD')
+        AND currency IN ('USD', 'EUR', 'GBP', 'CAD', 'AUD', 'CHF', 'MXN', 'JPY', 'KRW', 'KWD', 'BHD'));
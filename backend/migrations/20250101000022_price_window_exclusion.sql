@@ -0,0 +1,16 @@
+-- Non-overlapping price windows
+--
+-- `insert_price_change` checks for overlapping windows before inserting, but
+-- two changes scheduled at the same time can both pass that check. The
+-- exclusion constraint makes the database the arbiter; the loser gets a
+-- validation error. `variant_id` is coalesced because NULLs never compare
+-- equal, which would let product-level windows overlap freely.
+
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+ALTER TABLE product_prices
+    ADD CONSTRAINT product_prices_no_overlap EXCLUDE USING gist (
+        product_id WITH =,
+        (COALESCE(variant_id, '00000000-0000-0000-0000-000000000000'::UUID)) WITH =,
+        tstzrange(effective_from, effective_to) WITH &&
+    );
@@ -0,0 +1,16 @@
+-- Ledger references pin their targets
+--
+-- `stock_movements` is append-only, so its foreign keys to products,
+-- variants and orders stay without ON DELETE actions: SET NULL or CASCADE
+-- would rewrite ledger rows and trip the append-only trigger. Anything that
+-- has moved stock is archived (`is_active = false`, or cancelled for orders)
+-- rather than deleted, and the comments below record that in the schema.
+
+COMMENT ON TABLE stock_movements IS
+    'Append-only inventory ledger. Referenced products, variants and orders are archived, never deleted.';
+COMMENT ON COLUMN stock_movements.product_id IS
+    'Pins the product: archive it instead of deleting once it has movements';
+COMMENT ON COLUMN stock_movements.variant_id IS
+    'Pins the variant: archive it instead of deleting once it has movements';
+COMMENT ON COLUMN stock_movements.order_id IS
+    'Pins the order: cancel it instead of deleting once it has movements';
@@ -0,0 +1,11 @@
+-- When an order was cancelled
+--
+-- The Z report counts cancellations in its window. `updated_at` moves on any
+-- later edit, so it can't say when the cancellation happened. Orders
+-- cancelled before this migration fall back to their last update.
+
+ALTER TABLE orders ADD COLUMN cancelled_at TIMESTAMPTZ;
+
+UPDATE orders SET cancelled_at = updated_at WHERE status = 'cancelled';
+
+CREATE INDEX idx_orders_cancelled_at ON orders(cancelled_at) WHERE status = 'cancelled';
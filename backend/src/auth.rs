@@ -1,12 +1,7 @@
 //! Authentication and authorization
 
 use anyhow::Result;
-use axum::{
-    async_trait,
-    extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
-    RequestPartsExt,
-};
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts, RequestPartsExt};
 use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
@@ -17,13 +12,15 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use shared::{AppError, Role};
+
 use crate::db::User;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // user_id
     pub username: String,
-    pub role: String,
+    pub role: Role,
     pub exp: i64,
     pub iat: i64,
 }
@@ -36,7 +33,7 @@ impl Claims {
         Self {
             sub: user.id.to_string(),
             username: user.username.clone(),
-            role: user.role.clone(),
+            role: user.role,
             exp: exp.timestamp(),
             iat: now.timestamp(),
         }
@@ -77,7 +74,27 @@ pub async fn verify_password(password: &str, hash: &str) -> Result<bool> {
 pub struct AuthContext {
     pub user_id: Uuid,
     pub username: String,
-    pub role: String,
+    pub role: Role,
+}
+
+impl AuthContext {
+    /// Managers and admins may access reporting and back-office endpoints
+    pub fn is_manager(&self) -> bool {
+        self.role.is_manager()
+    }
+
+    /// Reject users who are not managers or admins
+    pub fn require_manager(&self) -> Result<(), AppError> {
+        if self.is_manager() {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden("manager role required".to_string()))
+        }
+    }
+}
+
+fn invalid_token() -> AppError {
+    AppError::Unauthorized("invalid or expired token".to_string())
 }
 
 #[async_trait]
@@ -85,25 +102,25 @@ impl<S> FromRequestParts<S> for AuthContext
 where
     S: Send + Sync,
 {
-    type Rejection = StatusCode;
+    type Rejection = AppError;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
         // Extract the token from the authorization header
         let TypedHeader(Authorization(bearer)) = parts
             .extract::<TypedHeader<Authorization<Bearer>>>()
             .await
-            .map_err(|_| StatusCode::UNAUTHORIZED)?;
+            .map_err(|_| AppError::Unauthorized("missing bearer token".to_string()))?;
 
         // Get JWT secret from extensions (set in middleware)
         let secret = parts
             .extensions
             .get::<String>()
-            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+            .ok_or_else(|| AppError::Internal("JWT secret not configured".to_string()))?;
 
         // Verify the JWT
-        let claims = verify_jwt(bearer.token(), secret).map_err(|_| StatusCode::UNAUTHORIZED)?;
+        let claims = verify_jwt(bearer.token(), secret).map_err(|_| invalid_token())?;
 
-        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| invalid_token())?;
 
         Ok(AuthContext {
             user_id,
@@ -113,21 +130,6 @@ where
     }
 }
 
-// Login request/response types
-#[derive(Debug, Deserialize)]
-pub struct LoginRequest {
-    pub username: String,
-    pub password: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct LoginResponse {
-    pub token: String,
-    pub user_id: Uuid,
-    pub username: String,
-    pub role: String,
-}
-
 pub async fn authenticate_user(
     pool: &PgPool,
     username: &str,
@@ -0,0 +1,56 @@
+//! Command-line subcommands run instead of the API server
+//!
+//! ```text
+//! trezza-terminal-server import-catalog <file.csv> [--dry-run]
+//! trezza-terminal-server export-catalog [file.csv]
+//! ```
+
+use anyhow::{bail, Context, Result};
+use sqlx::PgPool;
+
+use crate::services::catalog_csv;
+
+/// Returns `Ok(false)` when `args` is not a known subcommand and the server should start
+pub async fn run(db: &PgPool, args: &[String]) -> Result<bool> {
+    match args.first().map(String::as_str) {
+        Some("import-catalog") => {
+            let path = args
+                .iter()
+                .skip(1)
+                .find(|a| !a.starts_with("--"))
+                .context("usage: import-catalog <file.csv> [--dry-run]")?;
+            let dry_run = args.iter().any(|a| a == "--dry-run");
+
+            let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path))?;
+            let report = catalog_csv::import_catalog(db, None, &data, dry_run).await?;
+
+            for error in &report.errors {
+                eprintln!("line {}: {}: {}", error.line, error.field, error.message);
+            }
+            println!(
+                "{}{} rows: {} created, {} updated, {} categories created",
+                if dry_run { "[dry run] " } else { "" },
+                report.rows,
+                report.created,
+                report.updated,
+                report.categories_created
+            );
+
+            if !report.is_ok() {
+                bail!("Import failed with {} error(s); no changes were made", report.errors.len());
+            }
+            Ok(true)
+        }
+        Some("export-catalog") => {
+            let csv = catalog_csv::export_catalog(db).await?;
+            match args.get(1) {
+                Some(path) => {
+                    std::fs::write(path, csv).with_context(|| format!("Failed to write {}", path))?
+                }
+                None => print!("{}", csv),
+            }
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
@@ -1,6 +1,7 @@
 //! Configuration management for TREZZA TERMINAL backend
 
 use anyhow::Result;
+use shared::{CashRounding, RoundingMode};
 use std::env;
 
 #[derive(Debug, Clone)]
@@ -10,6 +11,12 @@ pub struct Config {
     pub server_port: u16,
     pub jwt_secret: String,
     pub session_duration_hours: i64,
+    /// Stock adjustments larger than this many units need manager approval
+    pub adjustment_approval_threshold: i32,
+    /// How often expired lots are written off; 0 turns the scheduled run off
+    pub lot_expiry_interval_minutes: u64,
+    /// Rounding of cash totals, from `CASH_ROUNDING_INCREMENT` in minor units
+    pub cash_rounding: CashRounding,
 }
 
 impl Config {
@@ -30,6 +37,19 @@ impl Config {
                 .unwrap_or_else(|_| "8".to_string())
                 .parse()
                 .unwrap_or(8),
+            adjustment_approval_threshold: env::var("ADJUSTMENT_APPROVAL_THRESHOLD")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .unwrap_or(10),
+            lot_expiry_interval_minutes: env::var("LOT_EXPIRY_INTERVAL_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            cash_rounding: env::var("CASH_ROUNDING_INCREMENT")
+                .ok()
+                .and_then(|increment| increment.parse().ok())
+                .and_then(|increment| CashRounding::new(increment, RoundingMode::HalfUp).ok())
+                .unwrap_or_default(),
         })
     }
 
@@ -0,0 +1,117 @@
+//! Conversions from database rows to the API types in `shared::dto`
+
+use shared::{
+    CurrencyCode, Money, ModifierOptionResponse, OrderItemModifierResponse, OrderItemResponse,
+    OrderSummary, ProductResponse, TenderResponse, VariantResponse,
+};
+
+use super::models::{
+    ModifierOption, Order, OrderItem, OrderItemModifier, OrderTender, Product, ProductVariant,
+};
+
+/// A product on its own, without variants, as available
+impl From<Product> for ProductResponse {
+    fn from(product: Product) -> Self {
+        Self {
+            id: product.id,
+            name: product.name,
+            description: product.description,
+            price: Money::new(product.price_cents, product.currency),
+            category_id: product.category_id,
+            sku: product.sku,
+            barcode: product.barcode,
+            is_active: product.is_active,
+            variants: Vec::new(),
+            available: true,
+        }
+    }
+}
+
+impl From<ProductVariant> for VariantResponse {
+    fn from(variant: ProductVariant) -> Self {
+        Self {
+            id: variant.id,
+            product_id: variant.product_id,
+            name: variant.name,
+            sku: variant.sku,
+            barcode: variant.barcode,
+            price: Money::new(variant.price_cents, variant.currency),
+        }
+    }
+}
+
+impl From<ModifierOption> for ModifierOptionResponse {
+    fn from(option: ModifierOption) -> Self {
+        Self {
+            id: option.id,
+            group_id: option.group_id,
+            name: option.name,
+            price_delta_cents: option.price_delta_cents,
+        }
+    }
+}
+
+impl From<Order> for OrderSummary {
+    fn from(order: Order) -> Self {
+        let currency = order.currency;
+        Self {
+            id: order.id,
+            order_number: order.order_number,
+            customer_name: order.customer_name,
+            customer_email: order.customer_email,
+            subtotal: Money::new(order.subtotal_cents, currency),
+            tax: Money::new(order.tax_cents, currency),
+            cash_rounding: Money::new(order.cash_rounding_cents, currency),
+            total: Money::new(order.total_cents, currency),
+            status: order.status,
+            payment_method: order.payment_method,
+            location_id: order.location_id,
+            terminal_id: order.terminal_id,
+            created_at: order.created_at,
+            completed_at: order.completed_at,
+        }
+    }
+}
+
+impl From<OrderItem> for OrderItemResponse {
+    fn from(item: OrderItem) -> Self {
+        let currency = item.currency;
+        Self {
+            id: item.id,
+            product_id: item.product_id,
+            variant_id: item.variant_id,
+            product_name: item.product_name,
+            quantity: item.quantity,
+            unit_price: Money::new(item.unit_price_cents, currency),
+            total_price: Money::new(item.total_price_cents, currency),
+        }
+    }
+}
+
+/// Modifier rows don't carry a currency; their deltas are in the order's
+pub(crate) fn order_item_modifier(
+    modifier: OrderItemModifier,
+    currency: CurrencyCode,
+) -> OrderItemModifierResponse {
+    OrderItemModifierResponse {
+        id: modifier.id,
+        order_item_id: modifier.order_item_id,
+        modifier_option_id: modifier.modifier_option_id,
+        group_name: modifier.group_name,
+        option_name: modifier.option_name,
+        price_delta: Money::new(modifier.price_delta_cents, currency),
+    }
+}
+
+/// Tenders store the converted amount and change in the order's currency, `base`
+pub(crate) fn order_tender(tender: OrderTender, base: CurrencyCode) -> TenderResponse {
+    TenderResponse {
+        id: tender.id,
+        amount: Money::new(tender.amount, tender.currency),
+        rate: tender.rate,
+        exchange_rate_id: tender.exchange_rate_id,
+        base_amount: Money::new(tender.base_amount_cents, base),
+        change: Money::new(tender.change_cents, base),
+        created_at: tender.created_at,
+    }
+}
@@ -1,5 +1,6 @@
 //! Database models and queries
 
+pub mod dto;
 pub mod models;
 pub mod pool;
 
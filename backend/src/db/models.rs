@@ -1,7 +1,9 @@
 //! Database models
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use shared::{CurrencyCode, OrderStatus, PaymentMethod, Role};
 use sqlx::FromRow;
 use uuid::Uuid;
 
@@ -13,7 +15,7 @@ pub struct User {
     pub password_hash: String,
     pub first_name: String,
     pub last_name: String,
-    pub role: String,
+    pub role: Role,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -36,11 +38,16 @@ pub struct Product {
     pub name: String,
     pub description: Option<String>,
     pub price_cents: i64,
-    pub currency: String,
+    pub cost_cents: Option<i64>,
+    pub currency: CurrencyCode,
     pub category_id: Option<Uuid>,
     pub sku: Option<String>,
     pub barcode: Option<String>,
     pub is_active: bool,
+    pub sort_order: i32,
+    pub is_ingredient: bool,
+    /// Unit stock is counted in: `each`, `g` or `ml`
+    pub unit: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -49,6 +56,8 @@ pub struct Product {
 pub struct Inventory {
     pub id: Uuid,
     pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub location_id: Uuid,
     pub quantity: i32,
     pub reorder_level: i32,
     pub reorder_quantity: i32,
@@ -57,6 +66,171 @@ pub struct Inventory {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Location {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: String,
+    pub is_default: bool,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Terminal {
+    pub id: Uuid,
+    pub name: String,
+    pub location_id: Uuid,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockTransfer {
+    pub id: Uuid,
+    pub transfer_number: String,
+    pub from_location_id: Uuid,
+    pub to_location_id: Uuid,
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub received_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockTransferLine {
+    pub id: Uuid,
+    pub transfer_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub quantity: i32,
+    pub created_at: DateTime<Utc>,
+    pub sent_movement_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockMovement {
+    pub id: Uuid,
+    pub inventory_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub movement_type: String,
+    pub quantity_delta: i32,
+    pub balance_after: i32,
+    pub reason: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub reason_code: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockLot {
+    pub id: Uuid,
+    pub inventory_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub lot_number: Option<String>,
+    pub received_at: DateTime<Utc>,
+    pub expires_on: Option<NaiveDate>,
+    pub initial_quantity: i32,
+    pub quantity: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockCount {
+    pub id: Uuid,
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub submitted_by: Option<Uuid>,
+    pub approved_by: Option<Uuid>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub posted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub location_id: Uuid,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct StockCountLine {
+    pub id: Uuid,
+    pub stock_count_id: Uuid,
+    pub inventory_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub expected_quantity: i32,
+    pub counted_quantity: i32,
+    pub reason_code: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Supplier {
+    pub id: Uuid,
+    pub name: String,
+    pub contact_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub notes: Option<String>,
+    pub is_active: bool,
+    /// Days from ordering to the stock arriving
+    pub lead_time_days: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SupplierProduct {
+    pub id: Uuid,
+    pub supplier_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub supplier_sku: Option<String>,
+    pub cost_cents: i64,
+    pub pack_size: i32,
+    pub is_preferred: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PurchaseOrder {
+    pub id: Uuid,
+    pub po_number: String,
+    pub supplier_id: Uuid,
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub received_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub location_id: Uuid,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PurchaseOrderLine {
+    pub id: Uuid,
+    pub purchase_order_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub quantity_ordered: i32,
+    pub quantity_received: i32,
+    pub pack_size: i32,
+    pub unit_cost_cents: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct Order {
     pub id: Uuid,
@@ -67,14 +241,19 @@ pub struct Order {
     pub subtotal_cents: i64,
     pub tax_cents: i64,
     pub total_cents: i64,
-    pub currency: String,
-    pub status: String,
-    pub payment_method: Option<String>,
+    pub currency: CurrencyCode,
+    pub status: OrderStatus,
+    pub payment_method: Option<PaymentMethod>,
     pub payment_reference: Option<String>,
     pub notes: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub location_id: Option<Uuid>,
+    pub terminal_id: Option<Uuid>,
+    /// Added to the total by cash rounding; negative when rounded down
+    pub cash_rounding_cents: i64,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
@@ -82,11 +261,14 @@ pub struct OrderItem {
     pub id: Uuid,
     pub order_id: Uuid,
     pub product_id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
     pub product_name: String,
     pub quantity: i32,
     pub unit_price_cents: i64,
     pub total_price_cents: i64,
-    pub currency: String,
+    pub unit_cost_cents: Option<i64>,
+    pub total_cost_cents: Option<i64>,
+    pub currency: CurrencyCode,
     pub created_at: DateTime<Utc>,
 }
 
@@ -98,3 +280,120 @@ pub struct Session {
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
 }
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ModifierGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub is_required: bool,
+    pub min_selections: i32,
+    pub max_selections: i32,
+    pub sort_order: i32,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ModifierOption {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub name: String,
+    pub price_delta_cents: i64,
+    pub sort_order: i32,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OrderItemModifier {
+    pub id: Uuid,
+    pub order_item_id: Uuid,
+    pub modifier_option_id: Option<Uuid>,
+    pub group_name: String,
+    pub option_name: String,
+    pub price_delta_cents: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ProductVariant {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub name: String,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
+    pub price_cents: i64,
+    pub cost_cents: Option<i64>,
+    pub currency: CurrencyCode,
+    pub sort_order: i32,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AvailabilityWindow {
+    pub id: Uuid,
+    pub product_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub days_of_week: Option<Vec<i16>>,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub timezone: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ProductPrice {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub price_cents: i64,
+    pub currency: CurrencyCode,
+    pub effective_from: DateTime<Utc>,
+    pub effective_to: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RecipeLine {
+    pub id: Uuid,
+    pub product_id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
+    pub modifier_option_id: Option<Uuid>,
+    pub ingredient_id: Uuid,
+    pub quantity: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    pub id: Uuid,
+    pub base_currency: CurrencyCode,
+    pub currency: CurrencyCode,
+    /// Units of `currency` one unit of `base_currency` buys
+    pub rate: Decimal,
+    pub source: String,
+    pub effective_from: DateTime<Utc>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct OrderTender {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub currency: CurrencyCode,
+    /// Minor units of `currency` handed over
+    pub amount: i64,
+    pub exchange_rate_id: Option<Uuid>,
+    pub rate: Decimal,
+    pub base_amount_cents: i64,
+    pub change_cents: i64,
+    pub created_at: DateTime<Utc>,
+}
@@ -11,12 +11,14 @@ use axum::{
 use serde_json::{json, Value};
 use shared::{APP_NAME, APP_VERSION};
 use sqlx::PgPool;
+use std::time::Duration;
 use tower::ServiceBuilder;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing::{info, Level};
+use tracing::{info, warn, Level};
 use tracing_subscriber;
 
 mod auth;
+mod cli;
 mod config;
 mod db;
 mod routes;
@@ -24,7 +26,11 @@ mod services;
 
 use config::Config;
 use db::pool::{create_pool, run_migrations};
-use routes::{auth_routes, inventory_routes, order_routes, product_routes};
+use routes::{
+    auth_routes, category_routes, exchange_rate_routes, inventory_routes, location_routes,
+    order_routes, product_routes, purchase_order_routes, recipe_routes, report_routes,
+    stock_count_routes, supplier_routes, terminal_routes, transfer_routes,
+};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -58,6 +64,19 @@ async fn main() {
         .expect("Failed to run migrations");
     info!("Database migrations completed");
 
+    // Run a CLI subcommand instead of serving, if one was given
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::run(&db, &args).await {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+
+    spawn_lot_expiry(db.clone(), config.lot_expiry_interval_minutes);
+
     // Build the app state
     let state = AppState { db, config: config.clone() };
 
@@ -83,8 +102,19 @@ fn create_app(state: AppState) -> Router {
         .route("/api/health", get(health_check))
         .nest("/api/auth", auth_routes())
         .nest("/api/products", product_routes())
+        .nest("/api/categories", category_routes())
         .nest("/api/orders", order_routes())
         .nest("/api/inventory", inventory_routes())
+        .nest("/api/locations", location_routes())
+        .nest("/api/terminals", terminal_routes())
+        .nest("/api/transfers", transfer_routes())
+        .nest("/api/recipes", recipe_routes())
+        .nest("/api/stock-counts", stock_count_routes())
+        .nest("/api/suppliers", supplier_routes())
+        .nest("/api/purchase-orders", purchase_order_routes())
+        .nest("/api/exchange-rates", exchange_rate_routes())
+        .nest("/api/reports", report_routes())
+        .layer(axum::middleware::map_response(routes::log_server_errors))
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
@@ -93,6 +123,29 @@ fn create_app(state: AppState) -> Router {
         .with_state(state)
 }
 
+/// Write off expired lots on a fixed interval in the background
+fn spawn_lot_expiry(db: PgPool, interval_minutes: u64) {
+    if interval_minutes == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_minutes * 60));
+        loop {
+            interval.tick().await;
+            match services::lots::expire_lots(&db, None).await {
+                Ok(run) if !run.movements.is_empty() => info!(
+                    "Wrote off {} expired unit(s) across {} stock row(s)",
+                    run.units_written_off,
+                    run.movements.len()
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Expiring lots failed: {}", e),
+            }
+        }
+    });
+}
+
 /// Health check endpoint
 async fn health_check() -> Json<Value> {
     Json(json!({
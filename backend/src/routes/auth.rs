@@ -1,15 +1,10 @@
 //! Authentication routes
 
-use axum::{
-    extract::State,
-    http::StatusCode,
-    response::Json,
-    routing::post,
-    Router,
-};
-use serde_json::{json, Value};
+use axum::{extract::State, response::Json, routing::post, Router};
+use shared::{AppError, LoginRequest, LoginResponse};
 
-use crate::auth::{authenticate_user, create_jwt, LoginRequest, LoginResponse};
+use crate::auth::{authenticate_user, create_jwt};
+use crate::routes::internal_error;
 use crate::AppState;
 
 pub fn auth_routes() -> Router<AppState> {
@@ -19,14 +14,18 @@ pub fn auth_routes() -> Router<AppState> {
 async fn login(
     State(state): State<AppState>,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, StatusCode> {
+) -> Result<Json<LoginResponse>, AppError> {
     let user = authenticate_user(&state.db, &payload.username, &payload.password)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+        .map_err(internal_error)?
+        .ok_or_else(|| AppError::Unauthorized("invalid username or password".to_string()))?;
 
-    let token = create_jwt(&user, &state.config.jwt_secret, state.config.session_duration_hours)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let token = create_jwt(
+        &user,
+        &state.config.jwt_secret,
+        state.config.session_duration_hours,
+    )
+    .map_err(internal_error)?;
 
     Ok(Json(LoginResponse {
         token,
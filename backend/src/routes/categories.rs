@@ -0,0 +1,124 @@
+//! Category routes
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use serde_json::{json, Value};
+use shared::AppError;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::routes::internal_error;
+use crate::services::availability::{self, AvailabilityTarget, AvailabilityWindowRequest};
+use crate::services::products::{self, CategoryRequest, SortOrderUpdate};
+use crate::AppState;
+
+pub fn category_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_categories).post(create_category))
+        .route("/reorder", post(reorder_categories))
+        .route("/:id", put(update_category))
+        .route("/:id/archive", post(archive_category))
+        .route("/:id/restore", post(restore_category))
+        .route("/:id/availability", get(get_availability).put(set_availability))
+}
+
+async fn get_categories(State(state): State<AppState>) -> Result<Json<Value>, AppError> {
+    let categories = products::get_all_categories(&state.db)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(json!(categories)))
+}
+
+async fn create_category(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<CategoryRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let category = products::create_category(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(json!(category)))
+}
+
+async fn update_category(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CategoryRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let category = products::update_category(&state.db, auth.user_id, id, payload).await?;
+
+    Ok(Json(json!(category)))
+}
+
+async fn archive_category(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let category = products::set_category_active(&state.db, auth.user_id, id, false).await?;
+
+    Ok(Json(json!(category)))
+}
+
+async fn restore_category(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let category = products::set_category_active(&state.db, auth.user_id, id, true).await?;
+
+    Ok(Json(json!(category)))
+}
+
+async fn reorder_categories(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<Vec<SortOrderUpdate>>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    products::reorder_categories(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(json!({"success": true})))
+}
+
+async fn get_availability(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let windows = availability::get_windows(&state.db, AvailabilityTarget::Category(id)).await?;
+
+    Ok(Json(json!(windows)))
+}
+
+async fn set_availability(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<Vec<AvailabilityWindowRequest>>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let windows = availability::set_windows(
+        &state.db,
+        auth.user_id,
+        AvailabilityTarget::Category(id),
+        payload,
+    )
+    .await?;
+
+    Ok(Json(json!(windows)))
+}
@@ -0,0 +1,53 @@
+//! Exchange rate routes
+
+use axum::{
+    extract::State,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde_json::{json, Value};
+use shared::AppError;
+
+use crate::auth::AuthContext;
+use crate::services::exchange_rates::{self, ImportRatesRequest, SetRateRequest};
+use crate::AppState;
+
+pub fn exchange_rate_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_rates).post(set_rate))
+        .route("/import", post(import_rates))
+}
+
+async fn list_rates(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+) -> Result<Json<Value>, AppError> {
+    let rates = exchange_rates::list_rates(&state.db).await?;
+
+    Ok(Json(json!(rates)))
+}
+
+async fn set_rate(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<SetRateRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let rate = exchange_rates::set_rate(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(json!(rate)))
+}
+
+async fn import_rates(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<ImportRatesRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let rates = exchange_rates::import_rates(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(json!(rates)))
+}
@@ -1,59 +1,177 @@
 //! Inventory routes
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
     response::Json,
     routing::{get, post},
     Router,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
+use shared::validation::{self, FieldErrors};
+use shared::{AppError, Validate};
 use uuid::Uuid;
 
-use crate::services::inventory;
+use crate::auth::AuthContext;
+use crate::routes::{internal_error, ValidatedJson};
+use crate::services::inventory::{self, AdjustStockRequest, MovementQuery};
+use crate::services::locations::{self, StockByLocationQuery};
+use crate::services::lots::{self, LotDetails, LotQuery};
 use crate::AppState;
 
 pub fn inventory_routes() -> Router<AppState> {
     Router::new()
         .route("/low-stock", get(get_low_stock))
+        .route("/by-location", get(get_stock_by_location))
+        .route("/lots/expire", post(expire_lots))
         .route("/:product_id", get(get_inventory))
         .route("/:product_id/restock", post(restock))
+        .route("/:product_id/adjust", post(adjust))
+        .route("/:product_id/movements", get(get_movements))
+        .route("/:product_id/lots", get(get_lots))
+}
+
+#[derive(Debug, Deserialize)]
+struct VariantQuery {
+    variant_id: Option<Uuid>,
+    /// Defaults to the default location
+    location_id: Option<Uuid>,
 }
 
 async fn get_inventory(
     State(state): State<AppState>,
     Path(product_id): Path<Uuid>,
-) -> Result<Json<Value>, StatusCode> {
-    let inv = inventory::get_inventory(&state.db, product_id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    Query(params): Query<VariantQuery>,
+) -> Result<Json<Value>, AppError> {
+    let inv =
+        inventory::get_inventory(&state.db, product_id, params.variant_id, params.location_id)
+            .await
+            .map_err(internal_error)?
+            .ok_or_else(|| AppError::NotFound(format!("Inventory for product {}", product_id)))?;
 
     Ok(Json(json!(inv)))
 }
 
-async fn get_low_stock(State(state): State<AppState>) -> Result<Json<Value>, StatusCode> {
+async fn get_low_stock(State(state): State<AppState>) -> Result<Json<Value>, AppError> {
     let items = inventory::get_low_stock_items(&state.db)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(internal_error)?;
 
     Ok(Json(json!(items)))
 }
 
+async fn get_stock_by_location(
+    State(state): State<AppState>,
+    Query(query): Query<StockByLocationQuery>,
+) -> Result<Json<Value>, AppError> {
+    let stock = locations::stock_by_location(&state.db, &query).await?;
+
+    Ok(Json(json!(stock)))
+}
+
 #[derive(Debug, Deserialize)]
 struct RestockRequest {
+    variant_id: Option<Uuid>,
+    location_id: Option<Uuid>,
     quantity: i32,
+    #[serde(flatten)]
+    lot: LotDetails,
+}
+
+impl Validate for RestockRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut errors = FieldErrors::new();
+        errors.check("quantity", validation::restock_quantity(self.quantity));
+        errors.into_result()
+    }
 }
 
 async fn restock(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(product_id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<RestockRequest>,
+) -> Result<Json<Value>, AppError> {
+    let movement = inventory::restock_inventory(
+        &state.db,
+        product_id,
+        payload.variant_id,
+        payload.location_id,
+        payload.quantity,
+        payload.lot,
+        Some(auth.user_id),
+    )
+    .await?;
+
+    Ok(Json(json!({"success": true, "movement": movement})))
+}
+
+impl Validate for AdjustStockRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut errors = FieldErrors::new();
+        errors.check(
+            "quantity_delta",
+            validation::adjustment_quantity(self.quantity_delta),
+        );
+        if self.reason_code.is_none() {
+            errors.add("reason_code", "is required");
+        }
+        if let Some(note) = &self.note {
+            errors.check("note", validation::note(note));
+        }
+        errors.into_result()
+    }
+}
+
+async fn adjust(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(product_id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<AdjustStockRequest>,
+) -> Result<Json<Value>, AppError> {
+    let movement = inventory::adjust_stock(
+        &state.db,
+        auth.user_id,
+        auth.is_manager(),
+        state.config.adjustment_approval_threshold,
+        product_id,
+        payload,
+    )
+    .await?;
+
+    Ok(Json(json!(movement)))
+}
+
+async fn get_movements(
     State(state): State<AppState>,
     Path(product_id): Path<Uuid>,
-    Json(payload): Json<RestockRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    inventory::restock_inventory(&state.db, product_id, payload.quantity)
+    Query(query): Query<MovementQuery>,
+) -> Result<Json<Value>, AppError> {
+    let movements = inventory::get_movements(&state.db, product_id, &query)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(internal_error)?;
+
+    Ok(Json(json!(movements)))
+}
+
+async fn get_lots(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    Query(query): Query<LotQuery>,
+) -> Result<Json<Value>, AppError> {
+    let lots = lots::get_lots(&state.db, product_id, &query).await?;
+
+    Ok(Json(json!(lots)))
+}
+
+/// Write off expired lots now rather than waiting for the next scheduled run
+async fn expire_lots(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let run = lots::expire_lots(&state.db, Some(auth.user_id)).await?;
 
-    Ok(Json(json!({"success": true})))
+    Ok(Json(json!(run)))
 }
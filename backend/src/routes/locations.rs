@@ -0,0 +1,95 @@
+//! Location and terminal routes
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+use serde_json::{json, Value};
+use shared::AppError;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::services::locations::{self, LocationRequest, TerminalRequest};
+use crate::AppState;
+
+pub fn location_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_locations).post(create_location))
+        .route("/:id", put(update_location))
+}
+
+pub fn terminal_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_terminals).post(create_terminal))
+        .route("/:id", put(update_terminal))
+}
+
+async fn list_locations(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+) -> Result<Json<Value>, AppError> {
+    let locations = locations::list_locations(&state.db).await?;
+
+    Ok(Json(json!(locations)))
+}
+
+async fn create_location(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<LocationRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let location = locations::create_location(&state.db, payload).await?;
+
+    Ok(Json(json!(location)))
+}
+
+async fn update_location(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<LocationRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let location = locations::update_location(&state.db, id, payload).await?;
+
+    Ok(Json(json!(location)))
+}
+
+async fn list_terminals(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+) -> Result<Json<Value>, AppError> {
+    let terminals = locations::list_terminals(&state.db).await?;
+
+    Ok(Json(json!(terminals)))
+}
+
+async fn create_terminal(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<TerminalRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let terminal = locations::create_terminal(&state.db, payload).await?;
+
+    Ok(Json(json!(terminal)))
+}
+
+async fn update_terminal(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<TerminalRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let terminal = locations::update_terminal(&state.db, id, payload).await?;
+
+    Ok(Json(json!(terminal)))
+}
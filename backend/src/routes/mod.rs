@@ -1,11 +1,73 @@
 //! API routes
 
 pub mod auth;
+pub mod categories;
 pub mod products;
 pub mod orders;
 pub mod inventory;
+pub mod locations;
+pub mod reports;
+pub mod stock_counts;
+pub mod suppliers;
+pub mod purchase_orders;
+pub mod recipes;
+pub mod transfers;
+pub mod exchange_rates;
 
 pub use auth::auth_routes;
+pub use categories::category_routes;
 pub use products::product_routes;
 pub use orders::order_routes;
 pub use inventory::inventory_routes;
+pub use locations::{location_routes, terminal_routes};
+pub use reports::report_routes;
+pub use stock_counts::stock_count_routes;
+pub use suppliers::supplier_routes;
+pub use purchase_orders::purchase_order_routes;
+pub use recipes::recipe_routes;
+pub use transfers::transfer_routes;
+pub use exchange_rates::exchange_rate_routes;
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    response::Response,
+    Json,
+};
+use serde::de::DeserializeOwned;
+use shared::{AppError, Validate};
+
+/// Report a failure from a service that doesn't return `AppError`
+pub(crate) fn internal_error(error: impl std::fmt::Display) -> AppError {
+    AppError::Internal(error.to_string())
+}
+
+/// Log the cause of server errors, which their response bodies leave out
+pub(crate) async fn log_server_errors(response: Response) -> Response {
+    if response.status().is_server_error() {
+        if let Some(error) = response.extensions().get::<AppError>() {
+            tracing::error!(status = %response.status(), "{}", error);
+        }
+    }
+    response
+}
+
+/// A JSON body that has passed its `Validate` checks
+pub(crate) struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| AppError::Validation(rejection.body_text()))?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
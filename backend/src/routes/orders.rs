@@ -1,24 +1,29 @@
 //! Order routes
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
     response::Json,
     routing::{get, post},
     Router,
 };
-use serde::Deserialize;
 use serde_json::{json, Value};
+use shared::{
+    AppError, CompleteOrderRequest, CompletedOrderResponse, CreateOrderRequest, OrderResponse,
+    OrderSummary,
+};
 use uuid::Uuid;
 
 use crate::auth::AuthContext;
-use crate::services::orders::{self, CreateOrderRequest};
+use crate::routes::{internal_error, ValidatedJson};
+use crate::services::exchange_rates::{self, QuoteQuery};
+use crate::services::orders::{self, OrderFilter};
 use crate::AppState;
 
 pub fn order_routes() -> Router<AppState> {
     Router::new()
-        .route("/", post(create_order))
+        .route("/", get(list_orders).post(create_order))
         .route("/:id", get(get_order))
+        .route("/:id/quote", get(quote_order))
         .route("/:id/complete", post(complete_order))
         .route("/:id/cancel", post(cancel_order))
 }
@@ -26,52 +31,73 @@ pub fn order_routes() -> Router<AppState> {
 async fn create_order(
     State(state): State<AppState>,
     auth: AuthContext,
-    Json(payload): Json<CreateOrderRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    let order_with_items = orders::create_order(&state.db, auth.user_id, payload)
-        .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    ValidatedJson(payload): ValidatedJson<CreateOrderRequest>,
+) -> Result<Json<OrderResponse>, AppError> {
+    let order_with_items = orders::create_order(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(order_with_items.into()))
+}
+
+async fn list_orders(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(filter): Query<OrderFilter>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
 
-    Ok(Json(json!(order_with_items)))
+    let page = orders::list_orders(&state.db, &filter).await?;
+
+    Ok(Json(json!(page)))
 }
 
 async fn get_order(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<OrderResponse>, AppError> {
     let order = orders::get_order(&state.db, id)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .map_err(internal_error)?
+        .ok_or(AppError::OrderNotFound { id })?;
 
-    Ok(Json(json!(order)))
+    Ok(Json(order.into()))
 }
 
-#[derive(Debug, Deserialize)]
-struct CompleteOrderRequest {
-    payment_method: String,
-    payment_reference: Option<String>,
+async fn quote_order(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(query): Query<QuoteQuery>,
+) -> Result<Json<Value>, AppError> {
+    let quote =
+        exchange_rates::quote(&state.db, id, &query.currency, state.config.cash_rounding).await?;
+
+    Ok(Json(json!(quote)))
 }
 
 async fn complete_order(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
     Json(payload): Json<CompleteOrderRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    let order = orders::complete_order(&state.db, id, &payload.payment_method, payload.payment_reference)
-        .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+) -> Result<Json<CompletedOrderResponse>, AppError> {
+    let order = orders::complete_order(
+        &state.db,
+        id,
+        payload.payment_method,
+        payload.payment_reference,
+        payload.tender,
+        state.config.cash_rounding,
+    )
+    .await?;
 
-    Ok(Json(json!(order)))
+    Ok(Json(order.into()))
 }
 
 async fn cancel_order(
     State(state): State<AppState>,
+    auth: AuthContext,
     Path(id): Path<Uuid>,
-) -> Result<Json<Value>, StatusCode> {
-    let order = orders::cancel_order(&state.db, id)
-        .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+) -> Result<Json<OrderSummary>, AppError> {
+    let order = orders::cancel_order(&state.db, Some(auth.user_id), id).await?;
 
-    Ok(Json(json!(order)))
+    Ok(Json(order.into()))
 }
@@ -2,57 +2,115 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
-    routing::get,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
+    routing::{get, post},
     Router,
 };
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde_json::{json, Value};
+use shared::{AppError, BarcodeMatchResponse, ModifierGroupResponse, ProductResponse};
 use uuid::Uuid;
 
-use crate::services::products;
+use crate::auth::AuthContext;
+use crate::routes::internal_error;
+use crate::services::products::{CreateProductRequest, SortOrderUpdate, UpdateProductRequest};
+use crate::services::pricing::{self, SchedulePriceRequest};
+use crate::services::availability::{self, AvailabilityTarget, AvailabilityWindowRequest};
+use crate::services::{catalog_csv, modifiers, products};
 use crate::AppState;
 
 pub fn product_routes() -> Router<AppState> {
     Router::new()
-        .route("/", get(get_products))
-        .route("/:id", get(get_product))
+        .route("/", get(get_products).post(create_product))
+        .route("/reorder", post(reorder_products))
+        .route("/import", post(import_catalog))
+        .route("/export", get(export_catalog))
+        .route("/barcode/:code", get(lookup_barcode))
+        .route("/:id", get(get_product).put(update_product))
+        .route("/:id/archive", post(archive_product))
+        .route("/:id/restore", post(restore_product))
+        .route("/:id/modifiers", get(get_product_modifiers))
+        .route("/:id/prices", get(get_price_history).post(schedule_price))
+        .route("/:id/availability", get(get_availability).put(set_availability))
         .route("/search", get(search_products))
 }
 
 #[derive(Debug, Deserialize)]
 struct ProductQuery {
     category_id: Option<Uuid>,
+    /// Preview the catalog (prices and availability) as of another instant
+    at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    include_unavailable: bool,
 }
 
 async fn get_products(
     State(state): State<AppState>,
     Query(params): Query<ProductQuery>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Vec<ProductResponse>>, AppError> {
+    let at = params.at.unwrap_or_else(Utc::now);
     let products = if let Some(category_id) = params.category_id {
-        products::get_products_by_category(&state.db, category_id)
+        products::get_products_by_category(&state.db, category_id, at, params.include_unavailable)
             .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map_err(internal_error)?
     } else {
-        products::get_all_products(&state.db)
+        products::get_all_products(&state.db, at, params.include_unavailable)
             .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map_err(internal_error)?
     };
 
-    Ok(Json(json!(products)))
+    let products = products::with_variants(&state.db, products, at)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(products.into_iter().map(Into::into).collect()))
 }
 
 async fn get_product(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Value>, StatusCode> {
-    let product = products::get_product_by_id(&state.db, id)
+) -> Result<Json<ProductResponse>, AppError> {
+    let mut product = products::get_product_by_id(&state.db, id)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .map_err(internal_error)?
+        .ok_or(AppError::ProductNotFound { id })?;
 
-    Ok(Json(json!(product)))
+    let now = Utc::now();
+    pricing::apply_product_prices(&state.db, std::slice::from_mut(&mut product), now)
+        .await
+        .map_err(internal_error)?;
+
+    let product = products::with_variants(&state.db, vec![product], now)
+        .await
+        .map_err(internal_error)?
+        .pop()
+        .ok_or(AppError::ProductNotFound { id })?;
+
+    Ok(Json(product.into()))
+}
+
+async fn get_product_modifiers(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<ModifierGroupResponse>>, AppError> {
+    let mut conn = state.db.acquire().await.map_err(internal_error)?;
+
+    let groups = modifiers::get_modifier_groups_for_product(&mut conn, id)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(groups.into_iter().map(Into::into).collect()))
+}
+
+async fn lookup_barcode(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<Json<BarcodeMatchResponse>, AppError> {
+    let found = products::lookup_barcode(&state.db, &code).await?;
+
+    Ok(Json(found.into()))
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,10 +121,174 @@ struct SearchQuery {
 async fn search_products(
     State(state): State<AppState>,
     Query(params): Query<SearchQuery>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Vec<ProductResponse>>, AppError> {
     let products = products::search_products(&state.db, &params.q)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(internal_error)?;
+
+    let products = products::with_variants(&state.db, products, Utc::now())
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(products.into_iter().map(Into::into).collect()))
+}
+
+async fn create_product(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<CreateProductRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let product = products::create_product(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(json!(product)))
+}
+
+async fn update_product(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateProductRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let product = products::update_product(&state.db, auth.user_id, id, payload).await?;
+
+    Ok(Json(json!(product)))
+}
+
+async fn archive_product(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let product = products::set_product_active(&state.db, auth.user_id, id, false).await?;
+
+    Ok(Json(json!(product)))
+}
+
+async fn restore_product(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let product = products::set_product_active(&state.db, auth.user_id, id, true).await?;
+
+    Ok(Json(json!(product)))
+}
+
+async fn reorder_products(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<Vec<SortOrderUpdate>>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    products::reorder_products(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(json!({"success": true})))
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Import a catalog CSV; responds 422 with per-row errors when nothing was changed
+async fn import_catalog(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(params): Query<ImportQuery>,
+    body: String,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    auth.require_manager()?;
+
+    let report = catalog_csv::import_catalog(
+        &state.db,
+        Some(auth.user_id),
+        body.as_bytes(),
+        params.dry_run,
+    )
+    .await?;
+
+    let status = if report.is_ok() {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+
+    Ok((status, Json(json!(report))))
+}
+
+async fn export_catalog(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<impl IntoResponse, AppError> {
+    auth.require_manager()?;
+
+    let csv = catalog_csv::export_catalog(&state.db)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"catalog.csv\""),
+        ],
+        csv,
+    ))
+}
+
+async fn get_price_history(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let prices = pricing::get_price_history(&state.db, id)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(json!(prices)))
+}
+
+async fn schedule_price(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<SchedulePriceRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let price = pricing::schedule_price(&state.db, auth.user_id, id, payload).await?;
+
+    Ok(Json(json!(price)))
+}
+
+async fn get_availability(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let windows = availability::get_windows(&state.db, AvailabilityTarget::Product(id)).await?;
+
+    Ok(Json(json!(windows)))
+}
+
+async fn set_availability(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<Vec<AvailabilityWindowRequest>>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let windows =
+        availability::set_windows(&state.db, auth.user_id, AvailabilityTarget::Product(id), payload)
+            .await?;
 
-    Ok(Json(json!(products)))
+    Ok(Json(json!(windows)))
 }
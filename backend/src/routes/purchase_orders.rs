@@ -0,0 +1,138 @@
+//! Purchase order routes
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde_json::{json, Value};
+use shared::AppError;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::services::forecasting::{self, ForecastQuery};
+use crate::services::purchasing::{
+    self, CreatePurchaseOrderRequest, PurchaseOrderFilter, ReceiveRequest,
+};
+use crate::AppState;
+
+pub fn purchase_order_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_purchase_orders).post(create_purchase_order))
+        .route("/draft-from-low-stock", post(draft_from_low_stock))
+        .route("/suggestions", get(get_reorder_suggestions))
+        .route("/draft-from-forecast", post(draft_from_forecast))
+        .route("/:id", get(get_purchase_order))
+        .route("/:id/send", post(send_purchase_order))
+        .route("/:id/receive", post(receive_purchase_order))
+        .route("/:id/cancel", post(cancel_purchase_order))
+}
+
+async fn list_purchase_orders(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(filter): Query<PurchaseOrderFilter>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let orders = purchasing::list_purchase_orders(&state.db, &filter).await?;
+
+    Ok(Json(json!(orders)))
+}
+
+async fn create_purchase_order(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<CreatePurchaseOrderRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let order = purchasing::create_purchase_order(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(json!(order)))
+}
+
+async fn draft_from_low_stock(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let result = purchasing::draft_from_low_stock(&state.db, auth.user_id).await?;
+
+    Ok(Json(json!(result)))
+}
+
+async fn get_reorder_suggestions(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(query): Query<ForecastQuery>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let suggestions = forecasting::reorder_suggestions(&state.db, &query).await?;
+
+    Ok(Json(json!(suggestions)))
+}
+
+async fn draft_from_forecast(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(query): Query<ForecastQuery>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let result = forecasting::draft_from_forecast(&state.db, auth.user_id, &query).await?;
+
+    Ok(Json(json!(result)))
+}
+
+async fn get_purchase_order(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let order = purchasing::get_purchase_order(&state.db, id).await?;
+
+    Ok(Json(json!(order)))
+}
+
+async fn send_purchase_order(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let order = purchasing::send_purchase_order(&state.db, auth.user_id, id).await?;
+
+    Ok(Json(json!(order)))
+}
+
+async fn receive_purchase_order(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ReceiveRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let order = purchasing::receive_purchase_order(&state.db, auth.user_id, id, payload).await?;
+
+    Ok(Json(json!(order)))
+}
+
+async fn cancel_purchase_order(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let order = purchasing::cancel_purchase_order(&state.db, auth.user_id, id).await?;
+
+    Ok(Json(json!(order)))
+}
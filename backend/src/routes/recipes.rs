@@ -0,0 +1,113 @@
+//! Ingredient and recipe routes
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use shared::AppError;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::services::recipes::{self, RecipeLineRequest, RecipeTarget};
+use crate::AppState;
+
+pub fn recipe_routes() -> Router<AppState> {
+    Router::new()
+        .route("/ingredients", get(list_ingredients))
+        .route(
+            "/products/:id",
+            get(get_product_recipe).put(set_product_recipe),
+        )
+        .route(
+            "/modifier-options/:id",
+            get(get_modifier_recipe).put(set_modifier_recipe),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+struct VariantQuery {
+    variant_id: Option<Uuid>,
+}
+
+fn product_target(product_id: Uuid, params: &VariantQuery) -> RecipeTarget {
+    match params.variant_id {
+        Some(variant_id) => RecipeTarget::Variant {
+            product_id,
+            variant_id,
+        },
+        None => RecipeTarget::Product(product_id),
+    }
+}
+
+async fn list_ingredients(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+) -> Result<Json<Value>, AppError> {
+    let ingredients = recipes::list_ingredients(&state.db).await?;
+
+    Ok(Json(json!(ingredients)))
+}
+
+async fn get_product_recipe(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(params): Query<VariantQuery>,
+) -> Result<Json<Value>, AppError> {
+    let lines = recipes::get_recipe(&state.db, product_target(id, &params)).await?;
+
+    Ok(Json(json!(lines)))
+}
+
+async fn set_product_recipe(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Query(params): Query<VariantQuery>,
+    Json(payload): Json<Vec<RecipeLineRequest>>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let lines = recipes::set_recipe(
+        &state.db,
+        auth.user_id,
+        product_target(id, &params),
+        payload,
+    )
+    .await?;
+
+    Ok(Json(json!(lines)))
+}
+
+async fn get_modifier_recipe(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let lines = recipes::get_recipe(&state.db, RecipeTarget::ModifierOption(id)).await?;
+
+    Ok(Json(json!(lines)))
+}
+
+async fn set_modifier_recipe(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<Vec<RecipeLineRequest>>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let lines = recipes::set_recipe(
+        &state.db,
+        auth.user_id,
+        RecipeTarget::ModifierOption(id),
+        payload,
+    )
+    .await?;
+
+    Ok(Json(json!(lines)))
+}
@@ -0,0 +1,94 @@
+//! Reporting routes
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+use shared::AppError;
+
+use crate::auth::AuthContext;
+use crate::services::lots::{self, NearExpiryQuery};
+use crate::services::reports::{self, MarginQuery, TenderQuery, UsageQuery, ZReportQuery};
+use crate::AppState;
+
+pub fn report_routes() -> Router<AppState> {
+    Router::new()
+        .route("/margins", get(get_margins))
+        .route("/inventory-valuation", get(get_inventory_valuation))
+        .route("/ingredient-usage", get(get_ingredient_usage))
+        .route("/near-expiry", get(get_near_expiry))
+        .route("/foreign-tenders", get(get_foreign_tenders))
+        .route("/z-report", get(get_z_report))
+}
+
+async fn get_margins(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(query): Query<MarginQuery>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let report = reports::margin_report(&state.db, &query).await?;
+
+    Ok(Json(json!(report)))
+}
+
+async fn get_inventory_valuation(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let valuation = reports::inventory_valuation(&state.db).await?;
+
+    Ok(Json(json!(valuation)))
+}
+
+async fn get_ingredient_usage(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let report = reports::ingredient_usage(&state.db, &query).await?;
+
+    Ok(Json(json!(report)))
+}
+
+async fn get_near_expiry(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+    Query(query): Query<NearExpiryQuery>,
+) -> Result<Json<Value>, AppError> {
+    let lots = lots::near_expiry(&state.db, &query).await?;
+
+    Ok(Json(json!(lots)))
+}
+
+async fn get_foreign_tenders(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(query): Query<TenderQuery>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let rows = reports::foreign_tenders(&state.db, &query).await?;
+
+    Ok(Json(json!(rows)))
+}
+
+async fn get_z_report(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Query(query): Query<ZReportQuery>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let report = reports::z_report(&state.db, &query).await?;
+
+    Ok(Json(json!(report)))
+}
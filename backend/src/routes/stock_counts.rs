@@ -0,0 +1,114 @@
+//! Stock count routes
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use serde_json::{json, Value};
+use shared::AppError;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::services::stock_counts::{self, CountEntry, CreateStockCountRequest};
+use crate::AppState;
+
+pub fn stock_count_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_counts).post(create_count))
+        .route("/:id", get(review_count))
+        .route("/:id/lines", put(record_counts))
+        .route("/:id/submit", post(submit_count))
+        .route("/:id/approve", post(approve_count))
+        .route("/:id/cancel", post(cancel_count))
+}
+
+async fn list_counts(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+) -> Result<Json<Value>, AppError> {
+    let counts = stock_counts::list_counts(&state.db).await?;
+
+    Ok(Json(json!(counts)))
+}
+
+async fn create_count(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<CreateStockCountRequest>,
+) -> Result<Json<Value>, AppError> {
+    let count = stock_counts::create_count(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(json!(count)))
+}
+
+async fn review_count(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let review =
+        stock_counts::review_count(&state.db, id, state.config.adjustment_approval_threshold)
+            .await?;
+
+    Ok(Json(json!(review)))
+}
+
+async fn record_counts(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<Vec<CountEntry>>,
+) -> Result<Json<Value>, AppError> {
+    let lines =
+        stock_counts::record_counts(&state.db, auth.user_id, auth.is_manager(), id, payload)
+            .await?;
+
+    Ok(Json(json!(lines)))
+}
+
+async fn submit_count(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let review = stock_counts::submit_count(
+        &state.db,
+        auth.user_id,
+        auth.is_manager(),
+        state.config.adjustment_approval_threshold,
+        id,
+    )
+    .await?;
+
+    Ok(Json(json!(review)))
+}
+
+async fn approve_count(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let review = stock_counts::approve_count(
+        &state.db,
+        auth.user_id,
+        state.config.adjustment_approval_threshold,
+        id,
+    )
+    .await?;
+
+    Ok(Json(json!(review)))
+}
+
+async fn cancel_count(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let count = stock_counts::cancel_count(&state.db, auth.user_id, auth.is_manager(), id).await?;
+
+    Ok(Json(json!(count)))
+}
@@ -0,0 +1,83 @@
+//! Supplier routes
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+use serde_json::{json, Value};
+use shared::AppError;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::services::suppliers::{self, SupplierProductRequest, SupplierRequest};
+use crate::AppState;
+
+pub fn supplier_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_suppliers).post(create_supplier))
+        .route("/:id", put(update_supplier))
+        .route("/:id/products", get(get_supplier_products).put(upsert_supplier_product))
+}
+
+async fn list_suppliers(
+    State(state): State<AppState>,
+    auth: AuthContext,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let suppliers = suppliers::list_suppliers(&state.db).await?;
+
+    Ok(Json(json!(suppliers)))
+}
+
+async fn create_supplier(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<SupplierRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let supplier = suppliers::create_supplier(&state.db, payload).await?;
+
+    Ok(Json(json!(supplier)))
+}
+
+async fn update_supplier(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<SupplierRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let supplier = suppliers::update_supplier(&state.db, id, payload).await?;
+
+    Ok(Json(json!(supplier)))
+}
+
+async fn get_supplier_products(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let products = suppliers::get_supplier_products(&state.db, id).await?;
+
+    Ok(Json(json!(products)))
+}
+
+async fn upsert_supplier_product(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<SupplierProductRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let link = suppliers::upsert_supplier_product(&state.db, id, payload).await?;
+
+    Ok(Json(json!(link)))
+}
@@ -0,0 +1,91 @@
+//! Stock transfer routes
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde_json::{json, Value};
+use shared::AppError;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::services::transfers::{self, CreateTransferRequest, TransferFilter};
+use crate::AppState;
+
+pub fn transfer_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_transfers).post(create_transfer))
+        .route("/:id", get(get_transfer))
+        .route("/:id/send", post(send_transfer))
+        .route("/:id/receive", post(receive_transfer))
+        .route("/:id/cancel", post(cancel_transfer))
+}
+
+async fn list_transfers(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+    Query(filter): Query<TransferFilter>,
+) -> Result<Json<Value>, AppError> {
+    let list = transfers::list_transfers(&state.db, &filter).await?;
+
+    Ok(Json(json!(list)))
+}
+
+async fn get_transfer(
+    State(state): State<AppState>,
+    _auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let transfer = transfers::get_transfer(&state.db, id).await?;
+
+    Ok(Json(json!(transfer)))
+}
+
+async fn create_transfer(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Json(payload): Json<CreateTransferRequest>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let transfer = transfers::create_transfer(&state.db, auth.user_id, payload).await?;
+
+    Ok(Json(json!(transfer)))
+}
+
+async fn send_transfer(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let transfer = transfers::send_transfer(&state.db, auth.user_id, id).await?;
+
+    Ok(Json(json!(transfer)))
+}
+
+/// Receiving is open to any staff member at the destination
+async fn receive_transfer(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let transfer = transfers::receive_transfer(&state.db, auth.user_id, id).await?;
+
+    Ok(Json(json!(transfer)))
+}
+
+async fn cancel_transfer(
+    State(state): State<AppState>,
+    auth: AuthContext,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    auth.require_manager()?;
+
+    let transfer = transfers::cancel_transfer(&state.db, auth.user_id, id).await?;
+
+    Ok(Json(json!(transfer)))
+}
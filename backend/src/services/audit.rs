@@ -0,0 +1,32 @@
+//! Audit log service
+
+use serde_json::Value;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+/// Record a change in `audit_logs`; call inside the transaction making the change.
+/// `user_id` is `None` for changes made outside a login session (e.g. the CLI).
+pub async fn record(
+    conn: &mut PgConnection,
+    user_id: Option<Uuid>,
+    action: &str,
+    entity_type: &str,
+    entity_id: Uuid,
+    old_values: Option<Value>,
+    new_values: Option<Value>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO audit_logs (user_id, action, entity_type, entity_id, old_values, new_values)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(user_id)
+    .bind(action)
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(old_values)
+    .bind(new_values)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
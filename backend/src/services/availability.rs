@@ -0,0 +1,236 @@
+//! Availability windows for products and categories
+//!
+//! The matching rules live in the `is_available` SQL function (see the
+//! `availability_windows` migration) so listings can filter in the query.
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde_json::json;
+use shared::AppError;
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::db::{AvailabilityWindow, Product};
+use crate::services::audit;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct AvailabilityWindowRequest {
+    /// ISO days of week, 1 = Monday ... 7 = Sunday
+    pub days_of_week: Option<Vec<i16>>,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub timezone: Option<String>,
+}
+
+/// What a set of windows is attached to
+#[derive(Debug, Clone, Copy)]
+pub enum AvailabilityTarget {
+    Product(Uuid),
+    Category(Uuid),
+}
+
+impl AvailabilityTarget {
+    fn ids(self) -> (Option<Uuid>, Option<Uuid>) {
+        match self {
+            AvailabilityTarget::Product(id) => (Some(id), None),
+            AvailabilityTarget::Category(id) => (None, Some(id)),
+        }
+    }
+
+    fn entity_type(self) -> &'static str {
+        match self {
+            AvailabilityTarget::Product(_) => "product",
+            AvailabilityTarget::Category(_) => "category",
+        }
+    }
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+fn validate_window(window: &AvailabilityWindowRequest) -> Result<(), AppError> {
+    if let Some(days) = &window.days_of_week {
+        if days.is_empty() || days.iter().any(|d| !(1..=7).contains(d)) {
+            return Err(AppError::Validation(
+                "days_of_week must list ISO weekdays between 1 (Monday) and 7 (Sunday)".to_string(),
+            ));
+        }
+    }
+    match (window.start_time, window.end_time) {
+        (Some(start), Some(end)) if start == end => {
+            return Err(AppError::Validation(
+                "start_time and end_time must differ; omit both for all day".to_string(),
+            ));
+        }
+        (Some(_), None) | (None, Some(_)) => {
+            return Err(AppError::Validation(
+                "start_time and end_time must be given together".to_string(),
+            ));
+        }
+        _ => {}
+    }
+    if let (Some(start), Some(end)) = (window.start_date, window.end_date) {
+        if start > end {
+            return Err(AppError::Validation(
+                "start_date must not be after end_date".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Windows attached to a product or category
+pub async fn get_windows(
+    pool: &PgPool,
+    target: AvailabilityTarget,
+) -> Result<Vec<AvailabilityWindow>, AppError> {
+    let (product_id, category_id) = target.ids();
+    sqlx::query_as::<_, AvailabilityWindow>(
+        "SELECT * FROM availability_windows
+         WHERE product_id IS NOT DISTINCT FROM $1 AND category_id IS NOT DISTINCT FROM $2
+         ORDER BY created_at",
+    )
+    .bind(product_id)
+    .bind(category_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+/// Replace all windows of a product or category; an empty list makes it always available
+pub async fn set_windows(
+    pool: &PgPool,
+    user_id: Uuid,
+    target: AvailabilityTarget,
+    windows: Vec<AvailabilityWindowRequest>,
+) -> Result<Vec<AvailabilityWindow>, AppError> {
+    for window in &windows {
+        validate_window(window)?;
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let (product_id, category_id) = target.ids();
+    let exists: bool = match target {
+        AvailabilityTarget::Product(id) => {
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM products WHERE id = $1)").bind(id)
+        }
+        AvailabilityTarget::Category(id) => {
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM categories WHERE id = $1)").bind(id)
+        }
+    }
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    if !exists {
+        return Err(match target {
+            AvailabilityTarget::Product(id) => AppError::ProductNotFound { id },
+            AvailabilityTarget::Category(id) => AppError::CategoryNotFound { id },
+        });
+    }
+
+    let old = sqlx::query_as::<_, AvailabilityWindow>(
+        "DELETE FROM availability_windows
+         WHERE product_id IS NOT DISTINCT FROM $1 AND category_id IS NOT DISTINCT FROM $2
+         RETURNING *",
+    )
+    .bind(product_id)
+    .bind(category_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let mut created = Vec::with_capacity(windows.len());
+    for window in windows {
+        let timezone = window.timezone.unwrap_or_else(|| "UTC".to_string());
+        let known_zone: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+                .bind(&timezone)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(db_error)?;
+        if !known_zone {
+            return Err(AppError::Validation(format!("Unknown time zone: {}", timezone)));
+        }
+
+        let row = sqlx::query_as::<_, AvailabilityWindow>(
+            "INSERT INTO availability_windows (product_id, category_id, days_of_week,
+             start_time, end_time, start_date, end_date, timezone)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             RETURNING *",
+        )
+        .bind(product_id)
+        .bind(category_id)
+        .bind(&window.days_of_week)
+        .bind(window.start_time)
+        .bind(window.end_time)
+        .bind(window.start_date)
+        .bind(window.end_date)
+        .bind(&timezone)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        created.push(row);
+    }
+
+    let (AvailabilityTarget::Product(entity_id) | AvailabilityTarget::Category(entity_id)) = target;
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        &format!("{}.availability.update", target.entity_type()),
+        target.entity_type(),
+        entity_id,
+        Some(json!(old)),
+        Some(json!(created)),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(created)
+}
+
+/// Ids among `product_ids` that are outside their availability windows at `at`
+pub async fn unavailable_product_ids(
+    pool: &PgPool,
+    product_ids: &[Uuid],
+    at: DateTime<Utc>,
+) -> Result<HashSet<Uuid>, sqlx::Error> {
+    let ids: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM products
+         WHERE id = ANY($1) AND NOT is_available(id, category_id, $2)",
+    )
+    .bind(product_ids)
+    .bind(at)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids.into_iter().collect())
+}
+
+/// Reject a product that cannot be sold at `at`
+pub async fn ensure_available(
+    conn: &mut PgConnection,
+    product: &Product,
+    at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let available: bool = sqlx::query_scalar("SELECT is_available($1, $2, $3)")
+        .bind(product.id)
+        .bind(product.category_id)
+        .bind(at)
+        .fetch_one(conn)
+        .await
+        .map_err(db_error)?;
+
+    if !available {
+        return Err(AppError::ProductUnavailable {
+            id: product.id,
+            name: product.name.clone(),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,518 @@
+//! Catalog import and export as CSV
+//!
+//! Columns: `sku, name, description, category, price_cents, currency, barcode, initial_stock`.
+//! Stock is read from and imported into the default location.
+//! Imports upsert products keyed on `sku`, create missing categories by name and
+//! run in a single transaction, so a file with any bad row changes nothing.
+
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::json;
+use shared::{AppError, CurrencyCode};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::services::inventory::{self, MovementType, StockChange};
+use crate::services::{audit, locations, pricing};
+
+/// Header row written by exports and expected by imports
+pub const CATALOG_CSV_HEADERS: [&str; 8] = [
+    "sku",
+    "name",
+    "description",
+    "category",
+    "price_cents",
+    "currency",
+    "barcode",
+    "initial_stock",
+];
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+struct ExportRow {
+    #[serde(skip)]
+    id: Uuid,
+    sku: Option<String>,
+    name: String,
+    description: Option<String>,
+    category: Option<String>,
+    price_cents: i64,
+    currency: String,
+    barcode: Option<String>,
+    initial_stock: Option<i32>,
+}
+
+/// Row as read from the file, before validation
+#[derive(Debug, serde::Deserialize)]
+struct RawRow {
+    sku: Option<String>,
+    name: Option<String>,
+    description: Option<String>,
+    category: Option<String>,
+    price_cents: Option<String>,
+    currency: Option<String>,
+    barcode: Option<String>,
+    initial_stock: Option<String>,
+}
+
+#[derive(Debug)]
+struct CatalogRow {
+    line: usize,
+    sku: String,
+    name: String,
+    description: Option<String>,
+    category: Option<String>,
+    price_cents: i64,
+    currency: CurrencyCode,
+    barcode: Option<String>,
+    initial_stock: i32,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct RowError {
+    /// Line number in the file; the header is line 1
+    pub line: usize,
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub rows: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub categories_created: usize,
+    pub errors: Vec<RowError>,
+}
+
+impl ImportReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Export all products with their category and current stock
+pub async fn export_catalog(pool: &PgPool) -> Result<String> {
+    let mut rows = sqlx::query_as::<_, ExportRow>(
+        "SELECT p.id, p.sku, p.name, p.description, c.name AS category, p.price_cents,
+                p.currency, p.barcode, i.quantity AS initial_stock
+         FROM products p
+         LEFT JOIN categories c ON c.id = p.category_id
+         LEFT JOIN inventory i ON i.product_id = p.id AND i.variant_id IS NULL
+              AND i.location_id = (SELECT id FROM locations WHERE is_default = true)
+         ORDER BY c.sort_order NULLS LAST, p.sort_order, p.name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+    let prices = pricing::product_prices_at(pool, &ids, Utc::now()).await?;
+    for row in rows.iter_mut() {
+        if let Some((price_cents, currency)) = prices.get(&row.id) {
+            row.price_cents = *price_cents;
+            row.currency = currency.code().to_string();
+        }
+    }
+
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    writer.write_record(CATALOG_CSV_HEADERS)?;
+    for row in &rows {
+        writer.serialize(row)?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Validate and import a catalog file.
+///
+/// Validation errors are collected per row and returned in the report without
+/// touching the database. With `dry_run` the upserts run and are rolled back,
+/// so the counts reflect exactly what a real import would do.
+pub async fn import_catalog(
+    pool: &PgPool,
+    user_id: Option<Uuid>,
+    data: &[u8],
+    dry_run: bool,
+) -> Result<ImportReport, AppError> {
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    let rows = parse_rows(data, &mut report)?;
+    if !report.is_ok() {
+        return Ok(report);
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+
+    for row in &rows {
+        if let Err(message) = upsert_row(&mut tx, user_id, row, &mut report).await? {
+            report.errors.push(RowError {
+                line: row.line,
+                field: "sku".to_string(),
+                message,
+            });
+        }
+    }
+
+    if !report.is_ok() || dry_run {
+        tx.rollback().await.map_err(|e| AppError::Database(e.to_string()))?;
+        return Ok(report);
+    }
+
+    tx.commit().await.map_err(|e| AppError::Database(e.to_string()))?;
+
+    Ok(report)
+}
+
+fn parse_rows(data: &[u8], report: &mut ImportReport) -> Result<Vec<CatalogRow>, AppError> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(data);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::Validation(format!("Unreadable CSV header: {}", e)))?
+        .clone();
+    for required in ["sku", "name", "price_cents"] {
+        if !headers.iter().any(|h| h == required) {
+            return Err(AppError::Validation(format!("Missing required column: {}", required)));
+        }
+    }
+
+    let mut rows = Vec::new();
+    let mut seen_skus = HashSet::new();
+
+    // Quoted fields may span lines, so rows are located by where they start
+    let mut line = 1;
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => {
+                line = record.position().map_or(line + 1, |p| p.line() as usize);
+                record.deserialize::<RawRow>(Some(&headers))
+            }
+            Err(e) => {
+                line = e.position().map_or(line + 1, |p| p.line() as usize);
+                Err(e)
+            }
+        };
+        report.rows += 1;
+        let mut error = |field: &str, message: String| {
+            report.errors.push(RowError {
+                line,
+                field: field.to_string(),
+                message,
+            })
+        };
+
+        let raw = match record {
+            Ok(raw) => raw,
+            Err(e) => {
+                error("row", e.to_string());
+                continue;
+            }
+        };
+
+        let sku = raw.sku.filter(|s| !s.is_empty());
+        let name = raw.name.filter(|s| !s.is_empty());
+        let price_cents = raw.price_cents.as_deref().map(str::parse::<i64>);
+        let currency = raw
+            .currency
+            .filter(|s| !s.is_empty())
+            .map_or(Ok(CurrencyCode::USD), |c| c.parse::<CurrencyCode>());
+        let initial_stock = raw
+            .initial_stock
+            .filter(|s| !s.is_empty())
+            .map_or(Ok(0), |s| s.parse::<i32>());
+
+        let mut valid = true;
+        if sku.is_none() {
+            error("sku", "SKU is required".to_string());
+            valid = false;
+        }
+        if let Some(sku) = &sku {
+            if !seen_skus.insert(sku.clone()) {
+                error("sku", format!("Duplicate SKU in file: {}", sku));
+                valid = false;
+            }
+        }
+        if name.is_none() {
+            error("name", "Name is required".to_string());
+            valid = false;
+        }
+        match price_cents {
+            Some(Ok(price)) if price >= 0 => {}
+            Some(Ok(price)) => {
+                error("price_cents", format!("Price must not be negative, got {}", price));
+                valid = false;
+            }
+            Some(Err(_)) | None => {
+                error("price_cents", "Price must be a whole number of cents".to_string());
+                valid = false;
+            }
+        }
+        if let Err(e) = &currency {
+            error("currency", e.to_string());
+            valid = false;
+        }
+        match initial_stock {
+            Ok(stock) if stock >= 0 => {}
+            _ => {
+                error("initial_stock", "Initial stock must be a non-negative integer".to_string());
+                valid = false;
+            }
+        }
+
+        if valid {
+            rows.push(CatalogRow {
+                line,
+                sku: sku.unwrap_or_default(),
+                name: name.unwrap_or_default(),
+                description: raw.description.filter(|s| !s.is_empty()),
+                category: raw.category.filter(|s| !s.is_empty()),
+                price_cents: price_cents.and_then(|p| p.ok()).unwrap_or_default(),
+                currency: currency.unwrap_or(CurrencyCode::USD),
+                barcode: raw.barcode.filter(|s| !s.is_empty()),
+                initial_stock: initial_stock.unwrap_or_default(),
+            });
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Upsert one row. The outer error aborts the import; the inner one is a row error.
+async fn upsert_row(
+    conn: &mut PgConnection,
+    user_id: Option<Uuid>,
+    row: &CatalogRow,
+    report: &mut ImportReport,
+) -> Result<Result<(), String>, AppError> {
+    let db_error = |e: sqlx::Error| AppError::Database(e.to_string());
+
+    let variant_sku: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM product_variants WHERE sku = $1)")
+            .bind(&row.sku)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(db_error)?;
+    if variant_sku {
+        return Ok(Err(format!("SKU {} belongs to a product variant", row.sku)));
+    }
+
+    let category_id = match &row.category {
+        Some(name) => {
+            let existing: Option<Uuid> =
+                sqlx::query_scalar("SELECT id FROM categories WHERE LOWER(name) = LOWER($1) LIMIT 1")
+                    .bind(name)
+                    .fetch_optional(&mut *conn)
+                    .await
+                    .map_err(db_error)?;
+            match existing {
+                Some(id) => Some(id),
+                None => {
+                    report.categories_created += 1;
+                    Some(
+                        sqlx::query_scalar("INSERT INTO categories (name) VALUES ($1) RETURNING id")
+                            .bind(name)
+                            .fetch_one(&mut *conn)
+                            .await
+                            .map_err(db_error)?,
+                    )
+                }
+            }
+        }
+        None => None,
+    };
+
+    let existing: Option<(Uuid, i64)> =
+        sqlx::query_as("SELECT id, price_cents FROM products WHERE sku = $1")
+            .bind(&row.sku)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(db_error)?;
+    let previous_price = match existing {
+        Some((id, stored)) => Some(
+            pricing::price_at(&mut *conn, id, None, Utc::now())
+                .await
+                .map_err(db_error)?
+                .unwrap_or(stored),
+        ),
+        None => None,
+    };
+
+    // xmax is zero only for freshly inserted tuples
+    let (product_id, inserted): (Uuid, bool) = sqlx::query_as(
+        "INSERT INTO products (sku, name, description, category_id, price_cents, currency, barcode)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (sku) DO UPDATE
+         SET name = EXCLUDED.name, description = EXCLUDED.description,
+             category_id = EXCLUDED.category_id, price_cents = EXCLUDED.price_cents,
+             currency = EXCLUDED.currency, barcode = EXCLUDED.barcode
+         RETURNING id, (xmax = 0)",
+    )
+    .bind(&row.sku)
+    .bind(&row.name)
+    .bind(&row.description)
+    .bind(category_id)
+    .bind(row.price_cents)
+    .bind(row.currency.code())
+    .bind(&row.barcode)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    if inserted {
+        report.created += 1;
+        let location_id = locations::resolve_location(conn, None).await?;
+        sqlx::query("INSERT INTO inventory (product_id, location_id, quantity) VALUES ($1, $2, 0)")
+            .bind(product_id)
+            .bind(location_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+        if row.initial_stock > 0 {
+            inventory::record_movement(
+                conn,
+                StockChange {
+                    product_id,
+                    variant_id: None,
+                    location_id,
+                    movement_type: MovementType::Adjustment,
+                    quantity_delta: row.initial_stock,
+                    reason: Some("Initial stock (catalog import)".to_string()),
+                    reason_code: None,
+                    user_id,
+                    order_id: None,
+                },
+            )
+            .await?;
+        }
+    } else {
+        report.updated += 1;
+    }
+
+    if previous_price != Some(row.price_cents) {
+        pricing::insert_price_change(
+            conn,
+            user_id,
+            product_id,
+            None,
+            row.price_cents,
+            row.currency,
+            Utc::now(),
+            None,
+        )
+        .await?;
+    }
+
+    let action = if inserted { "product.import.create" } else { "product.import.update" };
+    audit::record(
+        conn,
+        user_id,
+        action,
+        "product",
+        product_id,
+        None,
+        Some(json!({
+            "sku": row.sku,
+            "name": row.name,
+            "category_id": category_id,
+            "price_cents": row.price_cents,
+            "currency": row.currency.code(),
+            "barcode": row.barcode,
+        })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    Ok(Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &str) -> (Result<Vec<CatalogRow>, AppError>, ImportReport) {
+        let mut report = ImportReport::default();
+        let rows = parse_rows(data.as_bytes(), &mut report);
+        (rows, report)
+    }
+
+    #[test]
+    fn test_parse_rows_reads_valid_rows() {
+        let (rows, report) = parse(
+            "sku,name,description,category,price_cents,currency,barcode,initial_stock\n\
+             LAT-1, Latte ,,Coffee,450,EUR,4006381333931,12\n\
+             CRO-1,Croissant,Butter,,325,,,\n",
+        );
+        let rows = rows.unwrap();
+
+        assert!(report.is_ok());
+        assert_eq!(report.rows, 2);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(rows[0].name, "Latte");
+        assert_eq!(rows[0].description, None);
+        assert_eq!(rows[0].category.as_deref(), Some("Coffee"));
+        assert_eq!(rows[0].currency, CurrencyCode::EUR);
+        assert_eq!(rows[0].initial_stock, 12);
+        assert_eq!(rows[1].currency, CurrencyCode::USD);
+        assert_eq!(rows[1].barcode, None);
+        assert_eq!(rows[1].initial_stock, 0);
+    }
+
+    #[test]
+    fn test_parse_rows_counts_lines_of_quoted_fields() {
+        let (rows, report) = parse(
+            "sku,name,description,price_cents\n\
+             LAT-1,Latte,\"Double shot,\nsteamed milk\",450\n\
+             CRO-1,Croissant,,-1\n",
+        );
+
+        assert_eq!(
+            rows.unwrap()[0].description.as_deref(),
+            Some("Double shot,\nsteamed milk")
+        );
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 4);
+        assert_eq!(report.errors[0].field, "price_cents");
+    }
+
+    #[test]
+    fn test_parse_rows_requires_columns() {
+        let (rows, _) = parse("sku,name\nLAT-1,Latte\n");
+        assert!(matches!(rows, Err(AppError::Validation(m)) if m.contains("price_cents")));
+    }
+
+    #[test]
+    fn test_parse_rows_reports_each_bad_field() {
+        let (rows, report) = parse(
+            "sku,name,price_cents,currency,initial_stock\n\
+             LAT-1,Latte,450,,\n\
+             LAT-1,Latte again,-5,XXX,-1\n\
+             ,,4.50,,\n",
+        );
+
+        assert_eq!(rows.unwrap().len(), 1);
+        let errors: Vec<(usize, &str)> = report
+            .errors
+            .iter()
+            .map(|e| (e.line, e.field.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (3, "sku"),
+                (3, "price_cents"),
+                (3, "currency"),
+                (3, "initial_stock"),
+                (4, "sku"),
+                (4, "name"),
+                (4, "price_cents"),
+            ]
+        );
+    }
+}
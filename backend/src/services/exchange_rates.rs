@@ -0,0 +1,280 @@
+//! Exchange rates and foreign currency tenders
+//!
+//! Rates are quoted as units of the foreign currency one unit of the base
+//! currency buys. Tendered cash converts into the base currency rounding down,
+//! so a tender is never credited with more than it is worth; amounts due in a
+//! foreign currency round up, so collecting them always covers the total.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::json;
+use shared::{AppError, CashRounding, CurrencyCode, Money, RoundingMode};
+use sqlx::{PgConnection, PgPool};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::db::{ExchangeRate, Order};
+use crate::services::audit;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SetRateRequest {
+    #[serde(default = "default_base_currency")]
+    pub base_currency: String,
+    pub currency: String,
+    /// Units of `currency` one unit of `base_currency` buys
+    pub rate: Decimal,
+    /// Defaults to now
+    pub effective_from: Option<DateTime<Utc>>,
+}
+
+fn default_base_currency() -> String {
+    CurrencyCode::USD.code().to_string()
+}
+
+/// Rates in the shape most feeds publish them: `{"base": "USD", "rates": {"CAD": 1.36}}`
+#[derive(Debug, serde::Deserialize)]
+pub struct ImportRatesRequest {
+    pub base: String,
+    pub rates: BTreeMap<String, Decimal>,
+    /// Defaults to now
+    pub effective_from: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct QuoteQuery {
+    pub currency: String,
+}
+
+/// What an order costs in a foreign currency at the current rate
+#[derive(Debug, serde::Serialize)]
+pub struct TenderQuote {
+    pub order_id: Uuid,
+    pub base_currency: String,
+    /// Cash total in the order's currency, after cash rounding
+    pub base_total_cents: i64,
+    pub currency: String,
+    pub rate: Decimal,
+    /// `None` when quoting in the order's own currency
+    pub exchange_rate_id: Option<Uuid>,
+    /// Minor units of `currency` that cover the total
+    pub amount_due: i64,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+fn validate_pair(base: &str, currency: &str, rate: Decimal) -> Result<(), AppError> {
+    let base: CurrencyCode = base.parse()?;
+    let currency: CurrencyCode = currency.parse()?;
+    if base == currency {
+        return Err(AppError::Validation(format!(
+            "Cannot set a rate from {} to itself",
+            base
+        )));
+    }
+    if rate <= Decimal::ZERO {
+        return Err(AppError::Validation(format!(
+            "Exchange rate for {} must be positive, got {}",
+            currency, rate
+        )));
+    }
+    Ok(())
+}
+
+/// The rate currently in effect for every currency pair
+pub async fn list_rates(pool: &PgPool) -> Result<Vec<ExchangeRate>, AppError> {
+    sqlx::query_as::<_, ExchangeRate>(
+        "SELECT DISTINCT ON (base_currency, currency) * FROM exchange_rates
+         WHERE effective_from <= NOW()
+         ORDER BY base_currency, currency, effective_from DESC, created_at DESC",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+async fn insert_rate(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    base_currency: &str,
+    currency: &str,
+    rate: Decimal,
+    source: &str,
+    effective_from: Option<DateTime<Utc>>,
+) -> Result<ExchangeRate, AppError> {
+    let row = sqlx::query_as::<_, ExchangeRate>(
+        "INSERT INTO exchange_rates (base_currency, currency, rate, source, effective_from, created_by)
+         VALUES ($1, $2, $3, $4, COALESCE($5, NOW()), $6)
+         RETURNING *",
+    )
+    .bind(base_currency)
+    .bind(currency)
+    .bind(rate)
+    .bind(source)
+    .bind(effective_from)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        conn,
+        Some(user_id),
+        &format!("exchange_rate.{}", source),
+        "exchange_rate",
+        row.id,
+        None,
+        Some(json!(row)),
+    )
+    .await
+    .map_err(db_error)?;
+
+    Ok(row)
+}
+
+/// Record a manually maintained rate
+pub async fn set_rate(
+    pool: &PgPool,
+    user_id: Uuid,
+    request: SetRateRequest,
+) -> Result<ExchangeRate, AppError> {
+    validate_pair(&request.base_currency, &request.currency, request.rate)?;
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let rate = insert_rate(
+        &mut tx,
+        user_id,
+        &request.base_currency,
+        &request.currency,
+        request.rate,
+        "manual",
+        request.effective_from,
+    )
+    .await?;
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(rate)
+}
+
+/// Record a batch of rates from a feed; nothing is saved if any rate is invalid
+pub async fn import_rates(
+    pool: &PgPool,
+    user_id: Uuid,
+    request: ImportRatesRequest,
+) -> Result<Vec<ExchangeRate>, AppError> {
+    if request.rates.is_empty() {
+        return Err(AppError::Validation("No rates to import".to_string()));
+    }
+    // Feeds usually list the base currency against itself
+    let rates: Vec<(&String, &Decimal)> = request
+        .rates
+        .iter()
+        .filter(|(currency, _)| **currency != request.base)
+        .collect();
+    for (currency, rate) in &rates {
+        validate_pair(&request.base, currency, **rate)?;
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let mut imported = Vec::with_capacity(rates.len());
+    for (currency, rate) in rates {
+        imported.push(
+            insert_rate(
+                &mut tx,
+                user_id,
+                &request.base,
+                currency,
+                *rate,
+                "import",
+                request.effective_from,
+            )
+            .await?,
+        );
+    }
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(imported)
+}
+
+/// The rate in effect now from `base` to `currency`
+pub(crate) async fn current_rate(
+    conn: &mut PgConnection,
+    base: CurrencyCode,
+    currency: CurrencyCode,
+) -> Result<ExchangeRate, AppError> {
+    sqlx::query_as::<_, ExchangeRate>(
+        "SELECT * FROM exchange_rates
+         WHERE base_currency = $1 AND currency = $2 AND effective_from <= NOW()
+         ORDER BY effective_from DESC, created_at DESC
+         LIMIT 1",
+    )
+    .bind(base.code())
+    .bind(currency.code())
+    .fetch_optional(conn)
+    .await
+    .map_err(db_error)?
+    .ok_or_else(|| {
+        AppError::Validation(format!("No exchange rate from {} to {}", base, currency))
+    })
+}
+
+/// Value of foreign cash in the base currency, rounded down
+pub(crate) fn to_base(
+    tendered: Money,
+    base: CurrencyCode,
+    rate: Decimal,
+) -> Result<Money, AppError> {
+    let value = tendered
+        .as_decimal()
+        .checked_div(rate)
+        .ok_or(AppError::AmountOverflow)?;
+    Money::from_decimal(value, base, RoundingMode::Down)
+}
+
+/// Foreign cash needed to cover a base currency amount, rounded up
+pub(crate) fn amount_due(
+    total: Money,
+    currency: CurrencyCode,
+    rate: Decimal,
+) -> Result<Money, AppError> {
+    total.convert(currency, rate, RoundingMode::Up)
+}
+
+/// Quote an order's cash total, after `cash_rounding`, in another currency at
+/// the current rate
+pub async fn quote(
+    pool: &PgPool,
+    order_id: Uuid,
+    currency: &str,
+    cash_rounding: CashRounding,
+) -> Result<TenderQuote, AppError> {
+    let currency: CurrencyCode = currency.parse()?;
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(db_error)?
+        .ok_or(AppError::OrderNotFound { id: order_id })?;
+    let base = order.currency;
+    let total = cash_rounding.round(Money::new(order.total_cents, base))?;
+
+    let (rate, exchange_rate_id) = if currency == base {
+        (Decimal::ONE, None)
+    } else {
+        let rate = current_rate(&mut conn, base, currency).await?;
+        (rate.rate, Some(rate.id))
+    };
+
+    Ok(TenderQuote {
+        order_id,
+        base_currency: base.code().to_string(),
+        base_total_cents: total.amount,
+        currency: currency.code().to_string(),
+        rate,
+        exchange_rate_id,
+        amount_due: amount_due(total, currency, rate)?.amount,
+    })
+}
@@ -0,0 +1,464 @@
+//! Demand forecasting and suggested reorder quantities
+//!
+//! Demand is read from `order_items` of orders placed over the last few whole
+//! weeks, so every weekday appears the same number of times. Sales of recipe
+//! products count as demand for their ingredients, using the current recipes.
+//!
+//! Each stock row gets an average sales rate per weekday. The reorder point is
+//! the forecast demand over the supplier's lead time plus safety stock for the
+//! day-to-day variation the weekday rates don't explain. Once the stock on hand
+//! and on order falls to the reorder point, the suggestion tops it up to cover
+//! the lead time and `cover_days` more.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use shared::AppError;
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::services::purchasing::{self, DraftLine, DraftResult, UnassignedItem, OPEN_STATUSES};
+
+/// Lead time assumed for items without an active supplier
+const DEFAULT_LEAD_TIME_DAYS: i32 = 7;
+
+/// Standard normal quantile for a 95% chance of not running out during the lead time
+const SAFETY_FACTOR: f64 = 1.65;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ForecastQuery {
+    pub location_id: Option<Uuid>,
+    /// Whole weeks of sales history to learn from
+    #[serde(default = "default_lookback_weeks")]
+    pub lookback_weeks: i32,
+    /// Days of demand each order should cover beyond the lead time
+    #[serde(default = "default_cover_days")]
+    pub cover_days: i32,
+}
+
+fn default_lookback_weeks() -> i32 {
+    8
+}
+
+fn default_cover_days() -> i32 {
+    7
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ReorderSuggestion {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub location_id: Uuid,
+    pub name: String,
+    pub location_name: String,
+    /// Preferred active supplier; `None` when nobody supplies the item
+    pub supplier_id: Option<Uuid>,
+    pub lead_time_days: i32,
+    pub pack_size: i32,
+    pub on_hand: i32,
+    /// Units on open purchase orders for this location not yet received
+    pub on_order: i64,
+    /// Average units sold per day over the lookback window
+    pub daily_velocity: f64,
+    /// Each weekday's rate relative to `daily_velocity`, Monday first
+    pub weekday_factors: [f64; 7],
+    /// Forecast units sold over the lead time, starting today
+    pub lead_time_demand: f64,
+    pub safety_stock: i32,
+    pub reorder_point: i32,
+    /// Stock level an order should bring the item back up to
+    pub order_up_to: i32,
+    /// Static settings on the stock row, for comparison
+    pub reorder_level: i32,
+    pub reorder_quantity: i32,
+    /// Units to order before rounding to packs; zero while above the reorder point
+    pub suggested_quantity: i32,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct StockRow {
+    product_id: Uuid,
+    variant_id: Option<Uuid>,
+    location_id: Uuid,
+    name: String,
+    location_name: String,
+    supplier_id: Option<Uuid>,
+    lead_time_days: Option<i32>,
+    pack_size: Option<i32>,
+    cost_cents: Option<i64>,
+    on_hand: i32,
+    on_order: i64,
+    reorder_level: i32,
+    reorder_quantity: i32,
+}
+
+type ItemKey = (Uuid, Option<Uuid>, Uuid);
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+fn validate(query: &ForecastQuery) -> Result<(), AppError> {
+    if !(1..=52).contains(&query.lookback_weeks) {
+        return Err(AppError::Validation(format!(
+            "lookback_weeks must be between 1 and 52, got {}",
+            query.lookback_weeks
+        )));
+    }
+    if !(0..=90).contains(&query.cover_days) {
+        return Err(AppError::Validation(format!(
+            "cover_days must be between 0 and 90, got {}",
+            query.cover_days
+        )));
+    }
+    Ok(())
+}
+
+/// Units sold per day, by stock row, over `[from, to)`
+async fn daily_demand(
+    conn: &mut PgConnection,
+    from: NaiveDate,
+    to: NaiveDate,
+    location_id: Option<Uuid>,
+) -> Result<HashMap<ItemKey, HashMap<NaiveDate, i64>>, AppError> {
+    let start = DateTime::<Utc>::from_naive_utc_and_offset(from.and_hms_opt(0, 0, 0).unwrap(), Utc);
+    let end = DateTime::<Utc>::from_naive_utc_and_offset(to.and_hms_opt(0, 0, 0).unwrap(), Utc);
+
+    let rows: Vec<(Uuid, Option<Uuid>, Uuid, NaiveDate, i64)> = sqlx::query_as(
+        "WITH sold AS (
+             SELECT oi.id, oi.product_id, oi.variant_id, oi.quantity,
+                    COALESCE(o.location_id, (SELECT id FROM locations WHERE is_default = true))
+                        AS location_id,
+                    (o.created_at AT TIME ZONE 'UTC')::DATE AS day
+             FROM order_items oi
+             JOIN orders o ON o.id = oi.order_id
+             WHERE o.status NOT IN ('draft', 'cancelled')
+             AND o.created_at >= $1 AND o.created_at < $2
+             AND oi.product_id IS NOT NULL
+         ),
+         demand AS (
+             SELECT product_id, variant_id, location_id, day, quantity::BIGINT AS quantity
+             FROM sold
+             WHERE NOT is_recipe_tracked(product_id)
+             UNION ALL
+             SELECT r.ingredient_id, NULL, s.location_id, s.day, s.quantity::BIGINT * r.quantity
+             FROM sold s
+             JOIN recipe_lines r ON r.product_id = s.product_id
+             AND CASE WHEN EXISTS (SELECT 1 FROM recipe_lines WHERE variant_id = s.variant_id)
+                      THEN r.variant_id = s.variant_id
+                      ELSE r.variant_id IS NULL END
+             UNION ALL
+             SELECT r.ingredient_id, NULL, s.location_id, s.day, s.quantity::BIGINT * r.quantity
+             FROM sold s
+             JOIN order_item_modifiers m ON m.order_item_id = s.id
+             JOIN recipe_lines r ON r.modifier_option_id = m.modifier_option_id
+         )
+         SELECT product_id, variant_id, location_id, day, GREATEST(SUM(quantity), 0)::BIGINT
+         FROM demand
+         WHERE ($3::UUID IS NULL OR location_id = $3)
+         GROUP BY product_id, variant_id, location_id, day",
+    )
+    .bind(start)
+    .bind(end)
+    .bind(location_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let mut demand: HashMap<ItemKey, HashMap<NaiveDate, i64>> = HashMap::new();
+    for (product_id, variant_id, location_id, day, quantity) in rows {
+        demand
+            .entry((product_id, variant_id, location_id))
+            .or_default()
+            .insert(day, quantity);
+    }
+    Ok(demand)
+}
+
+/// Stock rows of active, stocked items with their preferred supplier's terms
+async fn stock_rows(
+    conn: &mut PgConnection,
+    location_id: Option<Uuid>,
+) -> Result<Vec<StockRow>, AppError> {
+    sqlx::query_as::<_, StockRow>(
+        "SELECT DISTINCT ON (i.id)
+                i.product_id, i.variant_id, i.location_id,
+                CASE WHEN v.id IS NULL THEN p.name ELSE p.name || ' (' || v.name || ')' END AS name,
+                loc.name AS location_name,
+                sp.supplier_id, s.lead_time_days, sp.pack_size, sp.cost_cents,
+                i.quantity AS on_hand,
+                COALESCE((
+                    SELECT SUM(l.quantity_ordered - l.quantity_received)
+                    FROM purchase_order_lines l
+                    JOIN purchase_orders po ON po.id = l.purchase_order_id
+                    WHERE po.status = ANY($1) AND po.location_id = i.location_id
+                    AND l.product_id = i.product_id AND l.variant_id IS NOT DISTINCT FROM i.variant_id
+                ), 0)::BIGINT AS on_order,
+                i.reorder_level, i.reorder_quantity
+         FROM inventory i
+         JOIN products p ON p.id = i.product_id AND p.is_active = true
+         JOIN locations loc ON loc.id = i.location_id
+         LEFT JOIN product_variants v ON v.id = i.variant_id
+         LEFT JOIN (supplier_products sp JOIN suppliers s ON s.id = sp.supplier_id AND s.is_active = true)
+              ON sp.product_id = i.product_id AND sp.variant_id IS NOT DISTINCT FROM i.variant_id
+         WHERE NOT is_recipe_tracked(i.product_id)
+         AND ($2::UUID IS NULL OR i.location_id = $2)
+         ORDER BY i.id, sp.is_preferred DESC NULLS LAST, sp.cost_cents",
+    )
+    .bind(&OPEN_STATUSES[..])
+    .bind(location_id)
+    .fetch_all(conn)
+    .await
+    .map_err(db_error)
+}
+
+/// Forecast one stock row from its daily sales over the `weeks` before `today`
+fn forecast(
+    row: &StockRow,
+    sales: &HashMap<NaiveDate, i64>,
+    today: NaiveDate,
+    weeks: i32,
+    cover_days: i32,
+) -> ReorderSuggestion {
+    let days = weeks as i64 * 7;
+    let from = today - Duration::days(days);
+
+    let mut weekday_totals = [0i64; 7];
+    for (day, quantity) in sales {
+        weekday_totals[day.weekday().num_days_from_monday() as usize] += quantity;
+    }
+    let weekday_rates = weekday_totals.map(|total| total as f64 / weeks as f64);
+    let daily_velocity = weekday_totals.iter().sum::<i64>() as f64 / days as f64;
+    let weekday_factors = weekday_rates.map(|rate| {
+        if daily_velocity > 0.0 {
+            rate / daily_velocity
+        } else {
+            1.0
+        }
+    });
+    let rate_on = |day: NaiveDate| weekday_rates[day.weekday().num_days_from_monday() as usize];
+
+    // Spread of each day's sales around its weekday rate
+    let squared_error: f64 = (0..days)
+        .map(|offset| {
+            let day = from + Duration::days(offset);
+            let sold = sales.get(&day).copied().unwrap_or(0) as f64;
+            (sold - rate_on(day)).powi(2)
+        })
+        .sum();
+    let daily_deviation = (squared_error / (days - 1).max(1) as f64).sqrt();
+
+    let demand_over = |horizon: i32| -> f64 {
+        (0..horizon as i64)
+            .map(|offset| rate_on(today + Duration::days(offset)))
+            .sum()
+    };
+
+    let lead_time_days = row.lead_time_days.unwrap_or(DEFAULT_LEAD_TIME_DAYS);
+    let lead_time_demand = demand_over(lead_time_days);
+    let safety_stock =
+        (SAFETY_FACTOR * daily_deviation * (lead_time_days as f64).sqrt()).ceil() as i32;
+    let reorder_point = (lead_time_demand.ceil() as i32).saturating_add(safety_stock);
+    let order_up_to =
+        (demand_over(lead_time_days + cover_days).ceil() as i32).saturating_add(safety_stock);
+
+    let position = row.on_hand as i64 + row.on_order;
+    let suggested_quantity = if position <= reorder_point as i64 {
+        (order_up_to as i64 - position).clamp(0, i32::MAX as i64) as i32
+    } else {
+        0
+    };
+
+    ReorderSuggestion {
+        product_id: row.product_id,
+        variant_id: row.variant_id,
+        location_id: row.location_id,
+        name: row.name.clone(),
+        location_name: row.location_name.clone(),
+        supplier_id: row.supplier_id,
+        lead_time_days,
+        pack_size: row.pack_size.unwrap_or(1),
+        on_hand: row.on_hand,
+        on_order: row.on_order,
+        daily_velocity,
+        weekday_factors,
+        lead_time_demand,
+        safety_stock,
+        reorder_point,
+        order_up_to,
+        reorder_level: row.reorder_level,
+        reorder_quantity: row.reorder_quantity,
+        suggested_quantity,
+    }
+}
+
+async fn suggestions_with_terms(
+    conn: &mut PgConnection,
+    query: &ForecastQuery,
+) -> Result<Vec<(ReorderSuggestion, Option<i64>)>, AppError> {
+    validate(query)?;
+
+    let today = Utc::now().date_naive();
+    let from = today - Duration::days(query.lookback_weeks as i64 * 7);
+    let demand = daily_demand(&mut *conn, from, today, query.location_id).await?;
+    let rows = stock_rows(&mut *conn, query.location_id).await?;
+
+    let mut suggestions: Vec<(ReorderSuggestion, Option<i64>)> = rows
+        .iter()
+        .filter_map(|row| {
+            let sales = demand.get(&(row.product_id, row.variant_id, row.location_id))?;
+            let suggestion = forecast(row, sales, today, query.lookback_weeks, query.cover_days);
+            Some((suggestion, row.cost_cents))
+        })
+        .collect();
+    suggestions.sort_by(|(a, _), (b, _)| {
+        b.suggested_quantity
+            .cmp(&a.suggested_quantity)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.location_name.cmp(&b.location_name))
+    });
+
+    Ok(suggestions)
+}
+
+/// Reorder suggestions for every stocked item sold during the lookback window,
+/// items most in need of ordering first
+pub async fn reorder_suggestions(
+    pool: &PgPool,
+    query: &ForecastQuery,
+) -> Result<Vec<ReorderSuggestion>, AppError> {
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+    let suggestions = suggestions_with_terms(&mut conn, query).await?;
+    Ok(suggestions.into_iter().map(|(s, _)| s).collect())
+}
+
+/// Draft one order per preferred supplier and location for every item the
+/// forecast says to reorder, at the suggested quantity rounded up to whole packs.
+///
+/// Stock already on order counts towards the item, so drafting twice does not
+/// order twice. Items nobody supplies are reported with their forecast reorder
+/// point as `reorder_level`.
+pub async fn draft_from_forecast(
+    pool: &PgPool,
+    user_id: Uuid,
+    query: &ForecastQuery,
+) -> Result<DraftResult, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let mut lines = Vec::new();
+    let mut unassigned = Vec::new();
+    for (suggestion, cost_cents) in suggestions_with_terms(&mut tx, query).await? {
+        if suggestion.suggested_quantity <= 0 {
+            continue;
+        }
+        match (suggestion.supplier_id, cost_cents) {
+            (Some(supplier_id), Some(cost_cents)) => lines.push(DraftLine {
+                product_id: suggestion.product_id,
+                variant_id: suggestion.variant_id,
+                supplier_id,
+                location_id: suggestion.location_id,
+                cost_cents,
+                pack_size: suggestion.pack_size,
+                quantity: suggestion.suggested_quantity,
+            }),
+            _ => unassigned.push(UnassignedItem {
+                product_id: suggestion.product_id,
+                variant_id: suggestion.variant_id,
+                location_id: suggestion.location_id,
+                name: suggestion.name,
+                quantity: suggestion.on_hand,
+                reorder_level: suggestion.reorder_point,
+            }),
+        }
+    }
+
+    let orders = purchasing::insert_drafts_by_supplier(
+        &mut tx,
+        user_id,
+        "Drafted from demand forecast",
+        lines,
+    )
+    .await?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(DraftResult { orders, unassigned })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stock_row(on_hand: i32, on_order: i64) -> StockRow {
+        StockRow {
+            product_id: Uuid::nil(),
+            variant_id: None,
+            location_id: Uuid::nil(),
+            name: "Croissant".to_string(),
+            location_name: "Main Store".to_string(),
+            supplier_id: None,
+            lead_time_days: Some(7),
+            pack_size: None,
+            cost_cents: None,
+            on_hand,
+            on_order,
+            reorder_level: 10,
+            reorder_quantity: 50,
+        }
+    }
+
+    /// Four weeks of sales before Monday 3 March 2025, `per_weekday` units on each day
+    fn sales(per_weekday: [i64; 7]) -> (NaiveDate, HashMap<NaiveDate, i64>) {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let sales = (1..=28)
+            .map(|offset| {
+                let day = today - Duration::days(offset);
+                (
+                    day,
+                    per_weekday[day.weekday().num_days_from_monday() as usize],
+                )
+            })
+            .collect();
+        (today, sales)
+    }
+
+    #[test]
+    fn test_flat_demand_has_even_factors() {
+        let (today, sales) = sales([5; 7]);
+        let suggestion = forecast(&stock_row(10, 0), &sales, today, 4, 7);
+
+        assert_eq!(suggestion.daily_velocity, 5.0);
+        assert_eq!(suggestion.weekday_factors, [1.0; 7]);
+        assert_eq!(suggestion.safety_stock, 0);
+        assert_eq!(suggestion.lead_time_demand, 35.0);
+        assert_eq!(suggestion.reorder_point, 35);
+        assert_eq!(suggestion.order_up_to, 70);
+        assert_eq!(suggestion.suggested_quantity, 60);
+    }
+
+    #[test]
+    fn test_weekend_spike_shows_in_factors() {
+        let (today, sales) = sales([2, 2, 2, 2, 2, 9, 9]);
+        let suggestion = forecast(&stock_row(0, 0), &sales, today, 4, 7);
+
+        assert_eq!(suggestion.daily_velocity, 4.0);
+        assert_eq!(suggestion.weekday_factors[..5], [0.5; 5]);
+        assert_eq!(suggestion.weekday_factors[5..], [2.25; 2]);
+        // A week's lead time covers one weekend whichever day it starts
+        assert_eq!(suggestion.lead_time_demand, 28.0);
+    }
+
+    #[test]
+    fn test_stock_on_order_suppresses_suggestion() {
+        let (today, sales) = sales([5; 7]);
+
+        let short = forecast(&stock_row(10, 0), &sales, today, 4, 7);
+        assert!(short.suggested_quantity > 0);
+
+        let covered = forecast(&stock_row(10, 40), &sales, today, 4, 7);
+        assert_eq!(covered.on_order, 40);
+        assert_eq!(covered.suggested_quantity, 0);
+
+        let topped_up = forecast(&stock_row(10, 20), &sales, today, 4, 7);
+        assert_eq!(topped_up.suggested_quantity, short.suggested_quantity - 20);
+    }
+}
@@ -1,26 +1,170 @@
 //! Inventory management service
+//!
+//! Stock is held per product (or variant) per location. Every change to
+//! `inventory.quantity` goes through [`record_movement`], which updates the
+//! balance and appends a `stock_movements` row in the same transaction, so the
+//! ledger always sums to the stored quantity.
 
 use anyhow::Result;
-use chrono::Utc;
 use shared::AppError;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
-use crate::db::Inventory;
+use crate::db::{Inventory, StockMovement};
+use crate::services::lots::{self, LotDetails};
+use crate::services::locations;
 
-pub async fn get_inventory(pool: &PgPool, product_id: Uuid) -> Result<Option<Inventory>> {
+/// Default number of movements returned by [`get_movements`]
+pub const DEFAULT_MOVEMENT_LIMIT: i64 = 100;
+
+/// Maximum number of movements returned by [`get_movements`]
+pub const MAX_MOVEMENT_LIMIT: i64 = 500;
+
+/// Kind of stock change recorded in the ledger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MovementType {
+    Sale,
+    Refund,
+    Restock,
+    Adjustment,
+    Transfer,
+    Waste,
+}
+
+impl MovementType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MovementType::Sale => "sale",
+            MovementType::Refund => "refund",
+            MovementType::Restock => "restock",
+            MovementType::Adjustment => "adjustment",
+            MovementType::Transfer => "transfer",
+            MovementType::Waste => "waste",
+        }
+    }
+}
+
+/// Why stock was adjusted outside of sales and deliveries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasonCode {
+    Shrinkage,
+    Spoilage,
+    Damage,
+    Theft,
+    Miscount,
+    Found,
+    Other,
+}
+
+impl ReasonCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReasonCode::Shrinkage => "shrinkage",
+            ReasonCode::Spoilage => "spoilage",
+            ReasonCode::Damage => "damage",
+            ReasonCode::Theft => "theft",
+            ReasonCode::Miscount => "miscount",
+            ReasonCode::Found => "found",
+            ReasonCode::Other => "other",
+        }
+    }
+
+    /// Ledger type for a decrease with this reason; spoiled and damaged stock is waste
+    pub fn movement_type(self) -> MovementType {
+        match self {
+            ReasonCode::Spoilage | ReasonCode::Damage => MovementType::Waste,
+            _ => MovementType::Adjustment,
+        }
+    }
+}
+
+impl std::str::FromStr for ReasonCode {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shrinkage" => Ok(ReasonCode::Shrinkage),
+            "spoilage" => Ok(ReasonCode::Spoilage),
+            "damage" => Ok(ReasonCode::Damage),
+            "theft" => Ok(ReasonCode::Theft),
+            "miscount" => Ok(ReasonCode::Miscount),
+            "found" => Ok(ReasonCode::Found),
+            "other" => Ok(ReasonCode::Other),
+            other => Err(AppError::Validation(format!("Unknown reason code: {}", other))),
+        }
+    }
+}
+
+/// A signed change to one inventory row
+#[derive(Debug)]
+pub struct StockChange {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub location_id: Uuid,
+    pub movement_type: MovementType,
+    pub quantity_delta: i32,
+    pub reason: Option<String>,
+    pub reason_code: Option<ReasonCode>,
+    pub user_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct AdjustStockRequest {
+    pub variant_id: Option<Uuid>,
+    /// Defaults to the default location
+    pub location_id: Option<Uuid>,
+    /// Signed change, e.g. `-3` for three units written off
+    pub quantity_delta: i32,
+    /// Required; optional here so a missing code is reported against its field
+    pub reason_code: Option<ReasonCode>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct MovementQuery {
+    /// Only movements of this variant; all of the product's rows when omitted
+    pub variant_id: Option<Uuid>,
+    /// Only movements at this location; all locations when omitted
+    pub location_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+/// Stock row at a location, or at the default location when none is given
+pub async fn get_inventory(
+    pool: &PgPool,
+    product_id: Uuid,
+    variant_id: Option<Uuid>,
+    location_id: Option<Uuid>,
+) -> Result<Option<Inventory>> {
     let inventory = sqlx::query_as::<_, Inventory>(
-        "SELECT * FROM inventory WHERE product_id = $1",
+        "SELECT * FROM inventory
+         WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2
+         AND location_id = COALESCE($3, (SELECT id FROM locations WHERE is_default = true))",
     )
     .bind(product_id)
+    .bind(variant_id)
+    .bind(location_id)
     .fetch_optional(pool)
     .await?;
 
     Ok(inventory)
 }
 
-pub async fn check_availability(pool: &PgPool, product_id: Uuid, quantity: i32) -> Result<bool> {
-    let inventory = get_inventory(pool, product_id).await?;
+pub async fn check_availability(
+    pool: &PgPool,
+    product_id: Uuid,
+    variant_id: Option<Uuid>,
+    location_id: Uuid,
+    quantity: i32,
+) -> Result<bool> {
+    let inventory = get_inventory(pool, product_id, variant_id, Some(location_id)).await?;
 
     match inventory {
         Some(inv) => Ok(inv.quantity >= quantity),
@@ -28,70 +172,298 @@ pub async fn check_availability(pool: &PgPool, product_id: Uuid, quantity: i32)
     }
 }
 
-pub async fn reserve_inventory(
-    pool: &PgPool,
-    product_id: Uuid,
-    quantity: i32,
-) -> Result<(), AppError> {
-    let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+/// Apply a stock change and append it to the ledger.
+///
+/// Decreases that would take the balance below zero are rejected, and are
+/// drawn from the row's lots first-expiry-first-out. Refunds against an order
+/// go back into the lots its sale drew from. An increase at a location that
+/// has never held the item creates its stock row there.
+pub async fn record_movement(
+    conn: &mut PgConnection,
+    change: StockChange,
+) -> Result<StockMovement, AppError> {
+    if change.quantity_delta == 0 {
+        return Err(AppError::Validation(
+            "Stock movements must change the quantity".to_string(),
+        ));
+    }
 
-    let inventory = sqlx::query_as::<_, Inventory>(
-        "SELECT * FROM inventory WHERE product_id = $1 FOR UPDATE",
+    let existing = sqlx::query_as::<_, Inventory>(
+        "SELECT * FROM inventory
+         WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2 AND location_id = $3
+         FOR UPDATE",
     )
-    .bind(product_id)
-    .fetch_optional(&mut *tx)
+    .bind(change.product_id)
+    .bind(change.variant_id)
+    .bind(change.location_id)
+    .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| AppError::Database(e.to_string()))?;
+    .map_err(db_error)?;
 
-    match inventory {
-        Some(inv) => {
-            if inv.quantity < quantity {
-                return Err(AppError::InsufficientInventory {
-                    product_id,
-                    requested: quantity as u32,
-                    available: inv.quantity as u32,
-                });
-            }
-
-            sqlx::query("UPDATE inventory SET quantity = quantity - $1 WHERE product_id = $2")
-                .bind(quantity)
-                .bind(product_id)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| AppError::Database(e.to_string()))?;
-        }
+    let inventory = match existing {
+        Some(inventory) => inventory,
+        None if change.quantity_delta > 0 => create_stock_row(conn, &change).await?,
         None => {
-            return Err(AppError::ProductNotFound { id: product_id });
+            return Err(AppError::InsufficientInventory {
+                product_id: change.product_id,
+                requested: change.quantity_delta.unsigned_abs(),
+                available: 0,
+            })
         }
+    };
+
+    let balance = inventory
+        .quantity
+        .checked_add(change.quantity_delta)
+        .ok_or_else(|| AppError::Validation("Stock quantity out of range".to_string()))?;
+    if change.quantity_delta < 0 && balance < 0 {
+        return Err(AppError::InsufficientInventory {
+            product_id: change.product_id,
+            requested: change.quantity_delta.unsigned_abs(),
+            available: inventory.quantity.max(0) as u32,
+        });
+    }
+
+    sqlx::query(
+        "UPDATE inventory
+         SET quantity = $1,
+             last_restocked_at = CASE WHEN $2 THEN NOW() ELSE last_restocked_at END
+         WHERE id = $3",
+    )
+    .bind(balance)
+    .bind(change.movement_type == MovementType::Restock)
+    .bind(inventory.id)
+    .execute(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let movement = sqlx::query_as::<_, StockMovement>(
+        "INSERT INTO stock_movements (inventory_id, product_id, variant_id, movement_type,
+         quantity_delta, balance_after, reason, reason_code, user_id, order_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         RETURNING *",
+    )
+    .bind(inventory.id)
+    .bind(change.product_id)
+    .bind(change.variant_id)
+    .bind(change.movement_type.as_str())
+    .bind(change.quantity_delta)
+    .bind(balance)
+    .bind(&change.reason)
+    .bind(change.reason_code.map(ReasonCode::as_str))
+    .bind(change.user_id)
+    .bind(change.order_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    if change.quantity_delta < 0 {
+        lots::consume_fefo(conn, &movement).await?;
+    } else if let (MovementType::Refund, Some(order_id)) = (change.movement_type, change.order_id) {
+        lots::restore_for_order(conn, &movement, order_id).await?;
     }
 
-    tx.commit().await.map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(movement)
+}
 
-    Ok(())
+/// Stock row for an item at a new location, copying reorder settings from
+/// another location that stocks it
+async fn create_stock_row(conn: &mut PgConnection, change: &StockChange) -> Result<Inventory, AppError> {
+    let known: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM inventory
+         WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2)",
+    )
+    .bind(change.product_id)
+    .bind(change.variant_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)?;
+    if !known {
+        return Err(AppError::ProductNotFound {
+            id: change.product_id,
+        });
+    }
+
+    sqlx::query_as::<_, Inventory>(
+        "INSERT INTO inventory (product_id, variant_id, location_id, quantity, reorder_level,
+                                reorder_quantity)
+         SELECT $1, $2, $3, 0, reorder_level, reorder_quantity
+         FROM inventory
+         WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2
+         ORDER BY created_at
+         LIMIT 1
+         RETURNING *",
+    )
+    .bind(change.product_id)
+    .bind(change.variant_id)
+    .bind(change.location_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)
 }
 
+/// Take stock for an order line
+pub async fn reserve_inventory(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    variant_id: Option<Uuid>,
+    location_id: Uuid,
+    quantity: i32,
+    user_id: Option<Uuid>,
+    order_id: Uuid,
+) -> Result<StockMovement, AppError> {
+    record_movement(
+        conn,
+        StockChange {
+            product_id,
+            variant_id,
+            location_id,
+            movement_type: MovementType::Sale,
+            quantity_delta: -quantity,
+            reason: None,
+            reason_code: None,
+            user_id,
+            order_id: Some(order_id),
+        },
+    )
+    .await
+}
+
+/// Add delivered stock at a location (the default location when `None`),
+/// as a new lot when lot details are given
 pub async fn restock_inventory(
     pool: &PgPool,
     product_id: Uuid,
+    variant_id: Option<Uuid>,
+    location_id: Option<Uuid>,
     quantity: i32,
-) -> Result<()> {
-    sqlx::query(
-        "UPDATE inventory
-         SET quantity = quantity + $1, last_restocked_at = $2
-         WHERE product_id = $3",
+    lot: LotDetails,
+    user_id: Option<Uuid>,
+) -> Result<StockMovement, AppError> {
+    if quantity <= 0 {
+        return Err(AppError::InvalidQuantity {
+            quantity: quantity.max(0) as u32,
+        });
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let location_id = locations::resolve_location(&mut tx, location_id).await?;
+
+    let movement = record_movement(
+        &mut tx,
+        StockChange {
+            product_id,
+            variant_id,
+            location_id,
+            movement_type: MovementType::Restock,
+            quantity_delta: quantity,
+            reason: None,
+            reason_code: None,
+            user_id,
+            order_id: None,
+        },
+    )
+    .await?;
+
+    if !lot.is_empty() {
+        lots::receive_lot(&mut tx, &movement, &lot).await?;
+    }
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(movement)
+}
+
+/// Manually adjust stock with a reason code.
+///
+/// Adjustments moving more than `approval_threshold` units are refused unless
+/// made by a manager.
+pub async fn adjust_stock(
+    pool: &PgPool,
+    user_id: Uuid,
+    is_manager: bool,
+    approval_threshold: i32,
+    product_id: Uuid,
+    request: AdjustStockRequest,
+) -> Result<StockMovement, AppError> {
+    let reason_code = request
+        .reason_code
+        .ok_or_else(|| AppError::Validation("reason_code is required".to_string()))?;
+    if request.quantity_delta.unsigned_abs() > approval_threshold.max(0) as u32 && !is_manager {
+        return Err(AppError::ApprovalRequired(format!(
+            "adjustments over {} units must be made by a manager",
+            approval_threshold
+        )));
+    }
+
+    let movement_type = if request.quantity_delta < 0 {
+        reason_code.movement_type()
+    } else {
+        MovementType::Adjustment
+    };
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let location_id = locations::resolve_location(&mut tx, request.location_id).await?;
+
+    let movement = record_movement(
+        &mut tx,
+        StockChange {
+            product_id,
+            variant_id: request.variant_id,
+            location_id,
+            movement_type,
+            quantity_delta: request.quantity_delta,
+            reason: request.note,
+            reason_code: Some(reason_code),
+            user_id: Some(user_id),
+            order_id: None,
+        },
+    )
+    .await?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(movement)
+}
+
+/// Movement history of a product, newest first
+pub async fn get_movements(
+    pool: &PgPool,
+    product_id: Uuid,
+    query: &MovementQuery,
+) -> Result<Vec<StockMovement>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_MOVEMENT_LIMIT)
+        .clamp(1, MAX_MOVEMENT_LIMIT);
+
+    let movements = sqlx::query_as::<_, StockMovement>(
+        "SELECT m.* FROM stock_movements m
+         JOIN inventory i ON i.id = m.inventory_id
+         WHERE m.product_id = $1 AND ($2::UUID IS NULL OR m.variant_id = $2)
+         AND ($3::UUID IS NULL OR i.location_id = $3)
+         ORDER BY m.created_at DESC
+         LIMIT $4",
     )
-    .bind(quantity)
-    .bind(Utc::now())
     .bind(product_id)
-    .execute(pool)
+    .bind(query.variant_id)
+    .bind(query.location_id)
+    .bind(limit)
+    .fetch_all(pool)
     .await?;
 
-    Ok(())
+    Ok(movements)
 }
 
+/// Stock rows at or below their reorder level, across all locations.
+///
+/// Products made from a recipe hold no stock of their own and are left out;
+/// their ingredients are reported instead.
 pub async fn get_low_stock_items(pool: &PgPool) -> Result<Vec<Inventory>> {
     let items = sqlx::query_as::<_, Inventory>(
-        "SELECT * FROM inventory WHERE quantity <= reorder_level ORDER BY quantity",
+        "SELECT * FROM inventory
+         WHERE quantity <= reorder_level AND NOT is_recipe_tracked(product_id)
+         ORDER BY quantity",
     )
     .fetch_all(pool)
     .await?;
@@ -0,0 +1,256 @@
+//! Stock locations and the terminals bound to them
+
+use shared::AppError;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::{Location, Terminal};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct LocationRequest {
+    pub name: String,
+    #[serde(default = "default_kind")]
+    pub kind: String,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+fn default_kind() -> String {
+    "store".to_string()
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TerminalRequest {
+    pub name: String,
+    pub location_id: Uuid,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct StockByLocationQuery {
+    pub product_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+}
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct LocationStock {
+    pub location_id: Uuid,
+    pub location_name: String,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub name: String,
+    pub quantity: i32,
+    pub reorder_level: i32,
+    /// Units sent from this location on transfers not yet received
+    pub in_transit_out: i64,
+    /// Units on their way to this location
+    pub in_transit_in: i64,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+fn validate_location(request: &LocationRequest) -> Result<(), AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::Validation("Location name is required".to_string()));
+    }
+    if !matches!(request.kind.as_str(), "store" | "storeroom") {
+        return Err(AppError::Validation(format!(
+            "Location kind must be store or storeroom, got {}",
+            request.kind
+        )));
+    }
+    Ok(())
+}
+
+/// The given location if it exists and is active, otherwise the default location
+pub async fn resolve_location(
+    conn: &mut PgConnection,
+    location_id: Option<Uuid>,
+) -> Result<Uuid, AppError> {
+    let found: Option<Uuid> = match location_id {
+        Some(id) => sqlx::query_scalar("SELECT id FROM locations WHERE id = $1 AND is_active = true")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(db_error)?,
+        None => sqlx::query_scalar("SELECT id FROM locations WHERE is_default = true")
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(db_error)?,
+    };
+
+    found.ok_or_else(|| match location_id {
+        Some(id) => AppError::NotFound(format!("Active location {}", id)),
+        None => AppError::Internal("No default location configured".to_string()),
+    })
+}
+
+/// Location a terminal sells from
+pub async fn terminal_location(conn: &mut PgConnection, terminal_id: Uuid) -> Result<Uuid, AppError> {
+    sqlx::query_scalar(
+        "SELECT t.location_id FROM terminals t
+         JOIN locations l ON l.id = t.location_id
+         WHERE t.id = $1 AND t.is_active = true AND l.is_active = true",
+    )
+    .bind(terminal_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(db_error)?
+    .ok_or_else(|| AppError::NotFound(format!("Active terminal {}", terminal_id)))
+}
+
+pub async fn list_locations(pool: &PgPool) -> Result<Vec<Location>, AppError> {
+    sqlx::query_as::<_, Location>("SELECT * FROM locations ORDER BY is_default DESC, name")
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)
+}
+
+pub async fn create_location(pool: &PgPool, request: LocationRequest) -> Result<Location, AppError> {
+    validate_location(&request)?;
+
+    sqlx::query_as::<_, Location>(
+        "INSERT INTO locations (name, kind, is_active) VALUES ($1, $2, $3) RETURNING *",
+    )
+    .bind(request.name.trim())
+    .bind(&request.kind)
+    .bind(request.is_active)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error)
+}
+
+pub async fn update_location(
+    pool: &PgPool,
+    location_id: Uuid,
+    request: LocationRequest,
+) -> Result<Location, AppError> {
+    validate_location(&request)?;
+
+    let location = sqlx::query_as::<_, Location>(
+        "UPDATE locations SET name = $1, kind = $2, is_active = $3
+         WHERE id = $4 AND (is_default = false OR $3)
+         RETURNING *",
+    )
+    .bind(request.name.trim())
+    .bind(&request.kind)
+    .bind(request.is_active)
+    .bind(location_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)?;
+
+    if let Some(location) = location {
+        return Ok(location);
+    }
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM locations WHERE id = $1)")
+        .bind(location_id)
+        .fetch_one(pool)
+        .await
+        .map_err(db_error)?;
+    Err(if exists {
+        AppError::Validation(format!(
+            "Location {} is the default and cannot be deactivated",
+            location_id
+        ))
+    } else {
+        AppError::NotFound(format!("Location {}", location_id))
+    })
+}
+
+pub async fn list_terminals(pool: &PgPool) -> Result<Vec<Terminal>, AppError> {
+    sqlx::query_as::<_, Terminal>("SELECT * FROM terminals ORDER BY name")
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)
+}
+
+pub async fn create_terminal(pool: &PgPool, request: TerminalRequest) -> Result<Terminal, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::Validation("Terminal name is required".to_string()));
+    }
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+    resolve_location(&mut conn, Some(request.location_id)).await?;
+
+    sqlx::query_as::<_, Terminal>(
+        "INSERT INTO terminals (name, location_id, is_active) VALUES ($1, $2, $3) RETURNING *",
+    )
+    .bind(request.name.trim())
+    .bind(request.location_id)
+    .bind(request.is_active)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)
+}
+
+pub async fn update_terminal(
+    pool: &PgPool,
+    terminal_id: Uuid,
+    request: TerminalRequest,
+) -> Result<Terminal, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::Validation("Terminal name is required".to_string()));
+    }
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+    resolve_location(&mut conn, Some(request.location_id)).await?;
+
+    sqlx::query_as::<_, Terminal>(
+        "UPDATE terminals SET name = $1, location_id = $2, is_active = $3
+         WHERE id = $4
+         RETURNING *",
+    )
+    .bind(request.name.trim())
+    .bind(request.location_id)
+    .bind(request.is_active)
+    .bind(terminal_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_error)?
+    .ok_or_else(|| AppError::NotFound(format!("Terminal {}", terminal_id)))
+}
+
+/// Stock per product per location, with quantities in transit
+pub async fn stock_by_location(
+    pool: &PgPool,
+    query: &StockByLocationQuery,
+) -> Result<Vec<LocationStock>, AppError> {
+    sqlx::query_as::<_, LocationStock>(
+        "WITH in_transit AS (
+             SELECT t.from_location_id, t.to_location_id, l.product_id, l.variant_id, l.quantity
+             FROM stock_transfer_lines l
+             JOIN stock_transfers t ON t.id = l.transfer_id
+             WHERE t.status = 'in_transit'
+         )
+         SELECT i.location_id, loc.name AS location_name, i.product_id, i.variant_id,
+                CASE WHEN v.id IS NULL THEN p.name ELSE p.name || ' (' || v.name || ')' END AS name,
+                i.quantity, i.reorder_level,
+                COALESCE((SELECT SUM(quantity) FROM in_transit t
+                          WHERE t.from_location_id = i.location_id AND t.product_id = i.product_id
+                          AND t.variant_id IS NOT DISTINCT FROM i.variant_id), 0)::BIGINT
+                    AS in_transit_out,
+                COALESCE((SELECT SUM(quantity) FROM in_transit t
+                          WHERE t.to_location_id = i.location_id AND t.product_id = i.product_id
+                          AND t.variant_id IS NOT DISTINCT FROM i.variant_id), 0)::BIGINT
+                    AS in_transit_in
+         FROM inventory i
+         JOIN locations loc ON loc.id = i.location_id
+         JOIN products p ON p.id = i.product_id
+         LEFT JOIN product_variants v ON v.id = i.variant_id
+         WHERE ($1::UUID IS NULL OR i.product_id = $1)
+         AND ($2::UUID IS NULL OR i.location_id = $2)
+         ORDER BY p.name, v.sort_order NULLS FIRST, loc.is_default DESC, loc.name",
+    )
+    .bind(query.product_id)
+    .bind(query.location_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
@@ -0,0 +1,396 @@
+//! Stock lots, first-expiry-first-out picking and expiry write-offs
+//!
+//! Every decrease posted through `inventory::record_movement` draws from the
+//! stock row's lots in expiry order; refunds of an order put units back into
+//! the lots its sale took them from. See the `stock_lots` migration.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use shared::AppError;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::{StockLot, StockMovement};
+use crate::services::inventory::{self, ReasonCode, StockChange};
+
+/// Lot details for stock being received; both are optional
+#[derive(Debug, serde::Deserialize)]
+pub struct LotDetails {
+    pub lot_number: Option<String>,
+    /// Last day the stock may be sold
+    pub expires_on: Option<NaiveDate>,
+}
+
+impl LotDetails {
+    pub fn is_empty(&self) -> bool {
+        self.lot_number.is_none() && self.expires_on.is_none()
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct LotQuery {
+    pub variant_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    /// Include lots that have been used up
+    #[serde(default)]
+    pub include_empty: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct NearExpiryQuery {
+    /// Lots expiring within this many days, including already expired ones
+    #[serde(default = "default_days")]
+    pub days: i32,
+    pub location_id: Option<Uuid>,
+}
+
+fn default_days() -> i32 {
+    3
+}
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct NearExpiryLot {
+    pub lot_id: Uuid,
+    pub lot_number: Option<String>,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub name: String,
+    pub location_id: Uuid,
+    pub location_name: String,
+    pub expires_on: NaiveDate,
+    /// Negative once the lot has expired
+    pub days_left: i32,
+    pub quantity: i32,
+    /// `quantity × unit cost`; `None` when the item has no cost
+    pub value_cents: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ExpiryRun {
+    /// Waste movements posted, one per stock row with expired lots
+    pub movements: Vec<StockMovement>,
+    pub units_written_off: i64,
+}
+
+/// A lot as a transfer's send movement drew from it
+#[derive(Debug, sqlx::FromRow)]
+struct SentLot {
+    lot_number: Option<String>,
+    expires_on: Option<NaiveDate>,
+    received_at: DateTime<Utc>,
+    quantity: i32,
+}
+
+/// An expired lot with stock left, locked for writing off
+#[derive(Debug, sqlx::FromRow)]
+struct ExpiredLot {
+    inventory_id: Uuid,
+    product_id: Uuid,
+    variant_id: Option<Uuid>,
+    location_id: Uuid,
+    quantity: i32,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+async fn allocate(
+    conn: &mut PgConnection,
+    movement_id: Uuid,
+    lot_id: Uuid,
+    quantity: i32,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE stock_lots SET quantity = quantity + $1 WHERE id = $2")
+        .bind(quantity)
+        .bind(lot_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+    sqlx::query(
+        "INSERT INTO stock_lot_allocations (movement_id, lot_id, quantity) VALUES ($1, $2, $3)",
+    )
+    .bind(movement_id)
+    .bind(lot_id)
+    .bind(quantity)
+    .execute(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    Ok(())
+}
+
+/// Record the units added by an increase as a new lot
+pub async fn receive_lot(
+    conn: &mut PgConnection,
+    movement: &StockMovement,
+    details: &LotDetails,
+) -> Result<StockLot, AppError> {
+    if movement.quantity_delta <= 0 {
+        return Err(AppError::Validation(
+            "Only stock being received can start a lot".to_string(),
+        ));
+    }
+
+    let lot = sqlx::query_as::<_, StockLot>(
+        "INSERT INTO stock_lots (inventory_id, product_id, variant_id, lot_number, expires_on,
+         initial_quantity, quantity)
+         VALUES ($1, $2, $3, $4, $5, $6, 0)
+         RETURNING *",
+    )
+    .bind(movement.inventory_id)
+    .bind(movement.product_id)
+    .bind(movement.variant_id)
+    .bind(details.lot_number.as_deref().map(str::trim))
+    .bind(details.expires_on)
+    .bind(movement.quantity_delta)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    allocate(conn, movement.id, lot.id, movement.quantity_delta).await?;
+
+    Ok(StockLot {
+        quantity: movement.quantity_delta,
+        ..lot
+    })
+}
+
+/// Draw a decrease from the stock row's lots, soonest expiry first.
+///
+/// Whatever the lots cannot cover comes out of untracked stock.
+pub(crate) async fn consume_fefo(
+    conn: &mut PgConnection,
+    movement: &StockMovement,
+) -> Result<(), AppError> {
+    let lots = sqlx::query_as::<_, StockLot>(
+        "SELECT * FROM stock_lots
+         WHERE inventory_id = $1 AND quantity > 0
+         ORDER BY expires_on NULLS LAST, received_at, id
+         FOR UPDATE",
+    )
+    .bind(movement.inventory_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let mut remaining = -movement.quantity_delta;
+    for lot in lots {
+        if remaining == 0 {
+            break;
+        }
+        let take = remaining.min(lot.quantity);
+        allocate(conn, movement.id, lot.id, -take).await?;
+        remaining -= take;
+    }
+
+    Ok(())
+}
+
+/// Put refunded units back into the lots the order's sale drew them from
+pub(crate) async fn restore_for_order(
+    conn: &mut PgConnection,
+    movement: &StockMovement,
+    order_id: Uuid,
+) -> Result<(), AppError> {
+    let taken: Vec<(Uuid, i64)> = sqlx::query_as(
+        "SELECT a.lot_id, -SUM(a.quantity)
+         FROM stock_lot_allocations a
+         JOIN stock_movements m ON m.id = a.movement_id
+         WHERE m.order_id = $1 AND m.inventory_id = $2
+         GROUP BY a.lot_id
+         HAVING SUM(a.quantity) < 0",
+    )
+    .bind(order_id)
+    .bind(movement.inventory_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let mut remaining = movement.quantity_delta as i64;
+    for (lot_id, net_taken) in taken {
+        if remaining == 0 {
+            break;
+        }
+        let give = remaining.min(net_taken);
+        allocate(conn, movement.id, lot_id, give as i32).await?;
+        remaining -= give;
+    }
+
+    Ok(())
+}
+
+/// Recreate at the destination the lots a transfer's send movement drew from
+pub(crate) async fn receive_transferred(
+    conn: &mut PgConnection,
+    movement: &StockMovement,
+    sent_movement_id: Uuid,
+) -> Result<(), AppError> {
+    let sent: Vec<SentLot> = sqlx::query_as(
+        "SELECT l.lot_number, l.expires_on, l.received_at, -a.quantity AS quantity
+         FROM stock_lot_allocations a
+         JOIN stock_lots l ON l.id = a.lot_id
+         WHERE a.movement_id = $1 AND a.quantity < 0
+         ORDER BY l.expires_on NULLS LAST, l.received_at",
+    )
+    .bind(sent_movement_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    for lot in sent {
+        let lot_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO stock_lots (inventory_id, product_id, variant_id, lot_number, expires_on,
+             received_at, initial_quantity, quantity)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, 0)
+             RETURNING id",
+        )
+        .bind(movement.inventory_id)
+        .bind(movement.product_id)
+        .bind(movement.variant_id)
+        .bind(&lot.lot_number)
+        .bind(lot.expires_on)
+        .bind(lot.received_at)
+        .bind(lot.quantity)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+        allocate(conn, movement.id, lot_id, lot.quantity).await?;
+    }
+
+    Ok(())
+}
+
+/// Lots of an item at a location (the default location when none is given),
+/// in the order they will be picked
+pub async fn get_lots(
+    pool: &PgPool,
+    product_id: Uuid,
+    query: &LotQuery,
+) -> Result<Vec<StockLot>, AppError> {
+    sqlx::query_as::<_, StockLot>(
+        "SELECT l.* FROM stock_lots l
+         JOIN inventory i ON i.id = l.inventory_id
+         WHERE l.product_id = $1 AND l.variant_id IS NOT DISTINCT FROM $2
+         AND i.location_id = COALESCE($3, (SELECT id FROM locations WHERE is_default = true))
+         AND ($4 OR l.quantity > 0)
+         ORDER BY l.expires_on NULLS LAST, l.received_at, l.id",
+    )
+    .bind(product_id)
+    .bind(query.variant_id)
+    .bind(query.location_id)
+    .bind(query.include_empty)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+/// Lots with stock left that expire within `days`, soonest first
+pub async fn near_expiry(
+    pool: &PgPool,
+    query: &NearExpiryQuery,
+) -> Result<Vec<NearExpiryLot>, AppError> {
+    if query.days < 0 {
+        return Err(AppError::Validation(format!(
+            "days must not be negative, got {}",
+            query.days
+        )));
+    }
+
+    sqlx::query_as::<_, NearExpiryLot>(
+        "SELECT l.id AS lot_id, l.lot_number, l.product_id, l.variant_id,
+                CASE WHEN v.id IS NULL THEN p.name ELSE p.name || ' (' || v.name || ')' END AS name,
+                i.location_id, loc.name AS location_name, l.expires_on,
+                l.expires_on - CURRENT_DATE AS days_left,
+                l.quantity,
+                l.quantity * COALESCE(v.cost_cents, p.cost_cents) AS value_cents
+         FROM stock_lots l
+         JOIN inventory i ON i.id = l.inventory_id
+         JOIN locations loc ON loc.id = i.location_id
+         JOIN products p ON p.id = l.product_id
+         LEFT JOIN product_variants v ON v.id = l.variant_id
+         WHERE l.quantity > 0 AND l.expires_on IS NOT NULL
+         AND l.expires_on <= CURRENT_DATE + $1
+         AND ($2::UUID IS NULL OR i.location_id = $2)
+         ORDER BY l.expires_on, p.name, loc.name",
+    )
+    .bind(query.days)
+    .bind(query.location_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+/// Write off every lot whose expiry date has passed as spoilage waste.
+///
+/// Expired lots have the earliest expiry dates, so picking the waste
+/// movement first-expiry-first-out empties exactly those lots. The lots are
+/// locked first: a concurrent run waits, then finds them empty, rather than
+/// writing off the same units again from the next, unexpired lots.
+pub async fn expire_lots(pool: &PgPool, user_id: Option<Uuid>) -> Result<ExpiryRun, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let expired: Vec<ExpiredLot> = sqlx::query_as(
+        "SELECT l.inventory_id, i.product_id, i.variant_id, i.location_id, l.quantity
+         FROM stock_lots l
+         JOIN inventory i ON i.id = l.inventory_id
+         WHERE l.quantity > 0 AND l.expires_on < CURRENT_DATE
+         ORDER BY l.inventory_id, l.id
+         FOR UPDATE OF l",
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    // Rows are ordered by stock row, so each row's lots are adjacent
+    let mut rows: Vec<(ExpiredLot, i64, i64)> = Vec::new();
+    for lot in expired {
+        match rows.last_mut() {
+            Some((row, units, lots)) if row.inventory_id == lot.inventory_id => {
+                *units += lot.quantity as i64;
+                *lots += 1;
+            }
+            _ => {
+                let units = lot.quantity as i64;
+                rows.push((lot, units, 1));
+            }
+        }
+    }
+
+    let mut movements = Vec::with_capacity(rows.len());
+    let mut units_written_off = 0;
+    for (row, units, lots) in rows {
+        let ExpiredLot {
+            product_id,
+            variant_id,
+            location_id,
+            ..
+        } = row;
+        let reason_code = ReasonCode::Spoilage;
+        let movement = inventory::record_movement(
+            &mut tx,
+            StockChange {
+                product_id,
+                variant_id,
+                location_id,
+                movement_type: reason_code.movement_type(),
+                quantity_delta: -(units as i32),
+                reason: Some(format!("{} expired lot(s) written off", lots)),
+                reason_code: Some(reason_code),
+                user_id,
+                order_id: None,
+            },
+        )
+        .await?;
+        units_written_off += units;
+        movements.push(movement);
+    }
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(ExpiryRun {
+        movements,
+        units_written_off,
+    })
+}
@@ -1,8 +1,23 @@
 //! Business logic services
 
+pub mod audit;
+pub mod availability;
+pub mod catalog_csv;
+pub mod exchange_rates;
+pub mod forecasting;
 pub mod products;
 pub mod orders;
+pub mod pricing;
+pub mod purchasing;
+pub mod recipes;
 pub mod inventory;
+pub mod locations;
+pub mod lots;
+pub mod modifiers;
+pub mod reports;
+pub mod stock_counts;
+pub mod suppliers;
+pub mod transfers;
 
 pub use products::*;
 pub use orders::*;
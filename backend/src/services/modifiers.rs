@@ -0,0 +1,146 @@
+//! Product modifier service
+
+use anyhow::Result;
+use shared::{AppError, ModifierGroupResponse};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::db::{ModifierGroup, ModifierOption};
+
+#[derive(Debug, serde::Serialize)]
+pub struct ModifierGroupWithOptions {
+    #[serde(flatten)]
+    pub group: ModifierGroup,
+    pub options: Vec<ModifierOption>,
+}
+
+impl From<ModifierGroupWithOptions> for ModifierGroupResponse {
+    fn from(listed: ModifierGroupWithOptions) -> Self {
+        Self {
+            id: listed.group.id,
+            name: listed.group.name,
+            is_required: listed.group.is_required,
+            min_selections: listed.group.min_selections,
+            max_selections: listed.group.max_selections,
+            options: listed.options.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// A modifier option chosen for an order line
+#[derive(Debug, Clone)]
+pub struct SelectedModifier {
+    pub option_id: Uuid,
+    pub group_name: String,
+    pub option_name: String,
+    pub price_delta_cents: i64,
+}
+
+/// Get the modifier groups that apply to a product, either directly or via its category
+pub async fn get_modifier_groups_for_product(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+) -> Result<Vec<ModifierGroupWithOptions>> {
+    let groups = sqlx::query_as::<_, ModifierGroup>(
+        "SELECT * FROM modifier_groups
+         WHERE is_active = true
+         AND (id IN (SELECT group_id FROM product_modifier_groups WHERE product_id = $1)
+              OR id IN (SELECT cmg.group_id FROM category_modifier_groups cmg
+                        JOIN products p ON p.category_id = cmg.category_id
+                        WHERE p.id = $1))
+         ORDER BY sort_order, name",
+    )
+    .bind(product_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let group_ids: Vec<Uuid> = groups.iter().map(|g| g.id).collect();
+    let options = sqlx::query_as::<_, ModifierOption>(
+        "SELECT * FROM modifier_options
+         WHERE group_id = ANY($1) AND is_active = true
+         ORDER BY sort_order, name",
+    )
+    .bind(&group_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(groups
+        .into_iter()
+        .map(|group| {
+            let group_options = options
+                .iter()
+                .filter(|o| o.group_id == group.id)
+                .cloned()
+                .collect();
+            ModifierGroupWithOptions {
+                group,
+                options: group_options,
+            }
+        })
+        .collect())
+}
+
+/// Resolve the chosen option ids against a product's modifier groups and
+/// enforce each group's required flag and minimum/maximum selections.
+///
+/// An option may be chosen more than once (e.g. two extra shots); every
+/// occurrence counts towards the group's maximum.
+pub fn resolve_modifiers(
+    groups: &[ModifierGroupWithOptions],
+    option_ids: &[Uuid],
+) -> Result<Vec<SelectedModifier>, AppError> {
+    let mut selected = Vec::with_capacity(option_ids.len());
+
+    for option_id in option_ids {
+        let (group, option) = groups
+            .iter()
+            .find_map(|g| {
+                g.options
+                    .iter()
+                    .find(|o| o.id == *option_id)
+                    .map(|o| (&g.group, o))
+            })
+            .ok_or_else(|| {
+                AppError::Validation(format!(
+                    "Modifier option {} is not available for this product",
+                    option_id
+                ))
+            })?;
+
+        selected.push(SelectedModifier {
+            option_id: option.id,
+            group_name: group.name.clone(),
+            option_name: option.name.clone(),
+            price_delta_cents: option.price_delta_cents,
+        });
+    }
+
+    for g in groups {
+        let count = g
+            .options
+            .iter()
+            .map(|o| option_ids.iter().filter(|id| **id == o.id).count())
+            .sum::<usize>() as i32;
+
+        let min = if g.group.is_required {
+            g.group.min_selections.max(1)
+        } else {
+            g.group.min_selections
+        };
+
+        if count < min {
+            return Err(AppError::Validation(format!(
+                "{} requires at least {} selection(s)",
+                g.group.name, min
+            )));
+        }
+        if count > g.group.max_selections {
+            return Err(AppError::Validation(format!(
+                "{} allows at most {} selection(s)",
+                g.group.name, g.group.max_selections
+            )));
+        }
+    }
+
+    Ok(selected)
+}
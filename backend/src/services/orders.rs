@@ -1,32 +1,215 @@
 //! Order management service
 
 use anyhow::Result;
-use chrono::Utc;
-use shared::{AppError, DEFAULT_TAX_RATE};
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use shared::{
+    AppError, CashRounding, CompletedOrderResponse, CreateOrderRequest, Money, OrderResponse,
+    OrderStatus, PaymentMethod, TenderRequest, DEFAULT_TAX_RATE,
+};
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder};
 use uuid::Uuid;
 
-use crate::db::{Order, OrderItem, Product};
-use crate::services::inventory;
+use crate::db::{dto, Order, OrderItem, OrderItemModifier, OrderTender, Product, ProductVariant};
+use crate::services::{
+    availability, exchange_rates, inventory, locations, modifiers, pricing, recipes,
+};
 
-#[derive(Debug, serde::Deserialize)]
-pub struct CreateOrderRequest {
-    pub items: Vec<CreateOrderItem>,
-    pub customer_name: Option<String>,
-    pub customer_email: Option<String>,
-    pub notes: Option<String>,
+#[derive(Debug, serde::Serialize)]
+pub struct OrderWithItems {
+    pub order: Order,
+    pub items: Vec<OrderItem>,
+    pub modifiers: Vec<OrderItemModifier>,
+    pub tenders: Vec<OrderTender>,
 }
 
-#[derive(Debug, serde::Deserialize)]
-pub struct CreateOrderItem {
-    pub product_id: Uuid,
-    pub quantity: i32,
+impl From<OrderWithItems> for OrderResponse {
+    fn from(order: OrderWithItems) -> Self {
+        let currency = order.order.currency;
+        Self {
+            order: order.order.into(),
+            items: order.items.into_iter().map(Into::into).collect(),
+            modifiers: order
+                .modifiers
+                .into_iter()
+                .map(|modifier| dto::order_item_modifier(modifier, currency))
+                .collect(),
+            tenders: order
+                .tenders
+                .into_iter()
+                .map(|tender| dto::order_tender(tender, currency))
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, serde::Serialize)]
-pub struct OrderWithItems {
+pub struct CompletedOrder {
+    #[serde(flatten)]
     pub order: Order,
-    pub items: Vec<OrderItem>,
+    /// What was tendered and the change due, when a tender was given
+    pub tender: Option<OrderTender>,
+}
+
+impl From<CompletedOrder> for CompletedOrderResponse {
+    fn from(completed: CompletedOrder) -> Self {
+        let currency = completed.order.currency;
+        Self {
+            order: completed.order.into(),
+            tender: completed
+                .tender
+                .map(|tender| dto::order_tender(tender, currency)),
+        }
+    }
+}
+
+/// Default number of orders returned per page
+pub const DEFAULT_ORDER_PAGE_SIZE: i64 = 50;
+
+/// Maximum number of orders returned per page
+pub const MAX_ORDER_PAGE_SIZE: i64 = 200;
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct OrderFilter {
+    pub status: Option<OrderStatus>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub user_id: Option<Uuid>,
+    pub payment_method: Option<PaymentMethod>,
+    pub customer_email: Option<String>,
+    pub order_number: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct OrderPage {
+    pub orders: Vec<Order>,
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+/// Keyset position in the `(created_at, id)` ordering of orders
+#[derive(Debug, Clone, Copy)]
+struct OrderCursor {
+    created_at: DateTime<Utc>,
+    id: Uuid,
+}
+
+impl OrderCursor {
+    fn encode(&self) -> String {
+        format!("{}_{}", self.created_at.timestamp_micros(), self.id)
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        let (micros, id) = cursor.split_once('_')?;
+        Some(Self {
+            created_at: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+            id: Uuid::parse_str(id).ok()?,
+        })
+    }
+}
+
+fn push_order_filters(builder: &mut QueryBuilder<'_, Postgres>, filter: &OrderFilter) {
+    builder.push(" WHERE 1 = 1");
+
+    if let Some(status) = filter.status {
+        builder.push(" AND status = ").push_bind(status);
+    }
+    if let Some(from) = filter.from {
+        builder.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.to {
+        builder.push(" AND created_at < ").push_bind(to);
+    }
+    if let Some(user_id) = filter.user_id {
+        builder.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(payment_method) = filter.payment_method {
+        builder.push(" AND payment_method = ").push_bind(payment_method);
+    }
+    if let Some(email) = &filter.customer_email {
+        builder
+            .push(" AND LOWER(customer_email) = LOWER(")
+            .push_bind(email.clone())
+            .push(")");
+    }
+    if let Some(prefix) = &filter.order_number {
+        builder
+            .push(" AND order_number LIKE ")
+            .push_bind(format!("{}%", escape_like(prefix)));
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// List orders newest first, filtered and paginated by `(created_at, id)`
+pub async fn list_orders(pool: &PgPool, filter: &OrderFilter) -> Result<OrderPage, AppError> {
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_ORDER_PAGE_SIZE)
+        .clamp(1, MAX_ORDER_PAGE_SIZE);
+
+    let cursor = match &filter.cursor {
+        Some(raw) => Some(
+            OrderCursor::decode(raw)
+                .ok_or_else(|| AppError::Validation(format!("Invalid cursor: {}", raw)))?,
+        ),
+        None => None,
+    };
+
+    let mut count_query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM orders");
+    push_order_filters(&mut count_query, filter);
+    let total: i64 = count_query
+        .build_query_scalar()
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    let mut list_query = QueryBuilder::<Postgres>::new("SELECT * FROM orders");
+    push_order_filters(&mut list_query, filter);
+    if let Some(cursor) = cursor {
+        list_query
+            .push(" AND (created_at, id) < (")
+            .push_bind(cursor.created_at)
+            .push(", ")
+            .push_bind(cursor.id)
+            .push(")");
+    }
+    // Fetch one extra row to learn whether another page follows
+    list_query
+        .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(limit + 1);
+
+    let mut orders = list_query
+        .build_query_as::<Order>()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    let next_cursor = if orders.len() as i64 > limit {
+        orders.truncate(limit as usize);
+        orders.last().map(|o| {
+            OrderCursor {
+                created_at: o.created_at,
+                id: o.id,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(OrderPage {
+        orders,
+        total,
+        next_cursor,
+    })
 }
 
 pub async fn create_order(
@@ -39,6 +222,11 @@ pub async fn create_order(
     }
 
     let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let now = Utc::now();
+    let location_id = match request.terminal_id {
+        Some(terminal_id) => locations::terminal_location(&mut tx, terminal_id).await?,
+        None => locations::resolve_location(&mut tx, None).await?,
+    };
 
     // Calculate order totals
     let mut subtotal_cents: i64 = 0;
@@ -52,9 +240,67 @@ pub async fn create_order(
             .await
             .map_err(|e| AppError::Database(e.to_string()))?
             .ok_or(AppError::ProductNotFound { id: item.product_id })?;
+        if product.is_ingredient {
+            return Err(AppError::Validation(format!(
+                "{} is an ingredient and cannot be sold",
+                product.name
+            )));
+        }
+        availability::ensure_available(&mut tx, &product, now).await?;
+
+        // Variants carry their own price and stock; products that have them must name one
+        let variant = match item.variant_id {
+            Some(variant_id) => Some(
+                sqlx::query_as::<_, ProductVariant>(
+                    "SELECT * FROM product_variants
+                     WHERE id = $1 AND product_id = $2 AND is_active = true",
+                )
+                .bind(variant_id)
+                .bind(item.product_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?
+                .ok_or_else(|| {
+                    AppError::Validation(format!(
+                        "Variant {} does not belong to product {}",
+                        variant_id, item.product_id
+                    ))
+                })?,
+            ),
+            None => {
+                let has_variants: bool = sqlx::query_scalar(
+                    "SELECT EXISTS (SELECT 1 FROM product_variants
+                     WHERE product_id = $1 AND is_active = true)",
+                )
+                .bind(item.product_id)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?;
+
+                if has_variants {
+                    return Err(AppError::Validation(format!(
+                        "{} requires a variant to be selected",
+                        product.name
+                    )));
+                }
+                None
+            }
+        };
 
-        // Check inventory
-        if !inventory::check_availability(&pool, item.product_id, item.quantity).await.unwrap_or(false) {
+        // Check inventory; products made from a recipe are checked as their
+        // ingredients are taken out below
+        let recipe_tracked = recipes::is_recipe_tracked(&mut tx, item.product_id).await?;
+        if !recipe_tracked
+            && !inventory::check_availability(
+            pool,
+            item.product_id,
+            item.variant_id,
+            location_id,
+            item.quantity,
+        )
+        .await
+        .unwrap_or(false)
+        {
             return Err(AppError::InsufficientInventory {
                 product_id: item.product_id,
                 requested: item.quantity as u32,
@@ -62,10 +308,39 @@ pub async fn create_order(
             });
         }
 
-        let item_total = product.price_cents * item.quantity as i64;
+        // Resolve modifiers and fold their price deltas into the unit price
+        let groups = modifiers::get_modifier_groups_for_product(&mut tx, item.product_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let selected = modifiers::resolve_modifiers(&groups, &item.modifier_option_ids)?;
+
+        let (list_price, line_name) = match &variant {
+            Some(v) => (v.price_cents, format!("{} ({})", product.name, v.name)),
+            None => (product.price_cents, product.name.clone()),
+        };
+        // Snapshot the cost now so later cost edits don't rewrite past margins
+        let unit_cost = variant
+            .as_ref()
+            .and_then(|v| v.cost_cents)
+            .or(product.cost_cents);
+        let base_price = pricing::price_at(&mut tx, item.product_id, item.variant_id, now)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .unwrap_or(list_price);
+        let unit_price = base_price + selected.iter().map(|m| m.price_delta_cents).sum::<i64>();
+        let item_total = unit_price * item.quantity as i64;
         subtotal_cents += item_total;
 
-        order_items.push((product, item.quantity, item_total));
+        order_items.push((
+            product,
+            item.variant_id,
+            line_name,
+            item.quantity,
+            unit_price,
+            item_total,
+            unit_cost,
+            selected,
+        ));
     }
 
     // Calculate tax and total
@@ -78,8 +353,8 @@ pub async fn create_order(
     // Create order
     let order = sqlx::query_as::<_, Order>(
         "INSERT INTO orders (order_number, user_id, customer_name, customer_email,
-         subtotal_cents, tax_cents, total_cents, status, notes)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, 'pending', $8)
+         subtotal_cents, tax_cents, total_cents, status, notes, location_id, terminal_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, 'pending', $8, $9, $10)
          RETURNING *",
     )
     .bind(&order_number)
@@ -90,40 +365,95 @@ pub async fn create_order(
     .bind(tax_cents)
     .bind(total_cents)
     .bind(&request.notes)
+    .bind(location_id)
+    .bind(request.terminal_id)
     .fetch_one(&mut *tx)
     .await
     .map_err(|e| AppError::Database(e.to_string()))?;
 
     // Create order items and reserve inventory
     let mut items = Vec::new();
-    for (product, quantity, total_price) in order_items {
+    let mut item_modifiers = Vec::new();
+    for (product, variant_id, line_name, quantity, unit_price, total_price, unit_cost, selected) in
+        order_items
+    {
         let order_item = sqlx::query_as::<_, OrderItem>(
-            "INSERT INTO order_items (order_id, product_id, product_name, quantity,
-             unit_price_cents, total_price_cents)
-             VALUES ($1, $2, $3, $4, $5, $6)
+            "INSERT INTO order_items (order_id, product_id, variant_id, product_name, quantity,
+             unit_price_cents, total_price_cents, unit_cost_cents, total_cost_cents)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
              RETURNING *",
         )
         .bind(order.id)
         .bind(product.id)
-        .bind(&product.name)
+        .bind(variant_id)
+        .bind(&line_name)
         .bind(quantity)
-        .bind(product.price_cents)
+        .bind(unit_price)
         .bind(total_price)
+        .bind(unit_cost)
+        .bind(unit_cost.map(|cost| cost * quantity as i64))
         .fetch_one(&mut *tx)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        let option_ids: Vec<Uuid> = selected.iter().map(|m| m.option_id).collect();
+        for modifier in selected {
+            let item_modifier = sqlx::query_as::<_, OrderItemModifier>(
+                "INSERT INTO order_item_modifiers (order_item_id, modifier_option_id,
+                 group_name, option_name, price_delta_cents)
+                 VALUES ($1, $2, $3, $4, $5)
+                 RETURNING *",
+            )
+            .bind(order_item.id)
+            .bind(modifier.option_id)
+            .bind(&modifier.group_name)
+            .bind(&modifier.option_name)
+            .bind(modifier.price_delta_cents)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+            item_modifiers.push(item_modifier);
+        }
+
+        let depletion =
+            recipes::depletion_for(&mut tx, product.id, variant_id, &option_ids, quantity).await?;
+        if !depletion.recipe_tracked {
+            inventory::reserve_inventory(
+                &mut tx,
+                product.id,
+                variant_id,
+                location_id,
+                quantity,
+                Some(user_id),
+                order.id,
+            )
+            .await?;
+        }
+        for (ingredient_id, units) in depletion.ingredients {
+            inventory::reserve_inventory(
+                &mut tx,
+                ingredient_id,
+                None,
+                location_id,
+                units,
+                Some(user_id),
+                order.id,
+            )
+            .await?;
+        }
+
         items.push(order_item);
     }
 
     tx.commit().await.map_err(|e| AppError::Database(e.to_string()))?;
 
-    // Reserve inventory after commit
-    for item in &request.items {
-        inventory::reserve_inventory(pool, item.product_id, item.quantity).await?;
-    }
-
-    Ok(OrderWithItems { order, items })
+    Ok(OrderWithItems {
+        order,
+        items,
+        modifiers: item_modifiers,
+        tenders: Vec::new(),
+    })
 }
 
 pub async fn get_order(pool: &PgPool, order_id: Uuid) -> Result<Option<OrderWithItems>> {
@@ -141,60 +471,250 @@ pub async fn get_order(pool: &PgPool, order_id: Uuid) -> Result<Option<OrderWith
             .fetch_all(pool)
             .await?;
 
-            Ok(Some(OrderWithItems { order: o, items }))
+            let modifiers = sqlx::query_as::<_, OrderItemModifier>(
+                "SELECT m.* FROM order_item_modifiers m
+                 JOIN order_items i ON i.id = m.order_item_id
+                 WHERE i.order_id = $1
+                 ORDER BY m.created_at",
+            )
+            .bind(order_id)
+            .fetch_all(pool)
+            .await?;
+
+            let tenders = sqlx::query_as::<_, OrderTender>(
+                "SELECT * FROM order_tenders WHERE order_id = $1 ORDER BY created_at",
+            )
+            .bind(order_id)
+            .fetch_all(pool)
+            .await?;
+
+            Ok(Some(OrderWithItems {
+                order: o,
+                items,
+                modifiers,
+                tenders,
+            }))
         }
         None => Ok(None),
     }
 }
 
+/// Take payment for an order.
+///
+/// Cash payments round the total with `cash_rounding` and record the
+/// difference on the order. A cash tender may be in the order's currency or,
+/// at the current exchange rate, in a foreign one; it must cover the total and
+/// change is given in the order's currency.
 pub async fn complete_order(
     pool: &PgPool,
     order_id: Uuid,
-    payment_method: &str,
+    payment_method: PaymentMethod,
     payment_reference: Option<String>,
-) -> Result<Order, AppError> {
+    tender: Option<TenderRequest>,
+    cash_rounding: CashRounding,
+) -> Result<CompletedOrder, AppError> {
+    let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+
+    let existing = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or(AppError::OrderNotFound { id: order_id })?;
+
+    if matches!(existing.status, OrderStatus::Completed | OrderStatus::Cancelled) {
+        return Err(AppError::Validation(format!(
+            "Order {} is already {}",
+            existing.order_number, existing.status
+        )));
+    }
+
+    let total = Money::new(existing.total_cents, existing.currency);
+    let rounding = if payment_method == PaymentMethod::Cash {
+        cash_rounding.adjustment(total)?
+    } else {
+        Money::zero(total.currency)
+    };
+    let due = total.checked_add(rounding)?;
+
+    let tender = match tender {
+        Some(tender) => Some(record_tender(&mut tx, &existing, due, payment_method, tender).await?),
+        None => None,
+    };
+
     let order = sqlx::query_as::<_, Order>(
         "UPDATE orders
-         SET status = 'completed', payment_method = $1, payment_reference = $2, completed_at = $3
-         WHERE id = $4
+         SET status = 'completed', payment_method = $1, payment_reference = $2, completed_at = $3,
+             cash_rounding_cents = $4, total_cents = $5
+         WHERE id = $6
          RETURNING *",
     )
     .bind(payment_method)
     .bind(payment_reference)
     .bind(Utc::now())
+    .bind(rounding.amount)
+    .bind(due.amount)
     .bind(order_id)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| AppError::Database(e.to_string()))?;
 
-    Ok(order)
+    tx.commit().await.map_err(|e| AppError::Database(e.to_string()))?;
+
+    Ok(CompletedOrder { order, tender })
 }
 
-pub async fn cancel_order(pool: &PgPool, order_id: Uuid) -> Result<Order, AppError> {
-    // Get order items to restore inventory
-    let items = sqlx::query_as::<_, OrderItem>(
-        "SELECT * FROM order_items WHERE order_id = $1",
+/// Convert a cash tender into the order's currency and record it with the
+/// rate snapshot used and the change due from `due`
+async fn record_tender(
+    conn: &mut PgConnection,
+    order: &Order,
+    due: Money,
+    payment_method: PaymentMethod,
+    request: TenderRequest,
+) -> Result<OrderTender, AppError> {
+    if payment_method != PaymentMethod::Cash {
+        return Err(AppError::Validation(format!(
+            "Only cash can be tendered, not {}",
+            payment_method
+        )));
+    }
+    if request.amount <= 0 {
+        return Err(AppError::Validation(format!(
+            "Tendered amount must be positive, got {}",
+            request.amount
+        )));
+    }
+
+    let base = due.currency;
+    let currency = request.currency.unwrap_or(base);
+    let (rate, exchange_rate_id) = if currency == base {
+        (Decimal::ONE, None)
+    } else {
+        let rate = exchange_rates::current_rate(&mut *conn, base, currency).await?;
+        (rate.rate, Some(rate.id))
+    };
+
+    let tendered = Money::new(request.amount, currency);
+    let value = exchange_rates::to_base(tendered, base, rate)?;
+    if value.amount < due.amount {
+        return Err(AppError::PaymentFailed {
+            reason: format!(
+                "{} tendered is worth {}, short of the {} total",
+                tendered, value, due
+            ),
+        });
+    }
+    let change = value.checked_sub(due)?;
+
+    sqlx::query_as::<_, OrderTender>(
+        "INSERT INTO order_tenders (order_id, currency, amount, exchange_rate_id, rate,
+         base_amount_cents, change_cents)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING *",
+    )
+    .bind(order.id)
+    .bind(currency.code())
+    .bind(request.amount)
+    .bind(exchange_rate_id)
+    .bind(rate)
+    .bind(value.amount)
+    .bind(change.amount)
+    .fetch_one(conn)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))
+}
+
+pub async fn cancel_order(
+    pool: &PgPool,
+    user_id: Option<Uuid>,
+    order_id: Uuid,
+) -> Result<Order, AppError> {
+    let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+
+    let existing = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or(AppError::OrderNotFound { id: order_id })?;
+
+    // A cancelled order already returned its stock; a completed or refunded
+    // one has been paid for and goes through the refund flow instead
+    if matches!(
+        existing.status,
+        OrderStatus::Cancelled | OrderStatus::Completed | OrderStatus::Refunded
+    ) {
+        return Err(AppError::Validation(format!(
+            "Order {} is already {}",
+            existing.order_number, existing.status
+        )));
+    }
+
+    // Return exactly what the sale took out, ingredients included, to where it
+    // was taken from
+    let mut returns: Vec<(Uuid, Option<Uuid>, Uuid, i64)> = sqlx::query_as(
+        "SELECT m.product_id, m.variant_id, i.location_id, -SUM(m.quantity_delta)
+         FROM stock_movements m
+         JOIN inventory i ON i.id = m.inventory_id
+         WHERE m.order_id = $1 AND m.movement_type = 'sale'
+         GROUP BY m.product_id, m.variant_id, i.location_id
+         HAVING SUM(m.quantity_delta) < 0",
     )
     .bind(order_id)
-    .fetch_all(pool)
+    .fetch_all(&mut *tx)
     .await
     .map_err(|e| AppError::Database(e.to_string()))?;
 
+    // Orders taken before the stock ledger have no sale movements to reverse
+    if returns.is_empty() {
+        let location_id = locations::resolve_location(&mut tx, existing.location_id).await?;
+        let items = sqlx::query_as::<_, OrderItem>(
+            "SELECT * FROM order_items WHERE order_id = $1",
+        )
+        .bind(order_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        returns = items
+            .into_iter()
+            .filter_map(|item| {
+                item.product_id
+                    .map(|product_id| (product_id, item.variant_id, location_id, item.quantity as i64))
+            })
+            .collect();
+    }
+
     // Restore inventory
-    for item in items {
-        if let Some(product_id) = item.product_id {
-            inventory::restock_inventory(pool, product_id, item.quantity).await.ok();
-        }
+    for (product_id, variant_id, location_id, quantity) in returns {
+        inventory::record_movement(
+            &mut tx,
+            inventory::StockChange {
+                product_id,
+                variant_id,
+                location_id,
+                movement_type: inventory::MovementType::Refund,
+                quantity_delta: quantity as i32,
+                reason: Some("Order cancelled".to_string()),
+                reason_code: None,
+                user_id,
+                order_id: Some(order_id),
+            },
+        )
+        .await?;
     }
 
     // Update order status
     let order = sqlx::query_as::<_, Order>(
-        "UPDATE orders SET status = 'cancelled' WHERE id = $1 RETURNING *",
+        "UPDATE orders SET status = 'cancelled', cancelled_at = NOW() WHERE id = $1 RETURNING *",
     )
     .bind(order_id)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| AppError::Database(e.to_string()))?;
 
+    tx.commit().await.map_err(|e| AppError::Database(e.to_string()))?;
+
     Ok(order)
 }
@@ -0,0 +1,356 @@
+//! Price history and time-based price resolution
+//!
+//! `product_prices` holds non-overlapping `[effective_from, effective_to)` windows
+//! per product (or variant), enforced by an exclusion constraint. The window
+//! covering a given instant is the active price; `products.price_cents` is the
+//! fallback when no window covers it, and goes stale once a scheduled change
+//! takes effect, so read prices through this module.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use shared::{AppError, CurrencyCode};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::db::{Product, ProductPrice, ProductVariant};
+use crate::services::audit;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SchedulePriceRequest {
+    pub variant_id: Option<Uuid>,
+    pub price_cents: i64,
+    pub currency: Option<String>,
+    pub effective_from: DateTime<Utc>,
+    /// End of a temporary price; the previous price resumes afterwards
+    pub effective_to: Option<DateTime<Utc>>,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+/// A window overlapping another one, scheduled concurrently, trips the
+/// `product_prices_no_overlap` exclusion constraint
+fn overlap_error(e: sqlx::Error) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some("23P01") => AppError::Validation(
+            "Another price change overlapping this one was just scheduled".to_string(),
+        ),
+        _ => db_error(e),
+    }
+}
+
+/// Price and currency active at `at` for each product that has a price window
+/// covering it; products without one keep `products.price_cents`
+pub async fn product_prices_at(
+    pool: &PgPool,
+    product_ids: &[Uuid],
+    at: DateTime<Utc>,
+) -> Result<HashMap<Uuid, (i64, CurrencyCode)>> {
+    let prices = sqlx::query_as::<_, (Uuid, i64, CurrencyCode)>(
+        "SELECT DISTINCT ON (product_id) product_id, price_cents, currency
+         FROM product_prices
+         WHERE product_id = ANY($1) AND variant_id IS NULL
+         AND effective_from <= $2 AND (effective_to IS NULL OR effective_to > $2)
+         ORDER BY product_id, effective_from DESC",
+    )
+    .bind(product_ids)
+    .bind(at)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(id, price, currency)| (id, (price, currency)))
+    .collect();
+
+    Ok(prices)
+}
+
+/// Replace each product's price with the one active at `at`
+pub async fn apply_product_prices(
+    pool: &PgPool,
+    products: &mut [Product],
+    at: DateTime<Utc>,
+) -> Result<()> {
+    let ids: Vec<Uuid> = products.iter().map(|p| p.id).collect();
+    let prices = product_prices_at(pool, &ids, at).await?;
+
+    for product in products.iter_mut() {
+        if let Some((price, currency)) = prices.get(&product.id) {
+            product.price_cents = *price;
+            product.currency = *currency;
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace each variant's price with the one active at `at`
+pub async fn apply_variant_prices(
+    pool: &PgPool,
+    variants: &mut [ProductVariant],
+    at: DateTime<Utc>,
+) -> Result<()> {
+    let ids: Vec<Uuid> = variants.iter().map(|v| v.id).collect();
+    let prices: HashMap<Uuid, _> = sqlx::query_as::<_, (Uuid, i64, CurrencyCode)>(
+        "SELECT DISTINCT ON (variant_id) variant_id, price_cents, currency
+         FROM product_prices
+         WHERE variant_id = ANY($1)
+         AND effective_from <= $2 AND (effective_to IS NULL OR effective_to > $2)
+         ORDER BY variant_id, effective_from DESC",
+    )
+    .bind(&ids)
+    .bind(at)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(id, price, currency)| (id, (price, currency)))
+    .collect();
+
+    for variant in variants.iter_mut() {
+        if let Some((price, currency)) = prices.get(&variant.id) {
+            variant.price_cents = *price;
+            variant.currency = *currency;
+        }
+    }
+
+    Ok(())
+}
+
+/// Price active at `at` for a product or variant, if any window covers it
+pub async fn price_at(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    variant_id: Option<Uuid>,
+    at: DateTime<Utc>,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT price_cents FROM product_prices
+         WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2
+         AND effective_from <= $3 AND (effective_to IS NULL OR effective_to > $3)
+         ORDER BY effective_from DESC
+         LIMIT 1",
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .bind(at)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Insert a price window, trimming the window it starts inside.
+///
+/// An open-ended change runs until the next scheduled change. A temporary
+/// change (`effective_to` set) splits the covering window so its price
+/// resumes at `effective_to`. Changes starting inside another scheduled
+/// window's start range are rejected rather than silently overwritten.
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_price_change(
+    conn: &mut PgConnection,
+    user_id: Option<Uuid>,
+    product_id: Uuid,
+    variant_id: Option<Uuid>,
+    price_cents: i64,
+    currency: CurrencyCode,
+    effective_from: DateTime<Utc>,
+    effective_to: Option<DateTime<Utc>>,
+) -> Result<ProductPrice, AppError> {
+    let conflict: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "SELECT effective_from FROM product_prices
+         WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2
+         AND effective_from >= $3
+         AND (effective_from = $3 OR effective_from < $4)
+         LIMIT 1",
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .bind(effective_from)
+    .bind(effective_to)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    if let Some(at) = conflict {
+        return Err(AppError::Validation(format!(
+            "A price change is already scheduled at {}",
+            at.to_rfc3339()
+        )));
+    }
+
+    let covering = sqlx::query_as::<_, ProductPrice>(
+        "SELECT * FROM product_prices
+         WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2
+         AND effective_from < $3 AND (effective_to IS NULL OR effective_to > $3)
+         FOR UPDATE",
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .bind(effective_from)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    if let Some(covering) = &covering {
+        sqlx::query("UPDATE product_prices SET effective_to = $1 WHERE id = $2")
+            .bind(effective_from)
+            .bind(covering.id)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+
+        // Resume the covering price after a temporary change
+        if let Some(to) = effective_to {
+            if covering.effective_to.is_none_or(|end| end > to) {
+                sqlx::query(
+                    "INSERT INTO product_prices (product_id, variant_id, price_cents, currency,
+                     effective_from, effective_to, created_by)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                )
+                .bind(product_id)
+                .bind(variant_id)
+                .bind(covering.price_cents)
+                .bind(covering.currency)
+                .bind(to)
+                .bind(covering.effective_to)
+                .bind(user_id)
+                .execute(&mut *conn)
+                .await
+                .map_err(overlap_error)?;
+            }
+        }
+    }
+
+    let effective_to = match effective_to {
+        Some(to) => Some(to),
+        None => sqlx::query_scalar(
+            "SELECT MIN(effective_from) FROM product_prices
+             WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2
+             AND effective_from > $3",
+        )
+        .bind(product_id)
+        .bind(variant_id)
+        .bind(effective_from)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(db_error)?,
+    };
+
+    let price = sqlx::query_as::<_, ProductPrice>(
+        "INSERT INTO product_prices (product_id, variant_id, price_cents, currency,
+         effective_from, effective_to, created_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING *",
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .bind(price_cents)
+    .bind(currency)
+    .bind(effective_from)
+    .bind(effective_to)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(overlap_error)?;
+
+    Ok(price)
+}
+
+/// Schedule a future price change for a product or one of its variants
+pub async fn schedule_price(
+    pool: &PgPool,
+    user_id: Uuid,
+    product_id: Uuid,
+    request: SchedulePriceRequest,
+) -> Result<ProductPrice, AppError> {
+    if request.price_cents < 0 {
+        return Err(AppError::Validation(format!(
+            "price_cents must not be negative, got {}",
+            request.price_cents
+        )));
+    }
+    if request.effective_from < Utc::now() {
+        return Err(AppError::Validation(
+            "Price changes can only be scheduled in the future".to_string(),
+        ));
+    }
+    if request.effective_to.is_some_and(|to| to <= request.effective_from) {
+        return Err(AppError::Validation(
+            "effective_to must be after effective_from".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .ok_or(AppError::ProductNotFound { id: product_id })?;
+
+    if let Some(variant_id) = request.variant_id {
+        let belongs: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM product_variants WHERE id = $1 AND product_id = $2)",
+        )
+        .bind(variant_id)
+        .bind(product_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        if !belongs {
+            return Err(AppError::Validation(format!(
+                "Variant {} does not belong to product {}",
+                variant_id, product_id
+            )));
+        }
+    }
+
+    let currency = match &request.currency {
+        Some(code) => code.parse()?,
+        None => product.currency,
+    };
+
+    let price = insert_price_change(
+        &mut tx,
+        Some(user_id),
+        product_id,
+        request.variant_id,
+        request.price_cents,
+        currency,
+        request.effective_from,
+        request.effective_to,
+    )
+    .await?;
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "product.price.schedule",
+        "product",
+        product_id,
+        None,
+        Some(json!(price)),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(price)
+}
+
+/// Full price history of a product and its variants, newest first
+pub async fn get_price_history(pool: &PgPool, product_id: Uuid) -> Result<Vec<ProductPrice>> {
+    let prices = sqlx::query_as::<_, ProductPrice>(
+        "SELECT * FROM product_prices
+         WHERE product_id = $1
+         ORDER BY variant_id NULLS FIRST, effective_from DESC",
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(prices)
+}
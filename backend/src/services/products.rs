@@ -1,18 +1,60 @@
 //! Product service
 
 use anyhow::Result;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use shared::{
+    parse_scanned_code, AppError, BarcodeMatchResponse, CurrencyCode, Money, ProductResponse,
+    ScannedCode,
+};
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
-use crate::db::{Category, Product};
+use crate::db::{Category, Product, ProductVariant};
+use crate::services::inventory::{self, MovementType, StockChange};
+use crate::services::{audit, availability, locations, pricing};
 
-pub async fn get_all_products(pool: &PgPool) -> Result<Vec<Product>> {
-    let products = sqlx::query_as::<_, Product>(
-        "SELECT * FROM products WHERE is_active = true ORDER BY name",
+#[derive(Debug, serde::Serialize)]
+pub struct ProductWithVariants {
+    #[serde(flatten)]
+    pub product: Product,
+    pub variants: Vec<ProductVariant>,
+    /// Whether the product's availability windows allow selling it at the listing time
+    pub available: bool,
+}
+
+impl From<ProductWithVariants> for ProductResponse {
+    fn from(listed: ProductWithVariants) -> Self {
+        Self {
+            variants: listed.variants.into_iter().map(Into::into).collect(),
+            available: listed.available,
+            ..listed.product.into()
+        }
+    }
+}
+
+/// Active products with prices resolved as of `at`.
+///
+/// Products outside their availability windows at `at` are left out unless
+/// `include_unavailable` is set, so terminals can show them dimmed instead.
+pub async fn get_all_products(
+    pool: &PgPool,
+    at: DateTime<Utc>,
+    include_unavailable: bool,
+) -> Result<Vec<Product>> {
+    let mut products = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products
+         WHERE is_active = true AND is_ingredient = false
+         AND ($1 OR is_available(id, category_id, $2))
+         ORDER BY sort_order, name",
     )
+    .bind(include_unavailable)
+    .bind(at)
     .fetch_all(pool)
     .await?;
 
+    pricing::apply_product_prices(pool, &mut products, at).await?;
+
     Ok(products)
 }
 
@@ -25,14 +67,26 @@ pub async fn get_product_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Product
     Ok(product)
 }
 
-pub async fn get_products_by_category(pool: &PgPool, category_id: Uuid) -> Result<Vec<Product>> {
-    let products = sqlx::query_as::<_, Product>(
-        "SELECT * FROM products WHERE category_id = $1 AND is_active = true ORDER BY name",
+pub async fn get_products_by_category(
+    pool: &PgPool,
+    category_id: Uuid,
+    at: DateTime<Utc>,
+    include_unavailable: bool,
+) -> Result<Vec<Product>> {
+    let mut products = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products
+         WHERE category_id = $1 AND is_active = true AND is_ingredient = false
+         AND ($2 OR is_available(id, category_id, $3))
+         ORDER BY sort_order, name",
     )
     .bind(category_id)
+    .bind(include_unavailable)
+    .bind(at)
     .fetch_all(pool)
     .await?;
 
+    pricing::apply_product_prices(pool, &mut products, at).await?;
+
     Ok(products)
 }
 
@@ -46,19 +100,818 @@ pub async fn get_all_categories(pool: &PgPool) -> Result<Vec<Category>> {
     Ok(categories)
 }
 
+/// Ranked product search with prefix matching and typo tolerance.
+///
+/// Candidates come from the weighted full-text document (prefix matches on
+/// every word) or trigram similarity on name, description and SKU, so both
+/// "capp" and "capucino" find "Cappuccino". A product whose barcode equals the
+/// query always matches and comes first. Other results are ordered by
+/// full-text rank plus similarity, weighting name above description above SKU.
 pub async fn search_products(pool: &PgPool, query: &str) -> Result<Vec<Product>> {
-    let search_pattern = format!("%{}%", query);
-    let products = sqlx::query_as::<_, Product>(
-        "SELECT * FROM products
-         WHERE is_active = true
-         AND (name ILIKE $1 OR description ILIKE $1 OR sku ILIKE $1 OR barcode = $2)
-         ORDER BY name
+    let term = query.trim();
+    let Some(prefix_query) = prefix_tsquery(term) else {
+        return Ok(Vec::new());
+    };
+
+    let mut products = sqlx::query_as::<_, Product>(
+        "SELECT p.* FROM products p,
+              (SELECT $1::text AS term, to_tsquery('simple', $2) AS tsq) q
+         WHERE p.is_active = true AND p.is_ingredient = false
+         AND (p.search_vector @@ q.tsq
+              OR q.term <% p.name
+              OR q.term <% p.description
+              OR p.sku % q.term
+              OR p.barcode = q.term)
+         ORDER BY p.barcode = q.term DESC NULLS LAST,
+                  ts_rank('{0.1, 0.25, 0.5, 1.0}', p.search_vector, q.tsq)
+                  + word_similarity(q.term, p.name)
+                  + 0.5 * word_similarity(q.term, coalesce(p.description, ''))
+                  + 0.25 * similarity(coalesce(p.sku, ''), q.term) DESC,
+                  p.name
          LIMIT 50",
     )
-    .bind(&search_pattern)
-    .bind(query)
+    .bind(term)
+    .bind(&prefix_query)
     .fetch_all(pool)
     .await?;
 
+    pricing::apply_product_prices(pool, &mut products, Utc::now()).await?;
+
     Ok(products)
 }
+
+/// Build a `to_tsquery` expression matching every word as a prefix (`latt:* & oat:*`)
+fn prefix_tsquery(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("{}:*", w.to_lowercase()))
+        .collect();
+
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" & "))
+    }
+}
+
+/// Attach each product's active variants, priced as of `at`, and its availability
+/// at `at`, for nesting in catalog responses
+pub async fn with_variants(
+    pool: &PgPool,
+    products: Vec<Product>,
+    at: DateTime<Utc>,
+) -> Result<Vec<ProductWithVariants>> {
+    let product_ids: Vec<Uuid> = products.iter().map(|p| p.id).collect();
+    let mut variants = sqlx::query_as::<_, ProductVariant>(
+        "SELECT * FROM product_variants
+         WHERE product_id = ANY($1) AND is_active = true
+         ORDER BY sort_order, name",
+    )
+    .bind(&product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    pricing::apply_variant_prices(pool, &mut variants, at).await?;
+    let unavailable = availability::unavailable_product_ids(pool, &product_ids, at).await?;
+
+    Ok(products
+        .into_iter()
+        .map(|product| {
+            let product_variants = variants
+                .iter()
+                .filter(|v| v.product_id == product.id)
+                .cloned()
+                .collect();
+            ProductWithVariants {
+                available: !unavailable.contains(&product.id),
+                product,
+                variants: product_variants,
+            }
+        })
+        .collect())
+}
+
+/// The single product (or variant) a scanned barcode resolves to
+#[derive(Debug, serde::Serialize)]
+pub struct BarcodeMatch {
+    pub product: Product,
+    pub variant: Option<ProductVariant>,
+    /// Price read from a weighed-item scale label, overriding the catalog price
+    pub embedded_price_cents: Option<i64>,
+}
+
+impl From<BarcodeMatch> for BarcodeMatchResponse {
+    fn from(found: BarcodeMatch) -> Self {
+        let currency = found.product.currency;
+        Self {
+            embedded_price: found
+                .embedded_price_cents
+                .map(|cents| Money::new(cents, currency)),
+            product: found.product.into(),
+            variant: found.variant.map(Into::into),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateProductRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub price_cents: i64,
+    /// Unit cost used for margin reporting
+    pub cost_cents: Option<i64>,
+    pub currency: Option<String>,
+    pub category_id: Option<Uuid>,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default)]
+    pub initial_quantity: i32,
+    pub reorder_level: Option<i32>,
+    pub reorder_quantity: Option<i32>,
+    /// Where the initial stock is held; defaults to the default location
+    pub location_id: Option<Uuid>,
+    /// Stocked for use in recipes rather than sold
+    #[serde(default)]
+    pub is_ingredient: bool,
+    /// Unit stock is counted in: `each` (default), `g` or `ml`
+    pub unit: Option<String>,
+}
+
+/// Fields left out are kept; `description`, `cost_cents`, `category_id`, `sku`
+/// and `barcode` are cleared by sending `null`
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct UpdateProductRequest {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub description: Option<Option<String>>,
+    pub price_cents: Option<i64>,
+    #[serde(default, deserialize_with = "present")]
+    pub cost_cents: Option<Option<i64>>,
+    pub currency: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub category_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "present")]
+    pub sku: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub barcode: Option<Option<String>>,
+    pub unit: Option<String>,
+}
+
+/// Read a field that is present, `null` included, as `Some`, so that only a
+/// missing field comes out as `None`
+fn present<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: serde::Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    <Option<T> as serde::Deserialize>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CategoryRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+/// New position for a product or category
+#[derive(Debug, serde::Deserialize)]
+pub struct SortOrderUpdate {
+    pub id: Uuid,
+    pub sort_order: i32,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+/// Resolve scanner input to exactly one active product or variant
+pub async fn lookup_barcode(pool: &PgPool, code: &str) -> Result<BarcodeMatch, AppError> {
+    let scanned = parse_scanned_code(code)?;
+
+    let (mut products, mut variants, embedded_price_cents) = match &scanned {
+        ScannedCode::Gtin { gtin, .. } => {
+            // LPAD truncates longer values, which could otherwise collide with a GTIN
+            let products = sqlx::query_as::<_, Product>(
+                "SELECT * FROM products
+                 WHERE is_active = true AND LPAD(barcode, 14, '0') = $1
+                 AND length(barcode) <= 14",
+            )
+            .bind(gtin)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?;
+
+            let variants = sqlx::query_as::<_, ProductVariant>(
+                "SELECT * FROM product_variants
+                 WHERE is_active = true AND LPAD(barcode, 14, '0') = $1
+                 AND length(barcode) <= 14",
+            )
+            .bind(gtin)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?;
+
+            (products, variants, None)
+        }
+        ScannedCode::WeighedItem {
+            item_prefix,
+            price_cents,
+        } => {
+            let products = sqlx::query_as::<_, Product>(
+                "SELECT * FROM products
+                 WHERE is_active = true
+                 AND LEFT(LPAD(barcode, 13, '0'), 1) = '2'
+                 AND LEFT(LPAD(barcode, 13, '0'), 7) = $1
+                 AND length(barcode) <= 13",
+            )
+            .bind(item_prefix)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?;
+
+            (products, Vec::new(), Some(*price_cents))
+        }
+    };
+
+    let now = Utc::now();
+    pricing::apply_product_prices(pool, &mut products, now)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    pricing::apply_variant_prices(pool, &mut variants, now)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    match (products.len(), variants.len()) {
+        (0, 0) => Err(AppError::BarcodeNotFound {
+            barcode: code.trim().to_string(),
+        }),
+        (1, 0) => Ok(BarcodeMatch {
+            product: products.remove(0),
+            variant: None,
+            embedded_price_cents,
+        }),
+        (0, 1) => {
+            let variant = variants.remove(0);
+            let product = get_product_by_id(pool, variant.product_id)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?
+                .ok_or(AppError::ProductNotFound {
+                    id: variant.product_id,
+                })?;
+
+            Ok(BarcodeMatch {
+                product,
+                variant: Some(variant),
+                embedded_price_cents,
+            })
+        }
+        _ => Err(AppError::AmbiguousBarcode {
+            barcode: code.trim().to_string(),
+        }),
+    }
+}
+
+fn validate_product_fields(name: &str, price_cents: i64) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::Validation("Product name is required".to_string()));
+    }
+    if price_cents < 0 {
+        return Err(AppError::Validation(format!(
+            "price_cents must not be negative, got {}",
+            price_cents
+        )));
+    }
+    Ok(())
+}
+
+fn validate_unit(unit: &str) -> Result<(), AppError> {
+    if !matches!(unit, "each" | "g" | "ml") {
+        return Err(AppError::Validation(format!(
+            "unit must be each, g or ml, got {}",
+            unit
+        )));
+    }
+    Ok(())
+}
+
+fn validate_cost(cost_cents: Option<i64>) -> Result<(), AppError> {
+    match cost_cents {
+        Some(cost) if cost < 0 => Err(AppError::Validation(format!(
+            "cost_cents must not be negative, got {}",
+            cost
+        ))),
+        _ => Ok(()),
+    }
+}
+
+async fn ensure_sku_available(
+    conn: &mut PgConnection,
+    sku: Option<&str>,
+    product_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let Some(sku) = sku else {
+        return Ok(());
+    };
+
+    let taken: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM products WHERE sku = $1 AND id IS DISTINCT FROM $2)
+         OR EXISTS (SELECT 1 FROM product_variants WHERE sku = $1)",
+    )
+    .bind(sku)
+    .bind(product_id)
+    .fetch_one(conn)
+    .await
+    .map_err(db_error)?;
+
+    if taken {
+        return Err(AppError::DuplicateSku {
+            sku: sku.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Map a write error, reporting a unique violation as `DuplicateSku`.
+///
+/// Another request can take the SKU between `ensure_sku_available` and the
+/// write; `sku` is the only unique column on `products`.
+fn sku_write_error(e: sqlx::Error, sku: Option<&str>) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some("23505") => {
+            AppError::DuplicateSku {
+                sku: sku.unwrap_or_default().to_string(),
+            }
+        }
+        _ => db_error(e),
+    }
+}
+
+/// Create a product together with its initial inventory row
+pub async fn create_product(
+    pool: &PgPool,
+    user_id: Uuid,
+    request: CreateProductRequest,
+) -> Result<Product, AppError> {
+    validate_product_fields(&request.name, request.price_cents)?;
+    let currency = match &request.currency {
+        Some(code) => code.parse()?,
+        None => CurrencyCode::USD,
+    };
+    validate_cost(request.cost_cents)?;
+    let unit = request.unit.as_deref().unwrap_or("each");
+    validate_unit(unit)?;
+    if request.initial_quantity < 0 {
+        return Err(AppError::Validation(
+            "initial_quantity must not be negative".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    ensure_sku_available(&mut tx, request.sku.as_deref(), None).await?;
+
+    let product = sqlx::query_as::<_, Product>(
+        "INSERT INTO products (name, description, price_cents, cost_cents, currency, category_id,
+         sku, barcode, sort_order, is_ingredient, unit)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         RETURNING *",
+    )
+    .bind(request.name.trim())
+    .bind(&request.description)
+    .bind(request.price_cents)
+    .bind(request.cost_cents)
+    .bind(currency)
+    .bind(request.category_id)
+    .bind(&request.sku)
+    .bind(&request.barcode)
+    .bind(request.sort_order)
+    .bind(request.is_ingredient)
+    .bind(unit)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| sku_write_error(e, request.sku.as_deref()))?;
+
+    pricing::insert_price_change(
+        &mut tx,
+        Some(user_id),
+        product.id,
+        None,
+        product.price_cents,
+        product.currency,
+        Utc::now(),
+        None,
+    )
+    .await?;
+
+    let location_id = locations::resolve_location(&mut tx, request.location_id).await?;
+    sqlx::query(
+        "INSERT INTO inventory (product_id, location_id, quantity, reorder_level, reorder_quantity)
+         VALUES ($1, $2, 0, COALESCE($3, 10), COALESCE($4, 50))",
+    )
+    .bind(product.id)
+    .bind(location_id)
+    .bind(request.reorder_level)
+    .bind(request.reorder_quantity)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    if request.initial_quantity > 0 {
+        inventory::record_movement(
+            &mut tx,
+            StockChange {
+                product_id: product.id,
+                variant_id: None,
+                location_id,
+                movement_type: MovementType::Adjustment,
+                quantity_delta: request.initial_quantity,
+                reason: Some("Initial stock".to_string()),
+                reason_code: None,
+                user_id: Some(user_id),
+                order_id: None,
+            },
+        )
+        .await?;
+    }
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "product.create",
+        "product",
+        product.id,
+        None,
+        Some(json!(product)),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(product)
+}
+
+pub async fn update_product(
+    pool: &PgPool,
+    user_id: Uuid,
+    product_id: Uuid,
+    request: UpdateProductRequest,
+) -> Result<Product, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let old = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1 FOR UPDATE")
+        .bind(product_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .ok_or(AppError::ProductNotFound { id: product_id })?;
+
+    let name = request.name.unwrap_or_else(|| old.name.clone());
+    let price_cents = request.price_cents.unwrap_or(old.price_cents);
+    validate_product_fields(&name, price_cents)?;
+    let currency = match &request.currency {
+        Some(code) => code.parse()?,
+        None => old.currency,
+    };
+    let description = request
+        .description
+        .unwrap_or_else(|| old.description.clone());
+    let barcode = request.barcode.unwrap_or_else(|| old.barcode.clone());
+    let cost_cents = request.cost_cents.unwrap_or(old.cost_cents);
+    validate_cost(cost_cents)?;
+    let unit = request.unit.unwrap_or_else(|| old.unit.clone());
+    validate_unit(&unit)?;
+
+    let sku = request.sku.unwrap_or_else(|| old.sku.clone());
+    if sku != old.sku {
+        ensure_sku_available(&mut tx, sku.as_deref(), Some(product_id)).await?;
+    }
+
+    let product = sqlx::query_as::<_, Product>(
+        "UPDATE products
+         SET name = $1, description = $2, price_cents = $3, currency = $4,
+             category_id = $5, sku = $6, barcode = $7, cost_cents = $8, unit = $9
+         WHERE id = $10
+         RETURNING *",
+    )
+    .bind(name.trim())
+    .bind(&description)
+    .bind(price_cents)
+    .bind(currency)
+    .bind(request.category_id.unwrap_or(old.category_id))
+    .bind(&sku)
+    .bind(&barcode)
+    .bind(cost_cents)
+    .bind(&unit)
+    .bind(product_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| sku_write_error(e, sku.as_deref()))?;
+
+    // A direct price edit takes effect immediately in the price history
+    if product.price_cents != old.price_cents || product.currency != old.currency {
+        pricing::insert_price_change(
+            &mut tx,
+            Some(user_id),
+            product_id,
+            None,
+            product.price_cents,
+            product.currency,
+            Utc::now(),
+            None,
+        )
+        .await?;
+    }
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "product.update",
+        "product",
+        product_id,
+        Some(json!(old)),
+        Some(json!(product)),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(product)
+}
+
+/// Archive (`is_active = false`) or restore a product
+pub async fn set_product_active(
+    pool: &PgPool,
+    user_id: Uuid,
+    product_id: Uuid,
+    is_active: bool,
+) -> Result<Product, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let product = sqlx::query_as::<_, Product>(
+        "UPDATE products SET is_active = $1 WHERE id = $2 RETURNING *",
+    )
+    .bind(is_active)
+    .bind(product_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .ok_or(AppError::ProductNotFound { id: product_id })?;
+
+    let action = if is_active { "product.restore" } else { "product.archive" };
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        action,
+        "product",
+        product_id,
+        Some(json!({ "is_active": !is_active })),
+        Some(json!({ "is_active": is_active })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(product)
+}
+
+pub async fn reorder_products(
+    pool: &PgPool,
+    user_id: Uuid,
+    updates: Vec<SortOrderUpdate>,
+) -> Result<(), AppError> {
+    reorder(pool, user_id, "products", "product", updates).await
+}
+
+pub async fn create_category(
+    pool: &PgPool,
+    user_id: Uuid,
+    request: CategoryRequest,
+) -> Result<Category, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::Validation("Category name is required".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let category = sqlx::query_as::<_, Category>(
+        "INSERT INTO categories (name, description, sort_order)
+         VALUES ($1, $2, $3)
+         RETURNING *",
+    )
+    .bind(request.name.trim())
+    .bind(&request.description)
+    .bind(request.sort_order)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "category.create",
+        "category",
+        category.id,
+        None,
+        Some(json!(category)),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(category)
+}
+
+pub async fn update_category(
+    pool: &PgPool,
+    user_id: Uuid,
+    category_id: Uuid,
+    request: CategoryRequest,
+) -> Result<Category, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::Validation("Category name is required".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let old = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = $1 FOR UPDATE")
+        .bind(category_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .ok_or(AppError::CategoryNotFound { id: category_id })?;
+
+    let category = sqlx::query_as::<_, Category>(
+        "UPDATE categories SET name = $1, description = $2, sort_order = $3
+         WHERE id = $4
+         RETURNING *",
+    )
+    .bind(request.name.trim())
+    .bind(&request.description)
+    .bind(request.sort_order)
+    .bind(category_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "category.update",
+        "category",
+        category_id,
+        Some(json!(old)),
+        Some(json!(category)),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(category)
+}
+
+/// Archive (`is_active = false`) or restore a category
+pub async fn set_category_active(
+    pool: &PgPool,
+    user_id: Uuid,
+    category_id: Uuid,
+    is_active: bool,
+) -> Result<Category, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let category = sqlx::query_as::<_, Category>(
+        "UPDATE categories SET is_active = $1 WHERE id = $2 RETURNING *",
+    )
+    .bind(is_active)
+    .bind(category_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .ok_or(AppError::CategoryNotFound { id: category_id })?;
+
+    let action = if is_active { "category.restore" } else { "category.archive" };
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        action,
+        "category",
+        category_id,
+        Some(json!({ "is_active": !is_active })),
+        Some(json!({ "is_active": is_active })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(category)
+}
+
+pub async fn reorder_categories(
+    pool: &PgPool,
+    user_id: Uuid,
+    updates: Vec<SortOrderUpdate>,
+) -> Result<(), AppError> {
+    reorder(pool, user_id, "categories", "category", updates).await
+}
+
+/// Apply new `sort_order` values in one transaction, auditing each row that moved
+async fn reorder(
+    pool: &PgPool,
+    user_id: Uuid,
+    table: &str,
+    entity_type: &str,
+    updates: Vec<SortOrderUpdate>,
+) -> Result<(), AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    for update in updates {
+        let old_sort_order: Option<i32> = sqlx::query_scalar(&format!(
+            "UPDATE {table} t SET sort_order = $1
+             FROM (SELECT id, sort_order FROM {table} WHERE id = $2 FOR UPDATE) old
+             WHERE t.id = old.id
+             RETURNING old.sort_order"
+        ))
+        .bind(update.sort_order)
+        .bind(update.id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        let old_sort_order = old_sort_order.ok_or_else(|| {
+            AppError::Validation(format!("Unknown {} in reorder: {}", entity_type, update.id))
+        })?;
+
+        audit::record(
+            &mut tx,
+            Some(user_id),
+            &format!("{}.reorder", entity_type),
+            entity_type,
+            update.id,
+            Some(json!({ "sort_order": old_sort_order })),
+            Some(json!({ "sort_order": update.sort_order })),
+        )
+        .await
+        .map_err(db_error)?;
+    }
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_tsquery_matches_every_word() {
+        assert_eq!(
+            prefix_tsquery("Oat Latte"),
+            Some("oat:* & latte:*".to_string())
+        );
+        assert_eq!(prefix_tsquery("lat"), Some("lat:*".to_string()));
+    }
+
+    #[test]
+    fn test_prefix_tsquery_drops_operators_and_punctuation() {
+        assert_eq!(
+            prefix_tsquery("caffè-latte & (oat)!"),
+            Some("caffè:* & latte:* & oat:*".to_string())
+        );
+        assert_eq!(prefix_tsquery("  !:* | "), None);
+        assert_eq!(prefix_tsquery(""), None);
+    }
+
+    #[test]
+    fn test_update_request_tells_null_from_missing() {
+        let category_id = Uuid::from_u128(7);
+        let parse = |body: serde_json::Value| -> UpdateProductRequest {
+            serde_json::from_value(body).unwrap()
+        };
+
+        assert_eq!(parse(json!({})).category_id, None);
+        assert_eq!(
+            parse(json!({ "category_id": null })).category_id,
+            Some(None)
+        );
+        assert_eq!(
+            parse(json!({ "category_id": category_id })).category_id,
+            Some(Some(category_id))
+        );
+
+        let cleared = parse(json!({ "sku": null, "cost_cents": null, "name": "Mocha" }));
+        assert_eq!(cleared.sku, Some(None));
+        assert_eq!(cleared.cost_cents, Some(None));
+        assert_eq!(cleared.description, None);
+        assert_eq!(cleared.name.as_deref(), Some("Mocha"));
+    }
+}
@@ -0,0 +1,564 @@
+//! Purchase orders
+//!
+//! Orders move `draft → sent → partially_received → received` (or to
+//! `cancelled` before anything is received). Receiving stock posts restock
+//! movements to the ledger at the order's location.
+
+use chrono::Utc;
+use serde_json::json;
+use shared::AppError;
+use sqlx::{PgConnection, PgPool};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::db::{PurchaseOrder, PurchaseOrderLine};
+use crate::services::lots::{self, LotDetails};
+use crate::services::{audit, locations};
+use crate::services::inventory::{self, MovementType, StockChange};
+
+/// Statuses whose lines still count as stock on order
+pub(crate) const OPEN_STATUSES: [&str; 3] = ["draft", "sent", "partially_received"];
+
+#[derive(Debug, serde::Serialize)]
+pub struct PurchaseOrderWithLines {
+    pub order: PurchaseOrder,
+    pub lines: Vec<PurchaseOrderLine>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreatePurchaseOrderRequest {
+    pub supplier_id: Uuid,
+    /// Location receiving the stock; defaults to the default location
+    pub location_id: Option<Uuid>,
+    pub notes: Option<String>,
+    pub lines: Vec<PurchaseOrderLineRequest>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PurchaseOrderLineRequest {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    /// Units wanted; rounded up to whole packs
+    pub quantity: i32,
+    /// Defaults to the supplier's cost for the item
+    pub unit_cost_cents: Option<i64>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ReceiveRequest {
+    pub lines: Vec<ReceiveLine>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ReceiveLine {
+    pub line_id: Uuid,
+    pub quantity: i32,
+    #[serde(flatten)]
+    pub lot: LotDetails,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PurchaseOrderFilter {
+    pub status: Option<String>,
+    pub supplier_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+}
+
+/// Low-stock item that could not be drafted
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct UnassignedItem {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub location_id: Uuid,
+    pub name: String,
+    pub quantity: i32,
+    pub reorder_level: i32,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DraftResult {
+    pub orders: Vec<PurchaseOrderWithLines>,
+    /// Low-stock items with no active supplier
+    pub unassigned: Vec<UnassignedItem>,
+}
+
+/// One line to draft, with the supplier terms it will be ordered on
+#[derive(Debug, sqlx::FromRow)]
+pub struct DraftLine {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub supplier_id: Uuid,
+    /// Location the stock is ordered for
+    pub location_id: Uuid,
+    pub cost_cents: i64,
+    pub pack_size: i32,
+    /// Units wanted before rounding to packs
+    pub quantity: i32,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+/// Round a unit quantity up to whole packs, ordering at least one pack
+pub fn round_to_packs(quantity: i32, pack_size: i32) -> i32 {
+    let pack_size = pack_size.max(1);
+    let packs = (quantity.max(1) + pack_size - 1) / pack_size;
+    packs * pack_size
+}
+
+async fn load_lines(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+) -> Result<Vec<PurchaseOrderLine>, AppError> {
+    sqlx::query_as::<_, PurchaseOrderLine>(
+        "SELECT * FROM purchase_order_lines WHERE purchase_order_id = $1 ORDER BY created_at, id",
+    )
+    .bind(order_id)
+    .fetch_all(conn)
+    .await
+    .map_err(db_error)
+}
+
+async fn lock_order(conn: &mut PgConnection, order_id: Uuid) -> Result<PurchaseOrder, AppError> {
+    sqlx::query_as::<_, PurchaseOrder>("SELECT * FROM purchase_orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::NotFound(format!("Purchase order {}", order_id)))
+}
+
+pub async fn list_purchase_orders(
+    pool: &PgPool,
+    filter: &PurchaseOrderFilter,
+) -> Result<Vec<PurchaseOrder>, AppError> {
+    sqlx::query_as::<_, PurchaseOrder>(
+        "SELECT * FROM purchase_orders
+         WHERE ($1::TEXT IS NULL OR status = $1)
+         AND ($2::UUID IS NULL OR supplier_id = $2)
+         AND ($3::UUID IS NULL OR location_id = $3)
+         ORDER BY created_at DESC",
+    )
+    .bind(&filter.status)
+    .bind(filter.supplier_id)
+    .bind(filter.location_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+pub async fn get_purchase_order(
+    pool: &PgPool,
+    order_id: Uuid,
+) -> Result<PurchaseOrderWithLines, AppError> {
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+
+    let order = sqlx::query_as::<_, PurchaseOrder>("SELECT * FROM purchase_orders WHERE id = $1")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::NotFound(format!("Purchase order {}", order_id)))?;
+    let lines = load_lines(&mut conn, order_id).await?;
+
+    Ok(PurchaseOrderWithLines { order, lines })
+}
+
+/// Create a draft order by hand; costs and pack sizes come from the supplier links
+pub async fn create_purchase_order(
+    pool: &PgPool,
+    user_id: Uuid,
+    request: CreatePurchaseOrderRequest,
+) -> Result<PurchaseOrderWithLines, AppError> {
+    if request.lines.is_empty() {
+        return Err(AppError::Validation(
+            "A purchase order needs at least one line".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let location_id = locations::resolve_location(&mut tx, request.location_id).await?;
+
+    let mut lines = Vec::with_capacity(request.lines.len());
+    for line in &request.lines {
+        if line.quantity <= 0 {
+            return Err(AppError::InvalidQuantity {
+                quantity: line.quantity.max(0) as u32,
+            });
+        }
+
+        let terms: Option<(i64, i32)> = sqlx::query_as(
+            "SELECT cost_cents, pack_size FROM supplier_products
+             WHERE supplier_id = $1 AND product_id = $2 AND variant_id IS NOT DISTINCT FROM $3",
+        )
+        .bind(request.supplier_id)
+        .bind(line.product_id)
+        .bind(line.variant_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        let (cost_cents, pack_size) = terms.ok_or_else(|| {
+            AppError::Validation(format!(
+                "Product {} is not supplied by supplier {}",
+                line.product_id, request.supplier_id
+            ))
+        })?;
+
+        lines.push(DraftLine {
+            product_id: line.product_id,
+            variant_id: line.variant_id,
+            supplier_id: request.supplier_id,
+            location_id,
+            cost_cents: line.unit_cost_cents.unwrap_or(cost_cents),
+            pack_size,
+            quantity: line.quantity,
+        });
+    }
+
+    let order = insert_draft(
+        &mut tx,
+        user_id,
+        request.supplier_id,
+        location_id,
+        request.notes.as_deref(),
+        &lines,
+    )
+    .await?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(order)
+}
+
+/// Draft one order per preferred supplier and location for every low-stock item
+/// not already on order there.
+///
+/// Each item is ordered at its `reorder_quantity`, rounded up to whole packs.
+pub async fn draft_from_low_stock(pool: &PgPool, user_id: Uuid) -> Result<DraftResult, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let candidates = sqlx::query_as::<_, DraftLine>(
+        "SELECT DISTINCT ON (i.id)
+                i.product_id, i.variant_id, sp.supplier_id, i.location_id, sp.cost_cents, sp.pack_size,
+                i.reorder_quantity AS quantity
+         FROM inventory i
+         JOIN products p ON p.id = i.product_id AND p.is_active = true
+         JOIN supplier_products sp
+              ON sp.product_id = i.product_id AND sp.variant_id IS NOT DISTINCT FROM i.variant_id
+         JOIN suppliers s ON s.id = sp.supplier_id AND s.is_active = true
+         WHERE i.quantity <= i.reorder_level AND NOT is_recipe_tracked(i.product_id)
+         AND NOT EXISTS (
+             SELECT 1 FROM purchase_order_lines l
+             JOIN purchase_orders po ON po.id = l.purchase_order_id
+             WHERE po.status = ANY($1) AND po.location_id = i.location_id
+             AND l.product_id = i.product_id AND l.variant_id IS NOT DISTINCT FROM i.variant_id
+             AND l.quantity_received < l.quantity_ordered
+         )
+         ORDER BY i.id, sp.is_preferred DESC, sp.cost_cents",
+    )
+    .bind(&OPEN_STATUSES[..])
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let unassigned = sqlx::query_as::<_, UnassignedItem>(
+        "SELECT i.product_id, i.variant_id, i.location_id,
+                CASE WHEN v.id IS NULL THEN p.name ELSE p.name || ' (' || v.name || ')' END AS name,
+                i.quantity, i.reorder_level
+         FROM inventory i
+         JOIN products p ON p.id = i.product_id AND p.is_active = true
+         LEFT JOIN product_variants v ON v.id = i.variant_id
+         WHERE i.quantity <= i.reorder_level AND NOT is_recipe_tracked(i.product_id)
+         AND NOT EXISTS (
+             SELECT 1 FROM supplier_products sp
+             JOIN suppliers s ON s.id = sp.supplier_id AND s.is_active = true
+             WHERE sp.product_id = i.product_id AND sp.variant_id IS NOT DISTINCT FROM i.variant_id
+         )
+         ORDER BY p.name",
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let orders =
+        insert_drafts_by_supplier(&mut tx, user_id, "Drafted from low stock", candidates).await?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(DraftResult { orders, unassigned })
+}
+
+/// Group lines by supplier and location and insert one draft per group
+pub async fn insert_drafts_by_supplier(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    notes: &str,
+    lines: Vec<DraftLine>,
+) -> Result<Vec<PurchaseOrderWithLines>, AppError> {
+    let mut by_supplier: BTreeMap<(Uuid, Uuid), Vec<DraftLine>> = BTreeMap::new();
+    for line in lines {
+        by_supplier
+            .entry((line.supplier_id, line.location_id))
+            .or_default()
+            .push(line);
+    }
+
+    let mut orders = Vec::with_capacity(by_supplier.len());
+    for ((supplier_id, location_id), lines) in by_supplier {
+        orders.push(
+            insert_draft(
+                conn,
+                user_id,
+                supplier_id,
+                location_id,
+                Some(notes),
+                &lines,
+            )
+            .await?,
+        );
+    }
+
+    Ok(orders)
+}
+
+async fn insert_draft(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    supplier_id: Uuid,
+    location_id: Uuid,
+    notes: Option<&str>,
+    lines: &[DraftLine],
+) -> Result<PurchaseOrderWithLines, AppError> {
+    let order = sqlx::query_as::<_, PurchaseOrder>(
+        "INSERT INTO purchase_orders (supplier_id, location_id, notes, created_by)
+         VALUES ($1, $2, $3, $4)
+         RETURNING *",
+    )
+    .bind(supplier_id)
+    .bind(location_id)
+    .bind(notes)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let mut inserted = Vec::with_capacity(lines.len());
+    for line in lines {
+        let row = sqlx::query_as::<_, PurchaseOrderLine>(
+            "INSERT INTO purchase_order_lines (purchase_order_id, product_id, variant_id,
+             quantity_ordered, pack_size, unit_cost_cents)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING *",
+        )
+        .bind(order.id)
+        .bind(line.product_id)
+        .bind(line.variant_id)
+        .bind(round_to_packs(line.quantity, line.pack_size))
+        .bind(line.pack_size)
+        .bind(line.cost_cents)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(db_error)?;
+        inserted.push(row);
+    }
+
+    audit::record(
+        conn,
+        Some(user_id),
+        "purchase_order.create",
+        "purchase_order",
+        order.id,
+        None,
+        Some(json!({ "order": order, "lines": inserted })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    Ok(PurchaseOrderWithLines {
+        order,
+        lines: inserted,
+    })
+}
+
+/// Mark a draft as sent to the supplier
+pub async fn send_purchase_order(
+    pool: &PgPool,
+    user_id: Uuid,
+    order_id: Uuid,
+) -> Result<PurchaseOrder, AppError> {
+    transition(pool, user_id, order_id, &["draft"], "sent").await
+}
+
+/// Cancel an order that has not received anything yet
+pub async fn cancel_purchase_order(
+    pool: &PgPool,
+    user_id: Uuid,
+    order_id: Uuid,
+) -> Result<PurchaseOrder, AppError> {
+    transition(pool, user_id, order_id, &["draft", "sent"], "cancelled").await
+}
+
+async fn transition(
+    pool: &PgPool,
+    user_id: Uuid,
+    order_id: Uuid,
+    from: &[&str],
+    to: &str,
+) -> Result<PurchaseOrder, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let old = lock_order(&mut tx, order_id).await?;
+    if !from.contains(&old.status.as_str()) {
+        return Err(AppError::Validation(format!(
+            "Purchase order {} is {} and cannot be {}",
+            old.po_number, old.status, to
+        )));
+    }
+
+    let order = sqlx::query_as::<_, PurchaseOrder>(
+        "UPDATE purchase_orders
+         SET status = $1, sent_at = CASE WHEN $1 = 'sent' THEN NOW() ELSE sent_at END
+         WHERE id = $2
+         RETURNING *",
+    )
+    .bind(to)
+    .bind(order_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        &format!("purchase_order.{}", to),
+        "purchase_order",
+        order_id,
+        Some(json!({ "status": old.status })),
+        Some(json!({ "status": order.status })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(order)
+}
+
+/// Receive stock against a sent order, posting restock movements.
+///
+/// The order becomes `received` once every line is fully received.
+pub async fn receive_purchase_order(
+    pool: &PgPool,
+    user_id: Uuid,
+    order_id: Uuid,
+    request: ReceiveRequest,
+) -> Result<PurchaseOrderWithLines, AppError> {
+    if request.lines.is_empty() {
+        return Err(AppError::Validation("Nothing to receive".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let order = lock_order(&mut tx, order_id).await?;
+    if order.status != "sent" && order.status != "partially_received" {
+        return Err(AppError::Validation(format!(
+            "Purchase order {} is {} and cannot be received",
+            order.po_number, order.status
+        )));
+    }
+
+    for received in &request.lines {
+        if received.quantity <= 0 {
+            return Err(AppError::InvalidQuantity {
+                quantity: received.quantity.max(0) as u32,
+            });
+        }
+
+        let line = sqlx::query_as::<_, PurchaseOrderLine>(
+            "SELECT * FROM purchase_order_lines
+             WHERE id = $1 AND purchase_order_id = $2
+             FOR UPDATE",
+        )
+        .bind(received.line_id)
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            AppError::Validation(format!(
+                "Line {} is not on purchase order {}",
+                received.line_id, order.po_number
+            ))
+        })?;
+
+        let outstanding = line.quantity_ordered - line.quantity_received;
+        if received.quantity > outstanding {
+            return Err(AppError::Validation(format!(
+                "Cannot receive {} units on line {}: only {} outstanding",
+                received.quantity, line.id, outstanding
+            )));
+        }
+
+        sqlx::query(
+            "UPDATE purchase_order_lines SET quantity_received = quantity_received + $1 WHERE id = $2",
+        )
+        .bind(received.quantity)
+        .bind(line.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        let movement = inventory::record_movement(
+            &mut tx,
+            StockChange {
+                product_id: line.product_id,
+                variant_id: line.variant_id,
+                location_id: order.location_id,
+                movement_type: MovementType::Restock,
+                quantity_delta: received.quantity,
+                reason: Some(format!("Received on {}", order.po_number)),
+                reason_code: None,
+                user_id: Some(user_id),
+                order_id: None,
+            },
+        )
+        .await?;
+        if !received.lot.is_empty() {
+            lots::receive_lot(&mut tx, &movement, &received.lot).await?;
+        }
+    }
+
+    let lines = load_lines(&mut tx, order_id).await?;
+    let complete = lines.iter().all(|l| l.quantity_received >= l.quantity_ordered);
+
+    let order = sqlx::query_as::<_, PurchaseOrder>(
+        "UPDATE purchase_orders SET status = $1, received_at = $2 WHERE id = $3 RETURNING *",
+    )
+    .bind(if complete { "received" } else { "partially_received" })
+    .bind(complete.then(Utc::now))
+    .bind(order_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "purchase_order.receive",
+        "purchase_order",
+        order_id,
+        None,
+        Some(json!({
+            "status": order.status,
+            "lines": request.lines.iter().map(|l| json!({
+                "line_id": l.line_id,
+                "quantity": l.quantity,
+            })).collect::<Vec<_>>(),
+        })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(PurchaseOrderWithLines { order, lines })
+}
@@ -0,0 +1,468 @@
+//! Ingredients and recipes
+//!
+//! See the `recipes` migration for how product, variant and modifier lines
+//! combine. A product with a recipe is not stocked itself; selling it depletes
+//! its ingredients.
+
+use serde_json::json;
+use shared::AppError;
+use sqlx::{PgConnection, PgPool};
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+
+use crate::db::RecipeLine;
+use crate::services::audit;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RecipeLineRequest {
+    pub ingredient_id: Uuid,
+    /// In the ingredient's unit, per item sold
+    pub quantity: i32,
+}
+
+/// What a recipe is attached to
+#[derive(Debug, Clone, Copy)]
+pub enum RecipeTarget {
+    Product(Uuid),
+    Variant { product_id: Uuid, variant_id: Uuid },
+    ModifierOption(Uuid),
+}
+
+impl RecipeTarget {
+    fn ids(self) -> (Option<Uuid>, Option<Uuid>, Option<Uuid>) {
+        match self {
+            RecipeTarget::Product(id) => (Some(id), None, None),
+            RecipeTarget::Variant {
+                product_id,
+                variant_id,
+            } => (Some(product_id), Some(variant_id), None),
+            RecipeTarget::ModifierOption(id) => (None, None, Some(id)),
+        }
+    }
+
+    fn entity(self) -> (&'static str, Uuid) {
+        match self {
+            RecipeTarget::Product(id) => ("product", id),
+            RecipeTarget::Variant { variant_id, .. } => ("product_variant", variant_id),
+            RecipeTarget::ModifierOption(id) => ("modifier_option", id),
+        }
+    }
+}
+
+/// Ingredient with its stock summed over all locations
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct IngredientStock {
+    pub id: Uuid,
+    pub name: String,
+    pub sku: Option<String>,
+    pub unit: String,
+    /// Cost of one unit
+    pub cost_cents: Option<i64>,
+    pub quantity: i64,
+    /// Locations at or below their reorder level
+    pub low_stock_locations: i64,
+}
+
+/// Ingredients consumed by one order line
+#[derive(Debug)]
+pub struct Depletion {
+    /// Whether the product is made from a recipe rather than stocked itself
+    pub recipe_tracked: bool,
+    /// Ingredient ids with the units to take out of stock
+    pub ingredients: Vec<(Uuid, i32)>,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+/// Active ingredients, with total stock on hand
+pub async fn list_ingredients(pool: &PgPool) -> Result<Vec<IngredientStock>, AppError> {
+    sqlx::query_as::<_, IngredientStock>(
+        "SELECT p.id, p.name, p.sku, p.unit, p.cost_cents,
+                COALESCE(SUM(i.quantity), 0)::BIGINT AS quantity,
+                COUNT(i.id) FILTER (WHERE i.quantity <= i.reorder_level) AS low_stock_locations
+         FROM products p
+         LEFT JOIN inventory i ON i.product_id = p.id AND i.variant_id IS NULL
+         WHERE p.is_ingredient = true AND p.is_active = true
+         GROUP BY p.id
+         ORDER BY p.name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+pub async fn is_recipe_tracked(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+) -> Result<bool, AppError> {
+    sqlx::query_scalar("SELECT is_recipe_tracked($1)")
+        .bind(product_id)
+        .fetch_one(conn)
+        .await
+        .map_err(db_error)
+}
+
+/// Lines attached directly to a product, variant or modifier option
+pub async fn get_recipe(pool: &PgPool, target: RecipeTarget) -> Result<Vec<RecipeLine>, AppError> {
+    let (product_id, variant_id, option_id) = target.ids();
+    sqlx::query_as::<_, RecipeLine>(
+        "SELECT * FROM recipe_lines
+         WHERE product_id IS NOT DISTINCT FROM $1 AND variant_id IS NOT DISTINCT FROM $2
+         AND modifier_option_id IS NOT DISTINCT FROM $3
+         ORDER BY created_at",
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .bind(option_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+async fn ensure_target_exists(
+    conn: &mut PgConnection,
+    target: RecipeTarget,
+) -> Result<(), AppError> {
+    let (exists, is_ingredient): (bool, bool) = match target {
+        RecipeTarget::Product(id) => {
+            sqlx::query_as("SELECT true, is_ingredient FROM products WHERE id = $1").bind(id)
+        }
+        RecipeTarget::Variant {
+            product_id,
+            variant_id,
+        } => sqlx::query_as(
+            "SELECT true, p.is_ingredient FROM product_variants v
+             JOIN products p ON p.id = v.product_id
+             WHERE v.id = $1 AND v.product_id = $2",
+        )
+        .bind(variant_id)
+        .bind(product_id),
+        RecipeTarget::ModifierOption(id) => {
+            sqlx::query_as("SELECT true, false FROM modifier_options WHERE id = $1").bind(id)
+        }
+    }
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_error)?
+    .unwrap_or((false, false));
+
+    if !exists {
+        return Err(match target {
+            RecipeTarget::Product(id) => AppError::ProductNotFound { id },
+            RecipeTarget::Variant {
+                product_id,
+                variant_id,
+            } => AppError::Validation(format!(
+                "Variant {} does not belong to product {}",
+                variant_id, product_id
+            )),
+            RecipeTarget::ModifierOption(id) => {
+                AppError::NotFound(format!("Modifier option {}", id))
+            }
+        });
+    }
+    if is_ingredient {
+        return Err(AppError::Validation(
+            "Ingredients cannot have recipes of their own".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that a product's recipe lines leave every variant with something to deplete,
+/// and that a product only becomes recipe-tracked once its own stock is gone.
+async fn check_product_recipe(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    was_tracked: bool,
+) -> Result<(), AppError> {
+    if !is_recipe_tracked(&mut *conn, product_id).await? {
+        return Ok(());
+    }
+
+    let uncovered: Vec<String> = sqlx::query_scalar(
+        "SELECT v.name FROM product_variants v
+         WHERE v.product_id = $1
+         AND NOT EXISTS (SELECT 1 FROM recipe_lines r
+                         WHERE r.product_id = $1 AND r.variant_id IS NULL)
+         AND NOT EXISTS (SELECT 1 FROM recipe_lines r WHERE r.variant_id = v.id)
+         ORDER BY v.sort_order, v.name",
+    )
+    .bind(product_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+    if !uncovered.is_empty() {
+        return Err(AppError::Validation(format!(
+            "Variants {} would have no recipe; give the product a recipe first or give every variant one",
+            uncovered.join(", ")
+        )));
+    }
+
+    // Once tracked, sales stop taking from the product's own stock rows
+    if !was_tracked {
+        let on_hand: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(quantity), 0)::BIGINT FROM inventory
+             WHERE product_id = $1 AND quantity > 0",
+        )
+        .bind(product_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(db_error)?;
+        if on_hand > 0 {
+            return Err(AppError::Validation(format!(
+                "Product {} still has {} units in stock; adjust them out before giving it a recipe",
+                product_id, on_hand
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Replace the recipe of a product, variant or modifier option; an empty list removes it.
+///
+/// Product and variant quantities must be positive; modifier option quantities
+/// may be negative to take an ingredient out of the recipe. A product with
+/// variants needs either a recipe of its own or one on every variant, and
+/// cannot gain its first recipe while it still has stock on hand.
+pub async fn set_recipe(
+    pool: &PgPool,
+    user_id: Uuid,
+    target: RecipeTarget,
+    lines: Vec<RecipeLineRequest>,
+) -> Result<Vec<RecipeLine>, AppError> {
+    let allow_negative = matches!(target, RecipeTarget::ModifierOption(_));
+    let mut seen = HashSet::new();
+    for line in &lines {
+        if line.quantity == 0 || (line.quantity < 0 && !allow_negative) {
+            return Err(AppError::Validation(format!(
+                "Recipe quantity for ingredient {} must be positive, got {}",
+                line.ingredient_id, line.quantity
+            )));
+        }
+        if !seen.insert(line.ingredient_id) {
+            return Err(AppError::Validation(format!(
+                "Ingredient {} is listed more than once",
+                line.ingredient_id
+            )));
+        }
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    ensure_target_exists(&mut tx, target).await?;
+
+    let ingredient_ids: Vec<Uuid> = seen.into_iter().collect();
+    let found: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM products WHERE id = ANY($1) AND is_ingredient = true",
+    )
+    .bind(&ingredient_ids)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    if found as usize != ingredient_ids.len() {
+        return Err(AppError::Validation(
+            "Recipe lines must refer to ingredients".to_string(),
+        ));
+    }
+
+    let (product_id, variant_id, option_id) = target.ids();
+    let was_tracked = match product_id {
+        Some(id) => is_recipe_tracked(&mut tx, id).await?,
+        None => false,
+    };
+    let old = sqlx::query_as::<_, RecipeLine>(
+        "DELETE FROM recipe_lines
+         WHERE product_id IS NOT DISTINCT FROM $1 AND variant_id IS NOT DISTINCT FROM $2
+         AND modifier_option_id IS NOT DISTINCT FROM $3
+         RETURNING *",
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .bind(option_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let mut created = Vec::with_capacity(lines.len());
+    for line in lines {
+        let row = sqlx::query_as::<_, RecipeLine>(
+            "INSERT INTO recipe_lines (product_id, variant_id, modifier_option_id, ingredient_id, quantity)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING *",
+        )
+        .bind(product_id)
+        .bind(variant_id)
+        .bind(option_id)
+        .bind(line.ingredient_id)
+        .bind(line.quantity)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        created.push(row);
+    }
+
+    if let Some(product_id) = product_id {
+        check_product_recipe(&mut tx, product_id, was_tracked).await?;
+    }
+
+    let (entity_type, entity_id) = target.entity();
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        &format!("{}.recipe.update", entity_type),
+        entity_type,
+        entity_id,
+        Some(json!(old)),
+        Some(json!(created)),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(created)
+}
+
+/// Ingredients used by selling `quantity` of a product with the chosen modifier options.
+///
+/// A variant with recipe lines of its own uses them instead of the product's;
+/// one without falls back to the product recipe. Modifier lines are added once
+/// per time the option was chosen. Ingredients whose net use is not positive
+/// are left out.
+pub async fn depletion_for(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    variant_id: Option<Uuid>,
+    option_ids: &[Uuid],
+    quantity: i32,
+) -> Result<Depletion, AppError> {
+    let recipe_tracked = is_recipe_tracked(&mut *conn, product_id).await?;
+
+    let base: Vec<(Option<Uuid>, Uuid, i32)> = sqlx::query_as(
+        "SELECT variant_id, ingredient_id, quantity FROM recipe_lines
+         WHERE product_id = $1 AND (variant_id IS NULL OR variant_id = $2)",
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let modifier_lines: Vec<(Uuid, Uuid, i32)> = sqlx::query_as(
+        "SELECT modifier_option_id, ingredient_id, quantity FROM recipe_lines
+         WHERE modifier_option_id = ANY($1)",
+    )
+    .bind(option_ids)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let ingredients =
+        net_ingredients(&base, &modifier_lines, option_ids, quantity).ok_or_else(|| {
+            AppError::Validation(format!(
+                "Ingredient use for product {} is too large",
+                product_id
+            ))
+        })?;
+
+    Ok(Depletion {
+        recipe_tracked,
+        ingredients,
+    })
+}
+
+/// Combine the fetched recipe lines of one order line; `None` on overflow.
+///
+/// `base` holds `(variant_id, ingredient_id, units)` for the product and the
+/// sold variant, `modifier_lines` `(option_id, ingredient_id, units)`.
+fn net_ingredients(
+    base: &[(Option<Uuid>, Uuid, i32)],
+    modifier_lines: &[(Uuid, Uuid, i32)],
+    option_ids: &[Uuid],
+    quantity: i32,
+) -> Option<Vec<(Uuid, i32)>> {
+    let has_variant_lines = base.iter().any(|(variant_id, _, _)| variant_id.is_some());
+
+    let mut per_item: BTreeMap<Uuid, i64> = BTreeMap::new();
+    for (_, ingredient_id, units) in base
+        .iter()
+        .filter(|(variant_id, _, _)| variant_id.is_some() == has_variant_lines)
+    {
+        *per_item.entry(*ingredient_id).or_default() += *units as i64;
+    }
+    for option_id in option_ids {
+        for (_, ingredient_id, units) in modifier_lines.iter().filter(|l| l.0 == *option_id) {
+            *per_item.entry(*ingredient_id).or_default() += *units as i64;
+        }
+    }
+
+    per_item
+        .into_iter()
+        .filter(|(_, units)| *units > 0)
+        .map(|(ingredient_id, units)| {
+            let total = i32::try_from(units * quantity as i64).ok()?;
+            Some((ingredient_id, total))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEANS: Uuid = Uuid::from_u128(1);
+    const MILK: Uuid = Uuid::from_u128(2);
+    const OAT: Uuid = Uuid::from_u128(3);
+    const CUP: Uuid = Uuid::from_u128(4);
+    const LARGE: Uuid = Uuid::from_u128(10);
+    const OAT_OPTION: Uuid = Uuid::from_u128(20);
+    const EXTRA_SHOT: Uuid = Uuid::from_u128(21);
+
+    fn latte() -> Vec<(Option<Uuid>, Uuid, i32)> {
+        vec![(None, BEANS, 18), (None, MILK, 200), (None, CUP, 1)]
+    }
+
+    fn modifiers() -> Vec<(Uuid, Uuid, i32)> {
+        vec![
+            (OAT_OPTION, MILK, -200),
+            (OAT_OPTION, OAT, 200),
+            (EXTRA_SHOT, BEANS, 9),
+        ]
+    }
+
+    #[test]
+    fn test_variant_without_lines_uses_product_recipe() {
+        let used = net_ingredients(&latte(), &[], &[], 2).unwrap();
+        assert_eq!(used, vec![(BEANS, 36), (MILK, 400), (CUP, 2)]);
+    }
+
+    #[test]
+    fn test_variant_lines_replace_product_recipe() {
+        let mut base = latte();
+        base.push((Some(LARGE), BEANS, 27));
+        base.push((Some(LARGE), MILK, 300));
+
+        let used = net_ingredients(&base, &[], &[], 1).unwrap();
+        assert_eq!(used, vec![(BEANS, 27), (MILK, 300)]);
+    }
+
+    #[test]
+    fn test_modifier_lines_swap_and_repeat() {
+        let used = net_ingredients(
+            &latte(),
+            &modifiers(),
+            &[OAT_OPTION, EXTRA_SHOT, EXTRA_SHOT],
+            1,
+        )
+        .unwrap();
+        // Milk nets out to zero and is left out
+        assert_eq!(used, vec![(BEANS, 36), (OAT, 200), (CUP, 1)]);
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        assert_eq!(net_ingredients(&latte(), &[], &[], i32::MAX), None);
+    }
+}
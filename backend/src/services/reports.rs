@@ -0,0 +1,435 @@
+//! Margin, inventory valuation and ingredient usage reports
+//!
+//! Margins use the cost snapshotted onto each order line at sale time, so
+//! editing a product's cost only affects sales made afterwards. Lines sold
+//! without a recorded cost count towards revenue but are reported separately
+//! in `uncosted_revenue_cents` rather than being treated as pure profit.
+//!
+//! Ingredient usage is read from the stock ledger. Theoretical usage is what
+//! recipes took out for sales, net of cancellations; actual usage adds waste
+//! and reason-coded adjustments such as count variances. Deliveries, transfers
+//! and opening balances are not usage.
+//!
+//! Foreign tenders are summed per currency together with what they converted
+//! to at the rate snapshotted on each tender.
+//!
+//! The Z report totals completed orders per currency and payment method,
+//! including what cash rounding added or gave away.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use shared::{AppError, CurrencyCode, PaymentMethod};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Dimension a margin report is grouped by
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarginGrouping {
+    #[default]
+    Product,
+    Category,
+    Day,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct MarginQuery {
+    #[serde(default)]
+    pub group_by: MarginGrouping,
+    /// Completed at or after this instant
+    pub from: Option<DateTime<Utc>>,
+    /// Completed before this instant
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct MarginRow {
+    /// Product, variant or category id; `None` for days, uncategorized sales and
+    /// deleted products
+    pub id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
+    /// Product name, category name or `YYYY-MM-DD` (UTC)
+    pub label: String,
+    pub quantity: i64,
+    pub revenue_cents: i64,
+    pub cost_cents: i64,
+    /// Revenue from lines sold without a recorded cost
+    pub uncosted_revenue_cents: i64,
+    /// Revenue minus cost, over costed lines only
+    pub margin_cents: i64,
+    #[sqlx(skip)]
+    pub margin_percent: Option<f64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct MarginReport {
+    pub rows: Vec<MarginRow>,
+    pub revenue_cents: i64,
+    pub cost_cents: i64,
+    pub uncosted_revenue_cents: i64,
+    pub margin_cents: i64,
+    pub margin_percent: Option<f64>,
+}
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct ValuationRow {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub location_id: Uuid,
+    pub location_name: String,
+    pub name: String,
+    pub sku: Option<String>,
+    pub quantity: i32,
+    pub unit_cost_cents: Option<i64>,
+    /// `quantity × unit_cost_cents`; `None` when the item has no cost
+    pub value_cents: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct InventoryValuation {
+    pub items: Vec<ValuationRow>,
+    pub total_value_cents: i64,
+    /// Items in stock with no cost recorded, left out of the total
+    pub uncosted_items: usize,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct UsageQuery {
+    /// Movements at or after this instant
+    pub from: Option<DateTime<Utc>>,
+    /// Movements before this instant
+    pub to: Option<DateTime<Utc>>,
+    pub location_id: Option<Uuid>,
+}
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct UsageRow {
+    pub ingredient_id: Uuid,
+    pub name: String,
+    pub unit: String,
+    pub theoretical_usage: i64,
+    pub actual_usage: i64,
+    /// `actual_usage - theoretical_usage`; positive means more was used than sold
+    pub variance: i64,
+    pub unit_cost_cents: Option<i64>,
+    /// Variance valued at cost; `None` when the ingredient has no cost
+    pub variance_cost_cents: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct UsageReport {
+    pub rows: Vec<UsageRow>,
+    /// Net variance valued at cost, over ingredients with a recorded cost
+    pub variance_cost_cents: i64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TenderQuery {
+    /// Completed at or after this instant
+    pub from: Option<DateTime<Utc>>,
+    /// Completed before this instant
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct ForeignTenderRow {
+    /// Currency the orders were priced in
+    pub base_currency: String,
+    /// Currency the cash was handed over in
+    pub currency: String,
+    pub tenders: i64,
+    /// Minor units of `currency` taken
+    pub amount_tendered: i64,
+    /// What the tenders converted to in `base_currency`
+    pub base_amount_cents: i64,
+    /// Change given back in `base_currency`
+    pub change_cents: i64,
+    pub min_rate: Decimal,
+    pub max_rate: Decimal,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ZReportQuery {
+    /// Completed at or after this instant; defaults to the start of today (UTC)
+    pub from: Option<DateTime<Utc>>,
+    /// Completed before this instant; defaults to now
+    pub to: Option<DateTime<Utc>>,
+    pub location_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ZReportRow {
+    pub currency: CurrencyCode,
+    /// `None` on the per-currency totals, and for orders completed without one
+    pub payment_method: Option<PaymentMethod>,
+    pub orders: i64,
+    pub subtotal_cents: i64,
+    pub tax_cents: i64,
+    /// Net amount cash rounding added to totals; negative when it gave more away
+    pub cash_rounding_cents: i64,
+    pub total_cents: i64,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ZReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub location_id: Option<Uuid>,
+    /// Per currency and payment method
+    pub payments: Vec<ZReportRow>,
+    /// Per currency, over all payment methods
+    pub totals: Vec<ZReportRow>,
+    pub cancelled_orders: i64,
+}
+
+/// Margin as a percentage of costed revenue
+fn margin_percent(revenue_cents: i64, uncosted_revenue_cents: i64, margin_cents: i64) -> Option<f64> {
+    let costed_revenue = revenue_cents - uncosted_revenue_cents;
+    (costed_revenue > 0).then(|| margin_cents as f64 * 100.0 / costed_revenue as f64)
+}
+
+/// Gross margin of completed orders, grouped by product, category or day.
+///
+/// Lines whose product has since been deleted still count, under the name
+/// they were sold as and without a category.
+pub async fn margin_report(pool: &PgPool, query: &MarginQuery) -> Result<MarginReport, AppError> {
+    let (select, group_by, order_by) = match query.group_by {
+        MarginGrouping::Product => (
+            "oi.product_id AS id, oi.variant_id, MAX(oi.product_name) AS label",
+            // Deleted products are kept apart by the name they were sold as
+            "oi.product_id, oi.variant_id, CASE WHEN oi.product_id IS NULL THEN oi.product_name END",
+            "revenue_cents DESC",
+        ),
+        MarginGrouping::Category => (
+            "c.id AS id, NULL::UUID AS variant_id, COALESCE(MAX(c.name), 'Uncategorized') AS label",
+            "c.id",
+            "revenue_cents DESC",
+        ),
+        MarginGrouping::Day => (
+            "NULL::UUID AS id, NULL::UUID AS variant_id,
+             TO_CHAR(DATE_TRUNC('day', o.completed_at AT TIME ZONE 'UTC'), 'YYYY-MM-DD') AS label",
+            "label",
+            "label",
+        ),
+    };
+
+    let sql = format!(
+        "SELECT {select},
+                SUM(oi.quantity)::BIGINT AS quantity,
+                SUM(oi.total_price_cents)::BIGINT AS revenue_cents,
+                COALESCE(SUM(oi.total_cost_cents), 0)::BIGINT AS cost_cents,
+                COALESCE(SUM(oi.total_price_cents) FILTER (WHERE oi.total_cost_cents IS NULL), 0)::BIGINT
+                    AS uncosted_revenue_cents,
+                COALESCE(SUM(oi.total_price_cents - oi.total_cost_cents), 0)::BIGINT AS margin_cents
+         FROM order_items oi
+         JOIN orders o ON o.id = oi.order_id
+         LEFT JOIN products p ON p.id = oi.product_id
+         LEFT JOIN categories c ON c.id = p.category_id
+         WHERE o.status = 'completed'
+         AND ($1::TIMESTAMPTZ IS NULL OR o.completed_at >= $1)
+         AND ($2::TIMESTAMPTZ IS NULL OR o.completed_at < $2)
+         GROUP BY {group_by}
+         ORDER BY {order_by}"
+    );
+
+    let mut rows = sqlx::query_as::<_, MarginRow>(&sql)
+        .bind(query.from)
+        .bind(query.to)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    for row in rows.iter_mut() {
+        row.margin_percent =
+            margin_percent(row.revenue_cents, row.uncosted_revenue_cents, row.margin_cents);
+    }
+
+    let revenue_cents = rows.iter().map(|r| r.revenue_cents).sum();
+    let cost_cents = rows.iter().map(|r| r.cost_cents).sum();
+    let uncosted_revenue_cents = rows.iter().map(|r| r.uncosted_revenue_cents).sum();
+    let margin_cents = rows.iter().map(|r| r.margin_cents).sum();
+
+    Ok(MarginReport {
+        rows,
+        revenue_cents,
+        cost_cents,
+        uncosted_revenue_cents,
+        margin_cents,
+        margin_percent: margin_percent(revenue_cents, uncosted_revenue_cents, margin_cents),
+    })
+}
+
+/// Current stock valued at each item's cost (variant cost, else product cost)
+pub async fn inventory_valuation(pool: &PgPool) -> Result<InventoryValuation, AppError> {
+    let items = sqlx::query_as::<_, ValuationRow>(
+        "SELECT i.product_id, i.variant_id, i.location_id, loc.name AS location_name,
+                CASE WHEN v.id IS NULL THEN p.name ELSE p.name || ' (' || v.name || ')' END AS name,
+                COALESCE(v.sku, p.sku) AS sku,
+                i.quantity,
+                COALESCE(v.cost_cents, p.cost_cents) AS unit_cost_cents,
+                i.quantity * COALESCE(v.cost_cents, p.cost_cents) AS value_cents
+         FROM inventory i
+         JOIN locations loc ON loc.id = i.location_id
+         JOIN products p ON p.id = i.product_id
+         LEFT JOIN product_variants v ON v.id = i.variant_id
+         WHERE i.quantity > 0
+         ORDER BY p.name, v.sort_order NULLS FIRST, loc.is_default DESC, loc.name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    let total_value_cents = items.iter().filter_map(|i| i.value_cents).sum();
+    let uncosted_items = items.iter().filter(|i| i.value_cents.is_none()).count();
+
+    Ok(InventoryValuation {
+        items,
+        total_value_cents,
+        uncosted_items,
+    })
+}
+
+/// Theoretical versus actual use of each ingredient over a period
+pub async fn ingredient_usage(pool: &PgPool, query: &UsageQuery) -> Result<UsageReport, AppError> {
+    let rows = sqlx::query_as::<_, UsageRow>(
+        "WITH usage AS (
+             SELECT m.product_id,
+                    -COALESCE(SUM(m.quantity_delta)
+                        FILTER (WHERE m.movement_type IN ('sale', 'refund')), 0)::BIGINT
+                        AS theoretical_usage,
+                    -COALESCE(SUM(m.quantity_delta)
+                        FILTER (WHERE m.movement_type IN ('sale', 'refund', 'waste')
+                                OR (m.movement_type = 'adjustment' AND m.reason_code IS NOT NULL)),
+                        0)::BIGINT AS actual_usage
+             FROM stock_movements m
+             JOIN inventory i ON i.id = m.inventory_id
+             WHERE ($1::TIMESTAMPTZ IS NULL OR m.created_at >= $1)
+             AND ($2::TIMESTAMPTZ IS NULL OR m.created_at < $2)
+             AND ($3::UUID IS NULL OR i.location_id = $3)
+             GROUP BY m.product_id
+         )
+         SELECT p.id AS ingredient_id, p.name, p.unit,
+                u.theoretical_usage, u.actual_usage,
+                u.actual_usage - u.theoretical_usage AS variance,
+                p.cost_cents AS unit_cost_cents,
+                (u.actual_usage - u.theoretical_usage) * p.cost_cents AS variance_cost_cents
+         FROM usage u
+         JOIN products p ON p.id = u.product_id
+         WHERE p.is_ingredient = true
+         ORDER BY ABS(u.actual_usage - u.theoretical_usage) * COALESCE(p.cost_cents, 0) DESC, p.name",
+    )
+    .bind(query.from)
+    .bind(query.to)
+    .bind(query.location_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    let variance_cost_cents = rows.iter().filter_map(|r| r.variance_cost_cents).sum();
+
+    Ok(UsageReport {
+        rows,
+        variance_cost_cents,
+    })
+}
+
+/// Cash taken in foreign currencies on completed orders, per currency
+pub async fn foreign_tenders(
+    pool: &PgPool,
+    query: &TenderQuery,
+) -> Result<Vec<ForeignTenderRow>, AppError> {
+    sqlx::query_as::<_, ForeignTenderRow>(
+        "SELECT o.currency AS base_currency, t.currency,
+                COUNT(*) AS tenders,
+                SUM(t.amount)::BIGINT AS amount_tendered,
+                SUM(t.base_amount_cents)::BIGINT AS base_amount_cents,
+                SUM(t.change_cents)::BIGINT AS change_cents,
+                MIN(t.rate) AS min_rate,
+                MAX(t.rate) AS max_rate
+         FROM order_tenders t
+         JOIN orders o ON o.id = t.order_id
+         WHERE o.status = 'completed'
+         AND t.currency <> o.currency
+         AND ($1::TIMESTAMPTZ IS NULL OR o.completed_at >= $1)
+         AND ($2::TIMESTAMPTZ IS NULL OR o.completed_at < $2)
+         GROUP BY o.currency, t.currency
+         ORDER BY o.currency, t.currency",
+    )
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))
+}
+
+/// End-of-day takings of completed orders, with cash rounding shown separately
+pub async fn z_report(pool: &PgPool, query: &ZReportQuery) -> Result<ZReport, AppError> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| {
+        to.date_naive()
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc()
+    });
+
+    let payments = sqlx::query_as::<_, ZReportRow>(
+        "SELECT currency, payment_method,
+                COUNT(*) AS orders,
+                SUM(subtotal_cents)::BIGINT AS subtotal_cents,
+                SUM(tax_cents)::BIGINT AS tax_cents,
+                SUM(cash_rounding_cents)::BIGINT AS cash_rounding_cents,
+                SUM(total_cents)::BIGINT AS total_cents
+         FROM orders
+         WHERE status = 'completed'
+         AND completed_at >= $1 AND completed_at < $2
+         AND ($3::UUID IS NULL OR location_id = $3)
+         GROUP BY currency, payment_method
+         ORDER BY currency, payment_method",
+    )
+    .bind(from)
+    .bind(to)
+    .bind(query.location_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    let cancelled_orders: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM orders
+         WHERE status = 'cancelled'
+         AND cancelled_at >= $1 AND cancelled_at < $2
+         AND ($3::UUID IS NULL OR location_id = $3)",
+    )
+    .bind(from)
+    .bind(to)
+    .bind(query.location_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    // Payments are ordered by currency, so each currency's rows are adjacent
+    let mut totals: Vec<ZReportRow> = Vec::new();
+    for row in &payments {
+        match totals.last_mut() {
+            Some(total) if total.currency == row.currency => {
+                total.orders += row.orders;
+                total.subtotal_cents += row.subtotal_cents;
+                total.tax_cents += row.tax_cents;
+                total.cash_rounding_cents += row.cash_rounding_cents;
+                total.total_cents += row.total_cents;
+            }
+            _ => totals.push(ZReportRow {
+                payment_method: None,
+                ..row.clone()
+            }),
+        }
+    }
+
+    Ok(ZReport {
+        from,
+        to,
+        location_id: query.location_id,
+        payments,
+        totals,
+        cancelled_orders,
+    })
+}
@@ -0,0 +1,463 @@
+//! Cycle counts
+//!
+//! A count is opened, counted quantities are entered per product (or variant),
+//! the variances are reviewed and then posted as reason-coded stock movements.
+//! Counts whose variances exceed the approval threshold wait in
+//! `pending_approval` until a manager approves them.
+
+use chrono::Utc;
+use serde_json::json;
+use shared::AppError;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::{Inventory, StockCount, StockCountLine};
+use crate::services::{audit, locations};
+use crate::services::inventory::{self, ReasonCode, StockChange};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateStockCountRequest {
+    pub notes: Option<String>,
+    /// Location being counted; defaults to the default location
+    pub location_id: Option<Uuid>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CountEntry {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub counted_quantity: i32,
+    /// Required on posting for lines whose count differs from the system quantity
+    pub reason_code: Option<ReasonCode>,
+}
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct VarianceLine {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub name: String,
+    pub expected_quantity: i32,
+    pub counted_quantity: i32,
+    /// `counted_quantity - expected_quantity`
+    pub variance: i32,
+    /// System quantity now; differs from `expected_quantity` if stock moved since counting
+    pub current_quantity: i32,
+    pub reason_code: Option<String>,
+    pub unit_cost_cents: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct StockCountReview {
+    pub count: StockCount,
+    pub lines: Vec<VarianceLine>,
+    /// Lines with a non-zero variance
+    pub variance_lines: usize,
+    /// Net variance valued at cost, over lines with a recorded cost
+    pub variance_value_cents: i64,
+    pub requires_approval: bool,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+async fn lock_count(conn: &mut PgConnection, count_id: Uuid) -> Result<StockCount, AppError> {
+    sqlx::query_as::<_, StockCount>("SELECT * FROM stock_counts WHERE id = $1 FOR UPDATE")
+        .bind(count_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::NotFound(format!("Stock count {}", count_id)))
+}
+
+fn ensure_status(count: &StockCount, expected: &str) -> Result<(), AppError> {
+    if count.status != expected {
+        return Err(AppError::Validation(format!(
+            "Stock count {} is {}, expected {}",
+            count.id, count.status, expected
+        )));
+    }
+    Ok(())
+}
+
+pub async fn create_count(
+    pool: &PgPool,
+    user_id: Uuid,
+    request: CreateStockCountRequest,
+) -> Result<StockCount, AppError> {
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+    let location_id = locations::resolve_location(&mut conn, request.location_id).await?;
+
+    sqlx::query_as::<_, StockCount>(
+        "INSERT INTO stock_counts (notes, location_id, created_by) VALUES ($1, $2, $3) RETURNING *",
+    )
+    .bind(&request.notes)
+    .bind(location_id)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)
+}
+
+pub async fn list_counts(pool: &PgPool) -> Result<Vec<StockCount>, AppError> {
+    sqlx::query_as::<_, StockCount>("SELECT * FROM stock_counts ORDER BY created_at DESC")
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)
+}
+
+/// Enter counted quantities on an open count. Only whoever opened the count
+/// or a manager may record; replaced quantities are kept in the audit log.
+pub async fn record_counts(
+    pool: &PgPool,
+    user_id: Uuid,
+    is_manager: bool,
+    count_id: Uuid,
+    entries: Vec<CountEntry>,
+) -> Result<Vec<StockCountLine>, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let count = lock_count(&mut tx, count_id).await?;
+    ensure_status(&count, "open")?;
+    if count.created_by != Some(user_id) && !is_manager {
+        return Err(AppError::Forbidden(
+            "only the person who opened the count or a manager can record quantities".to_string(),
+        ));
+    }
+
+    let previous = sqlx::query_as::<_, StockCountLine>(
+        "SELECT * FROM stock_count_lines WHERE stock_count_id = $1",
+    )
+    .bind(count_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let mut lines = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if entry.counted_quantity < 0 {
+            return Err(AppError::Validation(format!(
+                "counted_quantity must not be negative for product {}",
+                entry.product_id
+            )));
+        }
+
+        let stock = sqlx::query_as::<_, Inventory>(
+            "SELECT * FROM inventory
+             WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2 AND location_id = $3",
+        )
+        .bind(entry.product_id)
+        .bind(entry.variant_id)
+        .bind(count.location_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .ok_or(AppError::ProductNotFound {
+            id: entry.product_id,
+        })?;
+
+        let line = sqlx::query_as::<_, StockCountLine>(
+            "INSERT INTO stock_count_lines (stock_count_id, inventory_id, product_id, variant_id,
+             expected_quantity, counted_quantity, reason_code)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (stock_count_id, inventory_id) DO UPDATE
+             SET expected_quantity = EXCLUDED.expected_quantity,
+                 counted_quantity = EXCLUDED.counted_quantity,
+                 reason_code = EXCLUDED.reason_code
+             RETURNING *",
+        )
+        .bind(count_id)
+        .bind(stock.id)
+        .bind(entry.product_id)
+        .bind(entry.variant_id)
+        .bind(stock.quantity)
+        .bind(entry.counted_quantity)
+        .bind(entry.reason_code.map(ReasonCode::as_str))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        lines.push(line);
+    }
+
+    let replaced: Vec<&StockCountLine> = previous
+        .iter()
+        .filter(|old| {
+            lines
+                .iter()
+                .any(|line| line.inventory_id == old.inventory_id)
+        })
+        .collect();
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "stock_count.record",
+        "stock_count",
+        count_id,
+        (!replaced.is_empty()).then(|| json!({ "lines": replaced })),
+        Some(json!({ "lines": lines })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(lines)
+}
+
+/// Count with its variances against the system quantities
+pub async fn review_count(
+    pool: &PgPool,
+    count_id: Uuid,
+    approval_threshold: i32,
+) -> Result<StockCountReview, AppError> {
+    let count = sqlx::query_as::<_, StockCount>("SELECT * FROM stock_counts WHERE id = $1")
+        .bind(count_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::NotFound(format!("Stock count {}", count_id)))?;
+
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+    let lines = variance_lines(&mut conn, count_id).await?;
+
+    Ok(build_review(count, lines, approval_threshold))
+}
+
+async fn variance_lines(
+    conn: &mut PgConnection,
+    count_id: Uuid,
+) -> Result<Vec<VarianceLine>, AppError> {
+    sqlx::query_as::<_, VarianceLine>(
+        "SELECT l.id, l.product_id, l.variant_id,
+                CASE WHEN v.id IS NULL THEN p.name ELSE p.name || ' (' || v.name || ')' END AS name,
+                l.expected_quantity, l.counted_quantity,
+                l.counted_quantity - l.expected_quantity AS variance,
+                i.quantity AS current_quantity, l.reason_code,
+                COALESCE(v.cost_cents, p.cost_cents) AS unit_cost_cents
+         FROM stock_count_lines l
+         JOIN inventory i ON i.id = l.inventory_id
+         JOIN products p ON p.id = l.product_id
+         LEFT JOIN product_variants v ON v.id = l.variant_id
+         WHERE l.stock_count_id = $1
+         ORDER BY p.name, v.sort_order NULLS FIRST",
+    )
+    .bind(count_id)
+    .fetch_all(conn)
+    .await
+    .map_err(db_error)
+}
+
+fn build_review(count: StockCount, lines: Vec<VarianceLine>, approval_threshold: i32) -> StockCountReview {
+    let variance_lines = lines.iter().filter(|l| l.variance != 0).count();
+    let variance_value_cents = lines
+        .iter()
+        .filter_map(|l| l.unit_cost_cents.map(|cost| cost * l.variance as i64))
+        .sum();
+    let requires_approval = lines
+        .iter()
+        .any(|l| l.variance.unsigned_abs() > approval_threshold.max(0) as u32);
+
+    StockCountReview {
+        count,
+        lines,
+        variance_lines,
+        variance_value_cents,
+        requires_approval,
+    }
+}
+
+/// Submit a count for posting.
+///
+/// Counts within the threshold, or submitted by a manager, are posted
+/// immediately; the rest wait for [`approve_count`].
+pub async fn submit_count(
+    pool: &PgPool,
+    user_id: Uuid,
+    is_manager: bool,
+    approval_threshold: i32,
+    count_id: Uuid,
+) -> Result<StockCountReview, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let count = lock_count(&mut tx, count_id).await?;
+    ensure_status(&count, "open")?;
+
+    let lines = variance_lines(&mut tx, count_id).await?;
+    if lines.is_empty() {
+        return Err(AppError::Validation("Stock count has no counted lines".to_string()));
+    }
+    if let Some(line) = lines.iter().find(|l| l.variance != 0 && l.reason_code.is_none()) {
+        return Err(AppError::Validation(format!(
+            "A reason code is required for the variance on {}",
+            line.name
+        )));
+    }
+
+    let review = build_review(count, lines, approval_threshold);
+    let count = if review.requires_approval && !is_manager {
+        sqlx::query_as::<_, StockCount>(
+            "UPDATE stock_counts
+             SET status = 'pending_approval', submitted_by = $1, submitted_at = $2
+             WHERE id = $3
+             RETURNING *",
+        )
+        .bind(user_id)
+        .bind(Utc::now())
+        .bind(count_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?
+    } else {
+        sqlx::query("UPDATE stock_counts SET submitted_by = $1, submitted_at = $2 WHERE id = $3")
+            .bind(user_id)
+            .bind(Utc::now())
+            .bind(count_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        post_count(&mut tx, user_id, is_manager.then_some(user_id), &review.count, &review.lines).await?
+    };
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(StockCountReview { count, ..review })
+}
+
+/// Approve and post a count waiting for manager approval
+pub async fn approve_count(
+    pool: &PgPool,
+    manager_id: Uuid,
+    approval_threshold: i32,
+    count_id: Uuid,
+) -> Result<StockCountReview, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let count = lock_count(&mut tx, count_id).await?;
+    ensure_status(&count, "pending_approval")?;
+
+    let lines = variance_lines(&mut tx, count_id).await?;
+    let review = build_review(count, lines, approval_threshold);
+    let count = post_count(&mut tx, manager_id, Some(manager_id), &review.count, &review.lines).await?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(StockCountReview { count, ..review })
+}
+
+/// Cancel a count that has not been posted; one awaiting approval can only be
+/// cancelled by a manager
+pub async fn cancel_count(
+    pool: &PgPool,
+    user_id: Uuid,
+    is_manager: bool,
+    count_id: Uuid,
+) -> Result<StockCount, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let old = lock_count(&mut tx, count_id).await?;
+    if old.status == "posted" || old.status == "cancelled" {
+        return Err(AppError::Validation(format!(
+            "Stock count {} is already {}",
+            count_id, old.status
+        )));
+    }
+    if old.status == "pending_approval" && !is_manager {
+        return Err(AppError::Forbidden(
+            "manager role required to cancel a count awaiting approval".to_string(),
+        ));
+    }
+
+    let count = sqlx::query_as::<_, StockCount>(
+        "UPDATE stock_counts SET status = 'cancelled' WHERE id = $1 RETURNING *",
+    )
+    .bind(count_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "stock_count.cancel",
+        "stock_count",
+        count_id,
+        Some(json!({ "status": old.status })),
+        Some(json!({ "status": count.status })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(count)
+}
+
+async fn post_count(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    approved_by: Option<Uuid>,
+    count: &StockCount,
+    lines: &[VarianceLine],
+) -> Result<StockCount, AppError> {
+    for line in lines.iter().filter(|l| l.variance != 0) {
+        let reason_code = line
+            .reason_code
+            .as_deref()
+            .and_then(|code| code.parse::<ReasonCode>().ok())
+            .unwrap_or(ReasonCode::Miscount);
+        let movement_type = if line.variance < 0 {
+            reason_code.movement_type()
+        } else {
+            inventory::MovementType::Adjustment
+        };
+
+        inventory::record_movement(
+            conn,
+            StockChange {
+                product_id: line.product_id,
+                variant_id: line.variant_id,
+                location_id: count.location_id,
+                movement_type,
+                quantity_delta: line.variance,
+                reason: Some(format!("Stock count {}", count.id)),
+                reason_code: Some(reason_code),
+                user_id: Some(user_id),
+                order_id: None,
+            },
+        )
+        .await?;
+    }
+
+    let count = sqlx::query_as::<_, StockCount>(
+        "UPDATE stock_counts
+         SET status = 'posted', approved_by = $1, posted_at = $2
+         WHERE id = $3
+         RETURNING *",
+    )
+    .bind(approved_by)
+    .bind(Utc::now())
+    .bind(count.id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        conn,
+        Some(user_id),
+        "stock_count.post",
+        "stock_count",
+        count.id,
+        None,
+        Some(json!({
+            "approved_by": approved_by,
+            "lines": lines.iter().filter(|l| l.variance != 0).map(|l| json!({
+                "product_id": l.product_id,
+                "variant_id": l.variant_id,
+                "variance": l.variance,
+                "reason_code": l.reason_code,
+            })).collect::<Vec<_>>(),
+        })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    Ok(count)
+}
@@ -0,0 +1,238 @@
+//! Suppliers and the products they supply
+
+use shared::AppError;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::{Supplier, SupplierProduct};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SupplierRequest {
+    pub name: String,
+    pub contact_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub notes: Option<String>,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+    /// Days from ordering to the stock arriving
+    #[serde(default = "default_lead_time_days")]
+    pub lead_time_days: i32,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+fn default_lead_time_days() -> i32 {
+    7
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SupplierProductRequest {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub supplier_sku: Option<String>,
+    /// Cost of one unit
+    pub cost_cents: i64,
+    #[serde(default = "default_pack_size")]
+    pub pack_size: i32,
+    #[serde(default)]
+    pub is_preferred: bool,
+}
+
+fn default_pack_size() -> i32 {
+    1
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+fn validate_supplier(request: &SupplierRequest) -> Result<(), AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::Validation("Supplier name is required".to_string()));
+    }
+    if request.lead_time_days < 0 {
+        return Err(AppError::Validation(format!(
+            "lead_time_days must not be negative, got {}",
+            request.lead_time_days
+        )));
+    }
+    Ok(())
+}
+
+pub async fn list_suppliers(pool: &PgPool) -> Result<Vec<Supplier>, AppError> {
+    sqlx::query_as::<_, Supplier>("SELECT * FROM suppliers ORDER BY is_active DESC, name")
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)
+}
+
+pub async fn create_supplier(pool: &PgPool, request: SupplierRequest) -> Result<Supplier, AppError> {
+    validate_supplier(&request)?;
+
+    sqlx::query_as::<_, Supplier>(
+        "INSERT INTO suppliers (name, contact_name, email, phone, notes, is_active, lead_time_days)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING *",
+    )
+    .bind(request.name.trim())
+    .bind(&request.contact_name)
+    .bind(&request.email)
+    .bind(&request.phone)
+    .bind(&request.notes)
+    .bind(request.is_active)
+    .bind(request.lead_time_days)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error)
+}
+
+pub async fn update_supplier(
+    pool: &PgPool,
+    supplier_id: Uuid,
+    request: SupplierRequest,
+) -> Result<Supplier, AppError> {
+    validate_supplier(&request)?;
+
+    sqlx::query_as::<_, Supplier>(
+        "UPDATE suppliers
+         SET name = $1, contact_name = $2, email = $3, phone = $4, notes = $5, is_active = $6,
+             lead_time_days = $7
+         WHERE id = $8
+         RETURNING *",
+    )
+    .bind(request.name.trim())
+    .bind(&request.contact_name)
+    .bind(&request.email)
+    .bind(&request.phone)
+    .bind(&request.notes)
+    .bind(request.is_active)
+    .bind(request.lead_time_days)
+    .bind(supplier_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)?
+    .ok_or_else(|| AppError::NotFound(format!("Supplier {}", supplier_id)))
+}
+
+pub async fn get_supplier_products(
+    pool: &PgPool,
+    supplier_id: Uuid,
+) -> Result<Vec<SupplierProduct>, AppError> {
+    sqlx::query_as::<_, SupplierProduct>(
+        "SELECT * FROM supplier_products WHERE supplier_id = $1 ORDER BY created_at",
+    )
+    .bind(supplier_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+/// Link a product (or variant) to a supplier, or update the existing link.
+///
+/// Marking a link preferred clears the flag on other suppliers of the same item.
+pub async fn upsert_supplier_product(
+    pool: &PgPool,
+    supplier_id: Uuid,
+    request: SupplierProductRequest,
+) -> Result<SupplierProduct, AppError> {
+    if request.cost_cents < 0 {
+        return Err(AppError::Validation(format!(
+            "cost_cents must not be negative, got {}",
+            request.cost_cents
+        )));
+    }
+    if request.pack_size <= 0 {
+        return Err(AppError::Validation(format!(
+            "pack_size must be positive, got {}",
+            request.pack_size
+        )));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let supplier_exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM suppliers WHERE id = $1)")
+            .bind(supplier_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    if !supplier_exists {
+        return Err(AppError::NotFound(format!("Supplier {}", supplier_id)));
+    }
+
+    let stocked: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM inventory
+         WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2)",
+    )
+    .bind(request.product_id)
+    .bind(request.variant_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    if !stocked {
+        return Err(AppError::ProductNotFound {
+            id: request.product_id,
+        });
+    }
+
+    if request.is_preferred {
+        sqlx::query(
+            "UPDATE supplier_products SET is_preferred = false
+             WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2 AND supplier_id <> $3",
+        )
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .bind(supplier_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    }
+
+    let existing: Option<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM supplier_products
+         WHERE supplier_id = $1 AND product_id = $2 AND variant_id IS NOT DISTINCT FROM $3",
+    )
+    .bind(supplier_id)
+    .bind(request.product_id)
+    .bind(request.variant_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let link = match existing {
+        Some(id) => sqlx::query_as::<_, SupplierProduct>(
+            "UPDATE supplier_products
+             SET supplier_sku = $1, cost_cents = $2, pack_size = $3, is_preferred = $4
+             WHERE id = $5
+             RETURNING *",
+        )
+        .bind(&request.supplier_sku)
+        .bind(request.cost_cents)
+        .bind(request.pack_size)
+        .bind(request.is_preferred)
+        .bind(id),
+        None => sqlx::query_as::<_, SupplierProduct>(
+            "INSERT INTO supplier_products (supplier_id, product_id, variant_id, supplier_sku,
+             cost_cents, pack_size, is_preferred)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING *",
+        )
+        .bind(supplier_id)
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .bind(&request.supplier_sku)
+        .bind(request.cost_cents)
+        .bind(request.pack_size)
+        .bind(request.is_preferred),
+    }
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(link)
+}
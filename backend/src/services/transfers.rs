@@ -0,0 +1,349 @@
+//! Stock transfers between locations
+//!
+//! Transfers move `draft → in_transit → received`, or to `cancelled` while
+//! still a draft. Sending takes stock out of the source location and receiving
+//! puts it into the destination, each as `transfer` movements in the ledger.
+//! Lots sent are recreated at the destination with the same expiry dates.
+
+use chrono::Utc;
+use serde_json::json;
+use shared::AppError;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::{StockTransfer, StockTransferLine};
+use crate::services::audit;
+use crate::services::inventory::{self, MovementType, StockChange};
+use crate::services::{locations, lots};
+
+#[derive(Debug, serde::Serialize)]
+pub struct TransferWithLines {
+    pub transfer: StockTransfer,
+    pub lines: Vec<StockTransferLine>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateTransferRequest {
+    pub from_location_id: Uuid,
+    pub to_location_id: Uuid,
+    pub notes: Option<String>,
+    pub lines: Vec<TransferLineRequest>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TransferLineRequest {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub quantity: i32,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TransferFilter {
+    pub status: Option<String>,
+    pub location_id: Option<Uuid>,
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+async fn load_lines(
+    conn: &mut PgConnection,
+    transfer_id: Uuid,
+) -> Result<Vec<StockTransferLine>, AppError> {
+    sqlx::query_as::<_, StockTransferLine>(
+        "SELECT * FROM stock_transfer_lines WHERE transfer_id = $1 ORDER BY created_at, id",
+    )
+    .bind(transfer_id)
+    .fetch_all(conn)
+    .await
+    .map_err(db_error)
+}
+
+async fn lock_transfer(
+    conn: &mut PgConnection,
+    transfer_id: Uuid,
+) -> Result<StockTransfer, AppError> {
+    sqlx::query_as::<_, StockTransfer>("SELECT * FROM stock_transfers WHERE id = $1 FOR UPDATE")
+        .bind(transfer_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::NotFound(format!("Transfer {}", transfer_id)))
+}
+
+fn ensure_status(transfer: &StockTransfer, expected: &str) -> Result<(), AppError> {
+    if transfer.status != expected {
+        return Err(AppError::Validation(format!(
+            "Transfer {} is {}, expected {}",
+            transfer.transfer_number, transfer.status, expected
+        )));
+    }
+    Ok(())
+}
+
+/// Transfers touching a location, either as source or destination
+pub async fn list_transfers(
+    pool: &PgPool,
+    filter: &TransferFilter,
+) -> Result<Vec<StockTransfer>, AppError> {
+    sqlx::query_as::<_, StockTransfer>(
+        "SELECT * FROM stock_transfers
+         WHERE ($1::TEXT IS NULL OR status = $1)
+         AND ($2::UUID IS NULL OR from_location_id = $2 OR to_location_id = $2)
+         ORDER BY created_at DESC",
+    )
+    .bind(&filter.status)
+    .bind(filter.location_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+pub async fn get_transfer(pool: &PgPool, transfer_id: Uuid) -> Result<TransferWithLines, AppError> {
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+
+    let transfer = sqlx::query_as::<_, StockTransfer>("SELECT * FROM stock_transfers WHERE id = $1")
+        .bind(transfer_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::NotFound(format!("Transfer {}", transfer_id)))?;
+    let lines = load_lines(&mut conn, transfer_id).await?;
+
+    Ok(TransferWithLines { transfer, lines })
+}
+
+pub async fn create_transfer(
+    pool: &PgPool,
+    user_id: Uuid,
+    request: CreateTransferRequest,
+) -> Result<TransferWithLines, AppError> {
+    if request.lines.is_empty() {
+        return Err(AppError::Validation(
+            "A transfer needs at least one line".to_string(),
+        ));
+    }
+    if request.from_location_id == request.to_location_id {
+        return Err(AppError::Validation(
+            "A transfer must move stock between two different locations".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    locations::resolve_location(&mut tx, Some(request.from_location_id)).await?;
+    locations::resolve_location(&mut tx, Some(request.to_location_id)).await?;
+
+    let transfer = sqlx::query_as::<_, StockTransfer>(
+        "INSERT INTO stock_transfers (from_location_id, to_location_id, notes, created_by)
+         VALUES ($1, $2, $3, $4)
+         RETURNING *",
+    )
+    .bind(request.from_location_id)
+    .bind(request.to_location_id)
+    .bind(&request.notes)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let mut lines = Vec::with_capacity(request.lines.len());
+    for line in &request.lines {
+        if line.quantity <= 0 {
+            return Err(AppError::InvalidQuantity {
+                quantity: line.quantity.max(0) as u32,
+            });
+        }
+
+        let row = sqlx::query_as::<_, StockTransferLine>(
+            "INSERT INTO stock_transfer_lines (transfer_id, product_id, variant_id, quantity)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+        )
+        .bind(transfer.id)
+        .bind(line.product_id)
+        .bind(line.variant_id)
+        .bind(line.quantity)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        lines.push(row);
+    }
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "stock_transfer.create",
+        "stock_transfer",
+        transfer.id,
+        None,
+        Some(json!({ "transfer": transfer, "lines": lines })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(TransferWithLines { transfer, lines })
+}
+
+/// Dispatch a draft, taking its stock out of the source location.
+///
+/// Fails with `InsufficientInventory` if the source cannot cover a line.
+pub async fn send_transfer(
+    pool: &PgPool,
+    user_id: Uuid,
+    transfer_id: Uuid,
+) -> Result<TransferWithLines, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let transfer = lock_transfer(&mut tx, transfer_id).await?;
+    ensure_status(&transfer, "draft")?;
+
+    let lines = load_lines(&mut tx, transfer_id).await?;
+    for line in &lines {
+        let movement = inventory::record_movement(
+            &mut tx,
+            StockChange {
+                product_id: line.product_id,
+                variant_id: line.variant_id,
+                location_id: transfer.from_location_id,
+                movement_type: MovementType::Transfer,
+                quantity_delta: -line.quantity,
+                reason: Some(format!("Sent on {}", transfer.transfer_number)),
+                reason_code: None,
+                user_id: Some(user_id),
+                order_id: None,
+            },
+        )
+        .await?;
+
+        sqlx::query("UPDATE stock_transfer_lines SET sent_movement_id = $1 WHERE id = $2")
+            .bind(movement.id)
+            .bind(line.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    }
+    // Reload to return the recorded send movements
+    let lines = load_lines(&mut tx, transfer_id).await?;
+
+    let transfer = sqlx::query_as::<_, StockTransfer>(
+        "UPDATE stock_transfers SET status = 'in_transit', sent_at = $1 WHERE id = $2 RETURNING *",
+    )
+    .bind(Utc::now())
+    .bind(transfer_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "stock_transfer.send",
+        "stock_transfer",
+        transfer_id,
+        Some(json!({ "status": "draft" })),
+        Some(json!({ "status": transfer.status })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(TransferWithLines { transfer, lines })
+}
+
+/// Receive an in-transit transfer in full at the destination location
+pub async fn receive_transfer(
+    pool: &PgPool,
+    user_id: Uuid,
+    transfer_id: Uuid,
+) -> Result<TransferWithLines, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let transfer = lock_transfer(&mut tx, transfer_id).await?;
+    ensure_status(&transfer, "in_transit")?;
+
+    let lines = load_lines(&mut tx, transfer_id).await?;
+    for line in &lines {
+        let movement = inventory::record_movement(
+            &mut tx,
+            StockChange {
+                product_id: line.product_id,
+                variant_id: line.variant_id,
+                location_id: transfer.to_location_id,
+                movement_type: MovementType::Transfer,
+                quantity_delta: line.quantity,
+                reason: Some(format!("Received on {}", transfer.transfer_number)),
+                reason_code: None,
+                user_id: Some(user_id),
+                order_id: None,
+            },
+        )
+        .await?;
+
+        // Lots travel with the stock, keeping their expiry dates
+        if let Some(sent_movement_id) = line.sent_movement_id {
+            lots::receive_transferred(&mut tx, &movement, sent_movement_id).await?;
+        }
+    }
+
+    let transfer = sqlx::query_as::<_, StockTransfer>(
+        "UPDATE stock_transfers SET status = 'received', received_at = $1 WHERE id = $2 RETURNING *",
+    )
+    .bind(Utc::now())
+    .bind(transfer_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "stock_transfer.receive",
+        "stock_transfer",
+        transfer_id,
+        Some(json!({ "status": "in_transit" })),
+        Some(json!({ "status": transfer.status })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(TransferWithLines { transfer, lines })
+}
+
+/// Cancel a transfer that has not been sent
+pub async fn cancel_transfer(
+    pool: &PgPool,
+    user_id: Uuid,
+    transfer_id: Uuid,
+) -> Result<StockTransfer, AppError> {
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let transfer = lock_transfer(&mut tx, transfer_id).await?;
+    ensure_status(&transfer, "draft")?;
+
+    let transfer = sqlx::query_as::<_, StockTransfer>(
+        "UPDATE stock_transfers SET status = 'cancelled' WHERE id = $1 RETURNING *",
+    )
+    .bind(transfer_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    audit::record(
+        &mut tx,
+        Some(user_id),
+        "stock_transfer.cancel",
+        "stock_transfer",
+        transfer_id,
+        Some(json!({ "status": "draft" })),
+        Some(json!({ "status": transfer.status })),
+    )
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(transfer)
+}
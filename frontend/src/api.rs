@@ -1,16 +1,80 @@
 //! API client for TREZZA TERMINAL backend
 
-use anyhow::Result;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use shared::{
+    AppError, CompleteOrderRequest, CreateOrderRequest, ErrorResponse, LoginRequest, Money,
+    PaymentMethod, TenderRequest,
+};
+use thiserror::Error;
 use uuid::Uuid;
 
+pub use shared::{
+    BarcodeMatchResponse, CompletedOrderResponse, LoginResponse, ModifierGroupResponse,
+    ModifierOptionResponse, OrderItemRequest, OrderResponse, ProductResponse, VariantResponse,
+};
+
 const API_BASE_URL: &str = "http://127.0.0.1:3000/api";
 
+#[derive(Error, Debug)]
+pub enum ApiError {
+    /// The backend rejected the request with a structured error body
+    #[error("{message}")]
+    Server {
+        status: u16,
+        error: AppError,
+        message: String,
+    },
+
+    /// The backend failed without a parseable error body
+    #[error("Request failed with status {0}")]
+    Status(u16),
+
+    #[error("Network error: {0}")]
+    Http(#[from] reqwest::Error),
+}
+
+impl ApiError {
+    /// The backend's error, when it sent one
+    pub fn app_error(&self) -> Option<&AppError> {
+        match self {
+            ApiError::Server { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ApiError>;
+
+/// Send a request and decode the body, turning error statuses into `ApiError`
+async fn send<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.json::<T>().await?);
+    }
+
+    let body = response.bytes().await?;
+    match serde_json::from_slice::<ErrorResponse>(&body) {
+        Ok(body) => Err(ApiError::Server {
+            status: status.as_u16(),
+            error: body.error,
+            message: body.message,
+        }),
+        Err(_) => Err(ApiError::Status(status.as_u16())),
+    }
+}
+
 #[derive(Clone)]
 pub struct ApiClient {
     client: Client,
     token: Option<String>,
+    /// This till's terminal, from `TERMINAL_ID`; orders sell from its location
+    terminal_id: Option<Uuid>,
+}
+
+fn terminal_id_from_env() -> Option<Uuid> {
+    std::env::var("TERMINAL_ID").ok()?.parse().ok()
 }
 
 impl ApiClient {
@@ -18,6 +82,7 @@ impl ApiClient {
         Self {
             client: Client::new(),
             token: None,
+            terminal_id: terminal_id_from_env(),
         }
     }
 
@@ -25,6 +90,7 @@ impl ApiClient {
         Self {
             client: Client::new(),
             token: Some(token),
+            terminal_id: terminal_id_from_env(),
         }
     }
 
@@ -34,57 +100,57 @@ impl ApiClient {
 
     // Auth endpoints
     pub async fn login(&self, username: &str, password: &str) -> Result<LoginResponse> {
-        let response = self
-            .client
-            .post(format!("{}/auth/login", API_BASE_URL))
-            .json(&serde_json::json!({
-                "username": username,
-                "password": password
-            }))
-            .send()
-            .await?
-            .json::<LoginResponse>()
-            .await?;
-
-        Ok(response)
+        send(
+            self.client
+                .post(format!("{}/auth/login", API_BASE_URL))
+                .json(&LoginRequest {
+                    username: username.to_string(),
+                    password: password.to_string(),
+                }),
+        )
+        .await
     }
 
     // Product endpoints
+    /// Full catalog, including items outside their availability windows so the
+    /// grid can show them dimmed
     pub async fn get_products(&self) -> Result<Vec<ProductResponse>> {
-        let response = self
-            .client
-            .get(format!("{}/products", API_BASE_URL))
-            .send()
-            .await?
-            .json::<Vec<ProductResponse>>()
-            .await?;
-
-        Ok(response)
+        send(
+            self.client
+                .get(format!("{}/products", API_BASE_URL))
+                .query(&[("include_unavailable", "true")]),
+        )
+        .await
     }
 
     pub async fn get_product(&self, id: Uuid) -> Result<ProductResponse> {
-        let response = self
-            .client
-            .get(format!("{}/products/{}", API_BASE_URL, id))
-            .send()
-            .await?
-            .json::<ProductResponse>()
-            .await?;
+        send(self.client.get(format!("{}/products/{}", API_BASE_URL, id))).await
+    }
+
+    pub async fn get_product_modifiers(&self, product_id: Uuid) -> Result<Vec<ModifierGroupResponse>> {
+        send(
+            self.client
+                .get(format!("{}/products/{}/modifiers", API_BASE_URL, product_id)),
+        )
+        .await
+    }
 
-        Ok(response)
+    /// Resolve scanner input to a single product or variant
+    pub async fn lookup_barcode(&self, code: &str) -> Result<BarcodeMatchResponse> {
+        send(
+            self.client
+                .get(format!("{}/products/barcode/{}", API_BASE_URL, code)),
+        )
+        .await
     }
 
     pub async fn search_products(&self, query: &str) -> Result<Vec<ProductResponse>> {
-        let response = self
-            .client
-            .get(format!("{}/products/search", API_BASE_URL))
-            .query(&[("q", query)])
-            .send()
-            .await?
-            .json::<Vec<ProductResponse>>()
-            .await?;
-
-        Ok(response)
+        send(
+            self.client
+                .get(format!("{}/products/search", API_BASE_URL))
+                .query(&[("q", query)]),
+        )
+        .await
     }
 
     // Order endpoints
@@ -92,89 +158,43 @@ impl ApiClient {
         let mut request = self
             .client
             .post(format!("{}/orders", API_BASE_URL))
-            .json(&serde_json::json!({
-                "items": items
-            }));
+            .json(&CreateOrderRequest {
+                items,
+                customer_name: None,
+                customer_email: None,
+                notes: None,
+                terminal_id: self.terminal_id,
+            });
 
         if let Some(token) = &self.token {
             request = request.bearer_auth(token);
         }
 
-        let response = request.send().await?.json::<OrderResponse>().await?;
-
-        Ok(response)
+        send(request).await
     }
 
     pub async fn complete_order(
         &self,
         order_id: Uuid,
-        payment_method: &str,
-    ) -> Result<OrderSummary> {
+        payment_method: PaymentMethod,
+        tender: Option<Money>,
+    ) -> Result<CompletedOrderResponse> {
         let mut request = self
             .client
             .post(format!("{}/orders/{}/complete", API_BASE_URL, order_id))
-            .json(&serde_json::json!({
-                "payment_method": payment_method
-            }));
+            .json(&CompleteOrderRequest {
+                payment_method,
+                payment_reference: None,
+                tender: tender.map(|cash| TenderRequest {
+                    currency: Some(cash.currency),
+                    amount: cash.amount,
+                }),
+            });
 
         if let Some(token) = &self.token {
             request = request.bearer_auth(token);
         }
 
-        let response = request.send().await?.json::<OrderSummary>().await?;
-
-        Ok(response)
+        send(request).await
     }
 }
-
-// API Response types
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LoginResponse {
-    pub token: String,
-    pub user_id: Uuid,
-    pub username: String,
-    pub role: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProductResponse {
-    pub id: Uuid,
-    pub name: String,
-    pub description: Option<String>,
-    pub price_cents: i64,
-    pub currency: String,
-    pub category_id: Option<Uuid>,
-    pub is_active: bool,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OrderItemRequest {
-    pub product_id: Uuid,
-    pub quantity: i32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OrderResponse {
-    pub order: OrderSummary,
-    pub items: Vec<OrderItemResponse>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OrderSummary {
-    pub id: Uuid,
-    pub order_number: String,
-    pub subtotal_cents: i64,
-    pub tax_cents: i64,
-    pub total_cents: i64,
-    pub status: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OrderItemResponse {
-    pub id: Uuid,
-    pub product_id: Option<Uuid>,
-    pub product_name: String,
-    pub quantity: i32,
-    pub unit_price_cents: i64,
-    pub total_price_cents: i64,
-}
@@ -3,11 +3,11 @@
 //! A modern, fast point of sale system built with GPUI
 
 use gpui::{
-    div, prelude::*, px, rgb, size, App, Application, Bounds, Context, Hsla,
-    SharedString, Window, WindowBounds, WindowOptions,
+    div, prelude::*, px, rgb, size, App, Application, Bounds, Context, Div, Hsla,
+    SharedString, Stateful, Window, WindowBounds, WindowOptions,
 };
 use log::info;
-use shared::APP_NAME;
+use shared::{CurrencyCode, Locale, Money, APP_NAME};
 
 /// TREZZA TERMINAL theme
 struct Theme {
@@ -38,14 +38,26 @@ impl Default for Theme {
     }
 }
 
+/// Variant choices shown after tapping a product that comes in sizes or flavors
+struct VariantPicker {
+    product_name: SharedString,
+    /// (variant name, price)
+    variants: Vec<(SharedString, SharedString)>,
+}
+
 struct MainView {
     theme: Theme,
     store_name: SharedString,
+    /// Locale prices are shown in, from `LOCALE`
+    locale: Locale,
+    variant_picker: Option<VariantPicker>,
 }
 
 impl Render for MainView {
-    fn render(&mut self, _win: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, _win: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let t = &self.theme;
+        let locale = self.locale;
+        let price = |cents| price_label(cents, locale);
 
         // Header
         let header = div()
@@ -90,12 +102,33 @@ impl Render for MainView {
                     .grid()
                     .grid_cols(3)
                     .gap(px(8.0))
-                    .child(product_tile("Espresso", "3.00", t))
-                    .child(product_tile("Americano", "3.50", t))
-                    .child(product_tile("Latte", "4.50", t))
-                    .child(product_tile("Cappuccino", "4.25", t))
-                    .child(product_tile("Mocha", "4.75", t))
-                    .child(product_tile("Cold Brew", "4.00", t)),
+                    .child(product_tile("Espresso", &price(300), t))
+                    .child(product_tile("Americano", &price(350), t))
+                    .child(product_tile("Latte", &price(450), t))
+                    .child(product_tile("Cappuccino", &price(425), t))
+                    .child(product_tile("Mocha", &price(475), t))
+                    .child(product_tile("Cold Brew", &price(400), t))
+                    // Breakfast-only item shown outside its availability window
+                    .child(unavailable_tile(product_tile("Breakfast Burrito", &price(650), t), t))
+                    .child(
+                        product_tile("Orange Juice", &format!("from {}", price(350)), t).on_click(
+                            cx.listener(move |this, _, _, cx| {
+                                this.variant_picker = Some(VariantPicker {
+                                    product_name: "Orange Juice".into(),
+                                    variants: vec![
+                                        ("330ml".into(), price_label(350, locale).into()),
+                                        ("500ml".into(), price_label(475, locale).into()),
+                                    ],
+                                });
+                                cx.notify();
+                            }),
+                        ),
+                    ),
+            )
+            .children(
+                self.variant_picker
+                    .as_ref()
+                    .map(|picker| variant_picker(picker, t, cx)),
             );
 
         // Cart panel (placeholder)
@@ -136,8 +169,9 @@ impl Render for MainView {
     }
 }
 
-fn product_tile(name: &str, price: &str, t: &Theme) -> impl IntoElement {
+fn product_tile(name: &str, price: &str, t: &Theme) -> Stateful<Div> {
     div()
+        .id(SharedString::from(format!("product-{}", name)))
         .p_4()
         .rounded(px(12.0))
         .bg(t.surface)
@@ -156,7 +190,70 @@ fn product_tile(name: &str, price: &str, t: &Theme) -> impl IntoElement {
             div()
                 .text_size(px(12.0))
                 .text_color(t.muted)
-                .child(format!("${}", price)),
+                .child(price.to_string()),
+        )
+}
+
+/// Format a demo price in US cents for `locale`
+fn price_label(cents: i64, locale: Locale) -> String {
+    Money::new(cents, CurrencyCode::USD).format(locale)
+}
+
+fn variant_picker(
+    picker: &VariantPicker,
+    t: &Theme,
+    cx: &mut Context<MainView>,
+) -> impl IntoElement {
+    let options = picker.variants.iter().map(|(name, price)| {
+        div()
+            .id(SharedString::from(format!("variant-{}", name)))
+            .px_3()
+            .py_2()
+            .rounded(px(8.0))
+            .bg(t.surface_alt)
+            .border(px(1.0))
+            .border_color(t.border)
+            .flex()
+            .justify_between()
+            .child(div().text_size(px(13.0)).child(name.clone()))
+            .child(
+                div()
+                    .text_size(px(12.0))
+                    .text_color(t.muted)
+                    .child(price.clone()),
+            )
+            .on_click(cx.listener(|this, _, _, cx| {
+                this.variant_picker = None;
+                cx.notify();
+            }))
+    });
+
+    div()
+        .p_4()
+        .rounded(px(12.0))
+        .bg(t.surface)
+        .border(px(1.0))
+        .border_color(t.accent)
+        .flex()
+        .flex_col()
+        .gap_2()
+        .child(
+            div()
+                .text_size(px(14.0))
+                .text_color(t.accent)
+                .child(format!("Choose {}", picker.product_name)),
+        )
+        .children(options)
+        .child(
+            div()
+                .id("variant-cancel")
+                .text_size(px(12.0))
+                .text_color(t.muted)
+                .child("Cancel")
+                .on_click(cx.listener(|this, _, _, cx| {
+                    this.variant_picker = None;
+                    cx.notify();
+                })),
         )
 }
 
@@ -174,7 +271,12 @@ fn main() {
             |_win, cx| {
                 cx.new(|_| MainView {
                     store_name: APP_NAME.into(),
+                    locale: std::env::var("LOCALE")
+                        .ok()
+                        .and_then(|tag| tag.parse().ok())
+                        .unwrap_or_default(),
                     theme: Theme::default(),
+                    variant_picker: None,
                 })
             },
         )
@@ -1,16 +1,20 @@
 //! Receipt generation and printing
 
 use chrono::Utc;
+use shared::{CurrencyCode, Money};
 use uuid::Uuid;
 
-use crate::state::{format_cents, CartItem};
+use crate::state::{cash_rounding, format_money, CartItem};
 
 pub struct Receipt {
     pub order_number: String,
     pub items: Vec<ReceiptItem>,
     pub subtotal_cents: i64,
     pub tax_cents: i64,
+    /// Added to the total by cash rounding; zero for other payment methods
+    pub cash_rounding_cents: i64,
     pub total_cents: i64,
+    pub currency: CurrencyCode,
     pub payment_method: String,
     pub timestamp: String,
 }
@@ -18,32 +22,58 @@ pub struct Receipt {
 pub struct ReceiptItem {
     pub name: String,
     pub quantity: u32,
+    pub modifiers: Vec<ReceiptModifier>,
     pub unit_price_cents: i64,
     pub total_cents: i64,
 }
 
+pub struct ReceiptModifier {
+    pub name: String,
+    pub price_delta_cents: i64,
+}
+
 impl Receipt {
     pub fn from_cart(cart_items: Vec<&CartItem>, payment_method: &str) -> Self {
+        let currency = cart_items
+            .first()
+            .map_or(CurrencyCode::USD, |ci| ci.currency());
         let items: Vec<ReceiptItem> = cart_items
             .iter()
             .map(|ci| ReceiptItem {
-                name: ci.product.name.clone(),
+                name: ci.display_name(),
                 quantity: ci.quantity,
-                unit_price_cents: ci.product.price_cents,
+                modifiers: ci
+                    .modifiers
+                    .iter()
+                    .map(|m| ReceiptModifier {
+                        name: m.name.clone(),
+                        price_delta_cents: m.price_delta_cents,
+                    })
+                    .collect(),
+                unit_price_cents: ci.unit_price_cents(),
                 total_cents: ci.total_cents(),
             })
             .collect();
 
         let subtotal_cents: i64 = items.iter().map(|i| i.total_cents).sum();
         let tax_cents = (subtotal_cents as f64 * shared::DEFAULT_TAX_RATE) as i64;
-        let total_cents = subtotal_cents + tax_cents;
+        let cash_rounding_cents = if payment_method == "cash" {
+            cash_rounding()
+                .adjustment(Money::new(subtotal_cents + tax_cents, currency))
+                .map_or(0, |rounding| rounding.amount)
+        } else {
+            0
+        };
+        let total_cents = subtotal_cents + tax_cents + cash_rounding_cents;
 
         Self {
             order_number: format!("ORD-{}", Utc::now().timestamp()),
             items,
             subtotal_cents,
             tax_cents,
+            cash_rounding_cents,
             total_cents,
+            currency,
             payment_method: payment_method.to_string(),
             timestamp: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
         }
@@ -67,23 +97,40 @@ impl Receipt {
                 "{:<20} x{:<3}  {}\n",
                 truncate(&item.name, 20),
                 item.quantity,
-                format_cents(item.total_cents)
+                format_money(item.total_cents, self.currency)
             ));
+            for modifier in &item.modifiers {
+                if modifier.price_delta_cents != 0 {
+                    output.push_str(&format!(
+                        "  + {:<23}{}\n",
+                        truncate(&modifier.name, 21),
+                        format_money(modifier.price_delta_cents, self.currency)
+                    ));
+                } else {
+                    output.push_str(&format!("  + {}\n", truncate(&modifier.name, 32)));
+                }
+            }
         }
 
         output.push_str("-------------------------------------\n");
         output.push_str(&format!(
             "Subtotal:             {}\n",
-            format_cents(self.subtotal_cents)
+            format_money(self.subtotal_cents, self.currency)
         ));
         output.push_str(&format!(
             "Tax (8.25%):          {}\n",
-            format_cents(self.tax_cents)
+            format_money(self.tax_cents, self.currency)
         ));
+        if self.cash_rounding_cents != 0 {
+            output.push_str(&format!(
+                "Cash rounding:        {}\n",
+                format_money(self.cash_rounding_cents, self.currency)
+            ));
+        }
         output.push_str("-------------------------------------\n");
         output.push_str(&format!(
             "TOTAL:                {}\n",
-            format_cents(self.total_cents)
+            format_money(self.total_cents, self.currency)
         ));
         output.push_str("-------------------------------------\n\n");
         output.push_str(&format!("Payment: {}\n\n", self.payment_method));
@@ -93,6 +140,29 @@ impl Receipt {
         output
     }
 
+    /// Kitchen ticket: items and their modifiers without prices
+    pub fn to_kitchen_ticket(&self) -> String {
+        let mut output = String::new();
+
+        output.push_str("=====================================\n");
+        output.push_str("           KITCHEN TICKET\n");
+        output.push_str("=====================================\n");
+        output.push_str(&format!("Order: {}\n", self.order_number));
+        output.push_str(&format!("Time:  {}\n", self.timestamp));
+        output.push_str("-------------------------------------\n");
+
+        for item in &self.items {
+            output.push_str(&format!("{:>3} x {}\n", item.quantity, item.name));
+            for modifier in &item.modifiers {
+                output.push_str(&format!("        + {}\n", modifier.name));
+            }
+        }
+
+        output.push_str("=====================================\n");
+
+        output
+    }
+
     pub fn print(&self) -> anyhow::Result<()> {
         // In a real implementation, this would send to a thermal printer
         // For now, we'll just write to a file
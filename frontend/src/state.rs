@@ -1,27 +1,94 @@
 //! Application state management
 
 use gpui::{Model, ModelContext};
+use shared::{validation, CashRounding, CurrencyCode, Locale, Money, RoundingMode};
 use std::collections::HashMap;
+use std::sync::OnceLock;
 use uuid::Uuid;
 
-use crate::api::{ApiClient, ProductResponse};
+use crate::api::{
+    ApiClient, ModifierGroupResponse, ModifierOptionResponse, ProductResponse, VariantResponse,
+};
+
+/// Cart lines are keyed by product, variant and chosen modifier options, so a
+/// latte with oat milk and a plain latte stay on separate lines
+pub type CartLineKey = (Uuid, Option<Uuid>, Vec<Uuid>);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CartModifier {
+    pub option_id: Uuid,
+    pub group_name: String,
+    pub name: String,
+    pub price_delta_cents: i64,
+}
+
+impl CartModifier {
+    pub fn new(group: &ModifierGroupResponse, option: &ModifierOptionResponse) -> Self {
+        Self {
+            option_id: option.id,
+            group_name: group.name.clone(),
+            name: option.name.clone(),
+            price_delta_cents: option.price_delta_cents,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct CartItem {
     pub product: ProductResponse,
+    pub variant: Option<VariantResponse>,
+    pub modifiers: Vec<CartModifier>,
     pub quantity: u32,
 }
 
 impl CartItem {
+    pub fn key(&self) -> CartLineKey {
+        line_key(self.product.id, self.variant.as_ref(), &self.modifiers)
+    }
+
+    pub fn display_name(&self) -> String {
+        match &self.variant {
+            Some(variant) => format!("{} ({})", self.product.name, variant.name),
+            None => self.product.name.clone(),
+        }
+    }
+
+    pub fn unit_price_cents(&self) -> i64 {
+        let base = self
+            .variant
+            .as_ref()
+            .map_or(self.product.price.amount, |v| v.price.amount);
+        base + self.modifiers.iter().map(|m| m.price_delta_cents).sum::<i64>()
+    }
+
     pub fn total_cents(&self) -> i64 {
-        self.product.price_cents * self.quantity as i64
+        self.unit_price_cents() * self.quantity as i64
+    }
+
+    /// Currency the line is priced in
+    pub fn currency(&self) -> CurrencyCode {
+        self.variant
+            .as_ref()
+            .map_or(self.product.price.currency, |v| v.price.currency)
     }
 }
 
+fn line_key(
+    product_id: Uuid,
+    variant: Option<&VariantResponse>,
+    modifiers: &[CartModifier],
+) -> CartLineKey {
+    let mut option_ids: Vec<Uuid> = modifiers.iter().map(|m| m.option_id).collect();
+    option_ids.sort();
+    (product_id, variant.map(|v| v.id), option_ids)
+}
+
 pub struct AppState {
     pub api: ApiClient,
-    pub cart: HashMap<Uuid, CartItem>,
+    pub cart: HashMap<CartLineKey, CartItem>,
     pub products: Vec<ProductResponse>,
+    /// Product whose variant picker is open
+    pub variant_picker: Option<ProductResponse>,
     pub current_user: Option<String>,
     pub is_loading: bool,
     pub error_message: Option<String>,
@@ -33,6 +100,7 @@ impl AppState {
             api: ApiClient::new(),
             cart: HashMap::new(),
             products: Vec::new(),
+            variant_picker: None,
             current_user: None,
             is_loading: false,
             error_message: None,
@@ -45,15 +113,40 @@ impl AppState {
     }
 
     pub fn add_to_cart(&mut self, product: ProductResponse, cx: &mut ModelContext<Self>) {
-        let product_id = product.id;
+        self.add_line_to_cart(product, None, Vec::new(), cx);
+    }
 
-        if let Some(item) = self.cart.get_mut(&product_id) {
+    /// Add one unit of a line, within the same quantity and line limits the
+    /// backend enforces on orders
+    pub fn add_line_to_cart(
+        &mut self,
+        product: ProductResponse,
+        variant: Option<VariantResponse>,
+        modifiers: Vec<CartModifier>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let key = line_key(product.id, variant.as_ref(), &modifiers);
+
+        let check = match self.cart.get(&key) {
+            Some(item) => validation::item_quantity(item.quantity as i32 + 1)
+                .map_err(|message| format!("{} quantity {}", item.display_name(), message)),
+            None => validation::cart_lines(self.cart.len() + 1)
+                .map_err(|message| format!("Cannot add {}: {}", product.name, message)),
+        };
+        if let Err(message) = check {
+            self.set_error(Some(message), cx);
+            return;
+        }
+
+        if let Some(item) = self.cart.get_mut(&key) {
             item.quantity += 1;
         } else {
             self.cart.insert(
-                product_id,
+                key,
                 CartItem {
                     product,
+                    variant,
+                    modifiers,
                     quantity: 1,
                 },
             );
@@ -61,12 +154,42 @@ impl AppState {
         cx.notify();
     }
 
-    pub fn remove_from_cart(&mut self, product_id: Uuid, cx: &mut ModelContext<Self>) {
-        if let Some(item) = self.cart.get_mut(&product_id) {
+    /// Handle a product tile tap: products with variants open the picker first
+    pub fn select_product(&mut self, product: ProductResponse, cx: &mut ModelContext<Self>) {
+        // Dimmed tiles; the backend would reject the order anyway
+        if !product.available {
+            return;
+        }
+        if product.variants.is_empty() {
+            self.add_to_cart(product, cx);
+        } else {
+            self.variant_picker = Some(product);
+            cx.notify();
+        }
+    }
+
+    pub fn choose_variant(&mut self, variant_id: Uuid, cx: &mut ModelContext<Self>) {
+        if let Some(product) = self.variant_picker.take() {
+            let variant = product.variants.iter().find(|v| v.id == variant_id).cloned();
+            if variant.is_some() {
+                self.add_line_to_cart(product, variant, Vec::new(), cx);
+                return;
+            }
+        }
+        cx.notify();
+    }
+
+    pub fn close_variant_picker(&mut self, cx: &mut ModelContext<Self>) {
+        self.variant_picker = None;
+        cx.notify();
+    }
+
+    pub fn remove_from_cart(&mut self, key: &CartLineKey, cx: &mut ModelContext<Self>) {
+        if let Some(item) = self.cart.get_mut(key) {
             if item.quantity > 1 {
                 item.quantity -= 1;
             } else {
-                self.cart.remove(&product_id);
+                self.cart.remove(key);
             }
         }
         cx.notify();
@@ -109,7 +232,31 @@ impl AppState {
     }
 }
 
-// Helper functions to format currency
-pub fn format_cents(cents: i64) -> String {
-    format!("${:.2}", cents as f64 / 100.0)
+/// Locale amounts are shown in on this till, from `LOCALE` (default `en-US`)
+pub fn display_locale() -> Locale {
+    static LOCALE: OnceLock<Locale> = OnceLock::new();
+    *LOCALE.get_or_init(|| {
+        std::env::var("LOCALE")
+            .ok()
+            .and_then(|tag| tag.parse().ok())
+            .unwrap_or_default()
+    })
+}
+
+/// Rounding of cash totals on this till, from `CASH_ROUNDING_INCREMENT` in
+/// minor units (default 1, no rounding); must match the backend's setting
+pub fn cash_rounding() -> CashRounding {
+    static CASH_ROUNDING: OnceLock<CashRounding> = OnceLock::new();
+    *CASH_ROUNDING.get_or_init(|| {
+        std::env::var("CASH_ROUNDING_INCREMENT")
+            .ok()
+            .and_then(|increment| increment.parse().ok())
+            .and_then(|increment| CashRounding::new(increment, RoundingMode::HalfUp).ok())
+            .unwrap_or_default()
+    })
+}
+
+/// Format an amount in minor units of `currency` for display
+pub fn format_money(amount: i64, currency: CurrencyCode) -> String {
+    Money::new(amount, currency).format(display_locale())
 }
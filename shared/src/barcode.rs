@@ -0,0 +1,109 @@
+//! Barcode parsing and GTIN check digit validation
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::AppError;
+
+/// Symbology a scanned GTIN was read as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BarcodeKind {
+    Ean8,
+    UpcA,
+    Ean13,
+}
+
+/// Result of parsing raw scanner input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScannedCode {
+    /// Regular trade item, identified by its GTIN
+    Gtin { gtin: String, kind: BarcodeKind },
+    /// In-store weighed item with the price embedded in the barcode
+    WeighedItem { item_prefix: String, price_cents: i64 },
+}
+
+/// Width every GTIN is left-padded to for storage and comparison
+pub const GTIN_WIDTH: usize = 14;
+
+/// Compute the GS1 check digit for a GTIN body (all digits except the check digit)
+pub fn gtin_check_digit(body: &str) -> Option<u8> {
+    let mut sum = 0u32;
+    for (i, c) in body.chars().rev().enumerate() {
+        let digit = c.to_digit(10)?;
+        // Weights alternate 3, 1, 3, ... starting from the digit next to the check digit
+        sum += if i % 2 == 0 { digit * 3 } else { digit };
+    }
+    Some(((10 - sum % 10) % 10) as u8)
+}
+
+/// Validate the check digit of an EAN-8, UPC-A, EAN-13 or GTIN-14
+pub fn is_valid_gtin(code: &str) -> bool {
+    if !matches!(code.len(), 8 | 12 | 13 | 14) || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let (body, check) = code.split_at(code.len() - 1);
+    gtin_check_digit(body) == check.parse().ok()
+}
+
+/// Left-pad a GTIN with zeros to [`GTIN_WIDTH`], so `012345678905` (UPC-A)
+/// and `0012345678905` (the same item as EAN-13) compare equal
+pub fn normalize_gtin(code: &str) -> String {
+    format!("{:0>width$}", code, width = GTIN_WIDTH)
+}
+
+/// Parse scanner input into a GTIN or a weighed-item code.
+///
+/// EAN-13 codes with a `20`–`29` prefix are treated as scale labels laid out as
+/// `2x IIIII PPPPP C`: a two-digit prefix and five-digit item code, which
+/// together identify the product, then the price in cents and the check digit.
+pub fn parse_scanned_code(input: &str) -> Result<ScannedCode, AppError> {
+    let code = input.trim();
+
+    if !code.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(AppError::Validation(format!(
+            "Barcode must contain only digits: {}",
+            code
+        )));
+    }
+    if !matches!(code.len(), 8 | 12 | 13 | 14) {
+        return Err(AppError::Validation(format!(
+            "Barcode must be 8, 12, 13 or 14 digits, got {}",
+            code.len()
+        )));
+    }
+    if code.len() == 14 && !code.starts_with('0') {
+        return Err(AppError::Validation(format!(
+            "Barcode {} is a case-level GTIN-14, not a retail item",
+            code
+        )));
+    }
+    if !is_valid_gtin(code) {
+        return Err(AppError::Validation(format!(
+            "Invalid check digit in barcode {}",
+            code
+        )));
+    }
+
+    // Reduce to the shortest standard form: drop zero padding down to 13 digits,
+    // and a 13-digit code with a leading zero is a UPC-A
+    let trimmed = code.trim_start_matches('0');
+    let ean13 = if code.len() == 8 { None } else { Some(format!("{:0>13}", trimmed)) };
+
+    match ean13 {
+        None => Ok(ScannedCode::Gtin {
+            gtin: normalize_gtin(code),
+            kind: BarcodeKind::Ean8,
+        }),
+        Some(ean13) if ean13.starts_with('2') => Ok(ScannedCode::WeighedItem {
+            item_prefix: ean13[..7].to_string(),
+            price_cents: ean13[7..12].parse().unwrap_or(0),
+        }),
+        Some(ean13) => Ok(ScannedCode::Gtin {
+            gtin: normalize_gtin(&ean13),
+            kind: if ean13.starts_with('0') {
+                BarcodeKind::UpcA
+            } else {
+                BarcodeKind::Ean13
+            },
+        }),
+    }
+}
@@ -9,6 +9,15 @@ pub const MAX_CART_ITEMS: usize = 100;
 /// Maximum quantity per item
 pub const MAX_ITEM_QUANTITY: u32 = 999;
 
+/// Maximum units received in a single restock
+pub const MAX_RESTOCK_QUANTITY: u32 = 100_000;
+
+/// Maximum length, in characters, of order notes and customer names
+pub const MAX_NOTE_LENGTH: usize = 500;
+
+/// Maximum length of an email address (RFC 5321)
+pub const MAX_EMAIL_LENGTH: usize = 254;
+
 /// Application name
 pub const APP_NAME: &str = "TREZZA TERMINAL";
 
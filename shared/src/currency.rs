@@ -0,0 +1,285 @@
+//! Currencies, rounding modes and locale number formats
+
+use rust_decimal::RoundingStrategy;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::errors::AppError;
+
+/// Supported currency codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum CurrencyCode {
+    USD,
+    EUR,
+    GBP,
+    CAD,
+    AUD,
+    CHF,
+    MXN,
+    JPY,
+    KRW,
+    KWD,
+    BHD,
+}
+
+impl CurrencyCode {
+    pub const ALL: [CurrencyCode; 11] = [
+        CurrencyCode::USD,
+        CurrencyCode::EUR,
+        CurrencyCode::GBP,
+        CurrencyCode::CAD,
+        CurrencyCode::AUD,
+        CurrencyCode::CHF,
+        CurrencyCode::MXN,
+        CurrencyCode::JPY,
+        CurrencyCode::KRW,
+        CurrencyCode::KWD,
+        CurrencyCode::BHD,
+    ];
+
+    /// ISO 4217 alphabetic code
+    pub fn code(&self) -> &'static str {
+        match self {
+            CurrencyCode::USD => "USD",
+            CurrencyCode::EUR => "EUR",
+            CurrencyCode::GBP => "GBP",
+            CurrencyCode::CAD => "CAD",
+            CurrencyCode::AUD => "AUD",
+            CurrencyCode::CHF => "CHF",
+            CurrencyCode::MXN => "MXN",
+            CurrencyCode::JPY => "JPY",
+            CurrencyCode::KRW => "KRW",
+            CurrencyCode::KWD => "KWD",
+            CurrencyCode::BHD => "BHD",
+        }
+    }
+
+    /// ISO 4217 minor unit exponent: digits after the decimal point
+    pub fn minor_units(&self) -> u32 {
+        match self {
+            CurrencyCode::JPY | CurrencyCode::KRW => 0,
+            CurrencyCode::KWD | CurrencyCode::BHD => 3,
+            _ => 2,
+        }
+    }
+
+    /// Minor units in one major unit (100 cents to the dollar, 1 yen to the yen)
+    pub fn minor_per_major(&self) -> i64 {
+        10i64.pow(self.minor_units())
+    }
+
+    /// Symbol shown next to amounts; currencies without a widely recognised
+    /// symbol use their code
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CurrencyCode::USD | CurrencyCode::CAD | CurrencyCode::AUD | CurrencyCode::MXN => "$",
+            CurrencyCode::EUR => "€",
+            CurrencyCode::GBP => "£",
+            CurrencyCode::JPY => "¥",
+            CurrencyCode::KRW => "₩",
+            CurrencyCode::CHF => "CHF",
+            CurrencyCode::KWD => "KWD",
+            CurrencyCode::BHD => "BHD",
+        }
+    }
+}
+
+impl fmt::Display for CurrencyCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl std::str::FromStr for CurrencyCode {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CurrencyCode::ALL
+            .into_iter()
+            .find(|currency| currency.code() == s)
+            .ok_or_else(|| AppError::Validation(format!("Unsupported currency: {}", s)))
+    }
+}
+
+/// How to round an amount that falls between two minor units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    /// Halves round away from zero (2.5 → 3, -2.5 → -3)
+    HalfUp,
+    /// Halves round towards zero (2.5 → 2)
+    HalfDown,
+    /// Bankers' rounding: halves round to the even neighbour (2.5 → 2, 3.5 → 4)
+    HalfEven,
+    /// Always away from zero
+    Up,
+    /// Always towards zero (truncation)
+    Down,
+    /// Always towards positive infinity
+    Ceiling,
+    /// Always towards negative infinity
+    Floor,
+}
+
+impl RoundingMode {
+    pub(crate) fn strategy(self) -> RoundingStrategy {
+        match self {
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::HalfDown => RoundingStrategy::MidpointTowardZero,
+            RoundingMode::HalfEven => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::Up => RoundingStrategy::AwayFromZero,
+            RoundingMode::Down => RoundingStrategy::ToZero,
+            RoundingMode::Ceiling => RoundingStrategy::ToPositiveInfinity,
+            RoundingMode::Floor => RoundingStrategy::ToNegativeInfinity,
+        }
+    }
+}
+
+/// Where a locale puts the currency symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolPosition {
+    /// `$1,234.50`; alphabetic symbols and `de-CH` get a space (`CHF 1’234.50`)
+    Before,
+    /// `1.234,50 €`, always separated by a no-break space
+    After,
+}
+
+/// Locales amounts can be formatted for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en-US")]
+    EnUs,
+    #[serde(rename = "en-GB")]
+    EnGb,
+    #[serde(rename = "en-CA")]
+    EnCa,
+    #[serde(rename = "fr-CA")]
+    FrCa,
+    #[serde(rename = "fr-FR")]
+    FrFr,
+    #[serde(rename = "de-DE")]
+    DeDe,
+    #[serde(rename = "de-CH")]
+    DeCh,
+    #[serde(rename = "ja-JP")]
+    JaJp,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 8] = [
+        Locale::EnUs,
+        Locale::EnGb,
+        Locale::EnCa,
+        Locale::FrCa,
+        Locale::FrFr,
+        Locale::DeDe,
+        Locale::DeCh,
+        Locale::JaJp,
+    ];
+
+    /// BCP 47 language tag
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::EnGb => "en-GB",
+            Locale::EnCa => "en-CA",
+            Locale::FrCa => "fr-CA",
+            Locale::FrFr => "fr-FR",
+            Locale::DeDe => "de-DE",
+            Locale::DeCh => "de-CH",
+            Locale::JaJp => "ja-JP",
+        }
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        match self {
+            Locale::FrCa | Locale::FrFr | Locale::DeDe => ',',
+            _ => '.',
+        }
+    }
+
+    /// Separator between groups of three integer digits
+    pub fn grouping_separator(&self) -> char {
+        match self {
+            Locale::FrCa => '\u{a0}',
+            Locale::FrFr => '\u{202f}',
+            Locale::DeDe => '.',
+            Locale::DeCh => '’',
+            _ => ',',
+        }
+    }
+
+    pub fn symbol_position(&self) -> SymbolPosition {
+        match self {
+            Locale::FrCa | Locale::FrFr | Locale::DeDe => SymbolPosition::After,
+            _ => SymbolPosition::Before,
+        }
+    }
+
+    /// Format a number of minor units with the currency's decimal places,
+    /// grouping and symbol, e.g. `-$1,234.50` or `1.234,50 €`
+    pub fn format_minor(&self, amount: i64, currency: CurrencyCode) -> String {
+        let digits = currency.minor_units() as usize;
+        let magnitude = amount.unsigned_abs();
+        let per_major = currency.minor_per_major() as u64;
+
+        let mut number = group_digits(magnitude / per_major, self.grouping_separator());
+        if digits > 0 {
+            number.push(self.decimal_separator());
+            number.push_str(&format!(
+                "{:0width$}",
+                magnitude % per_major,
+                width = digits
+            ));
+        }
+
+        let symbol = currency.symbol();
+        let formatted = match self.symbol_position() {
+            SymbolPosition::Before
+                if *self == Locale::DeCh || symbol.chars().all(char::is_alphabetic) =>
+            {
+                format!("{}\u{a0}{}", symbol, number)
+            }
+            SymbolPosition::Before => format!("{}{}", symbol, number),
+            SymbolPosition::After => format!("{}\u{a0}{}", number, symbol),
+        };
+
+        if amount < 0 {
+            format!("-{}", formatted)
+        } else {
+            formatted
+        }
+    }
+}
+
+fn group_digits(value: u64, separator: char) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl std::str::FromStr for Locale {
+    type Err = AppError;
+
+    /// Accepts tags case-insensitively, with `-` or `_` (`en-US`, `en_us`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.replace('_', "-");
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.tag().eq_ignore_ascii_case(&normalized))
+            .ok_or_else(|| AppError::Validation(format!("Unsupported locale: {}", s)))
+    }
+}
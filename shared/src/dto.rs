@@ -0,0 +1,201 @@
+//! Request and response bodies of the HTTP API
+//!
+//! The backend converts its database rows into these types and the terminal
+//! deserializes the same types, so changing the API shape breaks the build on
+//! both sides instead of failing at runtime. Amounts are [`Money`] in the
+//! currency of the product or order they belong to.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::currency::CurrencyCode;
+use crate::types::{Money, OrderStatus, PaymentMethod, Role};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: Role,
+}
+
+/// A catalog product with its active variants
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub price: Money,
+    pub category_id: Option<Uuid>,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
+    pub is_active: bool,
+    #[serde(default)]
+    pub variants: Vec<VariantResponse>,
+    /// Inside its availability windows at the listing time
+    #[serde(default = "default_available")]
+    pub available: bool,
+}
+
+fn default_available() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantResponse {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub name: String,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
+    pub price: Money,
+}
+
+/// The single product or variant a scanned code resolved to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarcodeMatchResponse {
+    pub product: ProductResponse,
+    pub variant: Option<VariantResponse>,
+    /// Price read from a weighed-item scale label, overriding the catalog price
+    pub embedded_price: Option<Money>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifierGroupResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub is_required: bool,
+    pub min_selections: i32,
+    pub max_selections: i32,
+    pub options: Vec<ModifierOptionResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifierOptionResponse {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub name: String,
+    /// Minor units of the currency of the item the option is applied to
+    pub price_delta_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderItemRequest {
+    pub product_id: Uuid,
+    #[serde(default)]
+    pub variant_id: Option<Uuid>,
+    pub quantity: i32,
+    #[serde(default)]
+    pub modifier_option_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateOrderRequest {
+    pub items: Vec<OrderItemRequest>,
+    pub customer_name: Option<String>,
+    pub customer_email: Option<String>,
+    pub notes: Option<String>,
+    /// Terminal taking the order; stock is taken from its location, or from the
+    /// default location when omitted
+    #[serde(default)]
+    pub terminal_id: Option<Uuid>,
+}
+
+/// Cash handed over when completing an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenderRequest {
+    /// Defaults to the order's currency
+    pub currency: Option<CurrencyCode>,
+    /// Minor units of `currency`
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteOrderRequest {
+    pub payment_method: PaymentMethod,
+    pub payment_reference: Option<String>,
+    /// Cash handed over, possibly in a foreign currency
+    pub tender: Option<TenderRequest>,
+}
+
+/// An order's header without its lines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderSummary {
+    pub id: Uuid,
+    pub order_number: String,
+    pub customer_name: Option<String>,
+    pub customer_email: Option<String>,
+    pub subtotal: Money,
+    pub tax: Money,
+    /// Added to the total when a cash total was rounded
+    pub cash_rounding: Money,
+    pub total: Money,
+    pub status: OrderStatus,
+    pub payment_method: Option<PaymentMethod>,
+    pub location_id: Option<Uuid>,
+    pub terminal_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderItemResponse {
+    pub id: Uuid,
+    pub product_id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
+    pub product_name: String,
+    pub quantity: i32,
+    pub unit_price: Money,
+    pub total_price: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderItemModifierResponse {
+    pub id: Uuid,
+    pub order_item_id: Uuid,
+    pub modifier_option_id: Option<Uuid>,
+    pub group_name: String,
+    pub option_name: String,
+    pub price_delta: Money,
+}
+
+/// Cash tendered for an order and what it was worth in the order's currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenderResponse {
+    pub id: Uuid,
+    /// As handed over, possibly in a foreign currency
+    pub amount: Money,
+    /// Rate snapshot the tender converted at; 1 in the order's own currency
+    pub rate: Decimal,
+    pub exchange_rate_id: Option<Uuid>,
+    pub base_amount: Money,
+    /// Given back in the order's currency
+    pub change: Money,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An order with its lines, chosen modifiers and tenders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderResponse {
+    pub order: OrderSummary,
+    pub items: Vec<OrderItemResponse>,
+    pub modifiers: Vec<OrderItemModifierResponse>,
+    #[serde(default)]
+    pub tenders: Vec<TenderResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletedOrderResponse {
+    #[serde(flatten)]
+    pub order: OrderSummary,
+    /// What was tendered and the change due, when a tender was given
+    pub tender: Option<TenderResponse>,
+}
@@ -1,20 +1,35 @@
 //! Error types for the TREZZA TERMINAL application
+//!
+//! Errors cross the API as `{"code": ..., "message": ..., "details": ...}`.
+//! The code is the variant name in snake_case and the details are its fields,
+//! so renaming a variant or a field is a breaking API change.
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::currency::CurrencyCode;
+use crate::validation::FieldError;
+
 /// Main application error type
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "code", content = "details", rename_all = "snake_case")]
 pub enum AppError {
     #[error("Product not found: {id}")]
     ProductNotFound { id: Uuid },
 
+    #[error("Category not found: {id}")]
+    CategoryNotFound { id: Uuid },
+
     #[error("Invalid quantity: {quantity}")]
     InvalidQuantity { quantity: u32 },
 
     #[error("Cart is empty")]
     EmptyCart,
 
+    #[error("Invalid input: {}", join_field_errors(errors))]
+    InvalidFields { errors: Vec<FieldError> },
+
     #[error("Order not found: {id}")]
     OrderNotFound { id: Uuid },
 
@@ -28,6 +43,39 @@ pub enum AppError {
         available: u32,
     },
 
+    #[error("{name} is not available at this time")]
+    ProductUnavailable { id: Uuid, name: String },
+
+    #[error("No product found for barcode: {barcode}")]
+    BarcodeNotFound { barcode: String },
+
+    #[error("Barcode {barcode} matches more than one product")]
+    AmbiguousBarcode { barcode: String },
+
+    #[error("SKU already in use: {sku}")]
+    DuplicateSku { sku: String },
+
+    #[error("Currency mismatch: expected {expected}, got {found}")]
+    CurrencyMismatch {
+        expected: CurrencyCode,
+        found: CurrencyCode,
+    },
+
+    #[error("Amount out of range")]
+    AmountOverflow,
+
+    #[error("Manager approval required: {0}")]
+    ApprovalRequired(String),
+
+    #[error("Not authenticated: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("{0} not found")]
+    NotFound(String),
+
     #[error("Database error: {0}")]
     Database(String),
 
@@ -41,5 +89,84 @@ pub enum AppError {
     Internal(String),
 }
 
+impl AppError {
+    /// HTTP status the error is reported with
+    pub fn status_code(&self) -> u16 {
+        match self {
+            AppError::ProductNotFound { .. }
+            | AppError::CategoryNotFound { .. }
+            | AppError::OrderNotFound { .. }
+            | AppError::BarcodeNotFound { .. }
+            | AppError::NotFound(_) => 404,
+            AppError::DuplicateSku { .. }
+            | AppError::AmbiguousBarcode { .. }
+            | AppError::InsufficientInventory { .. } => 409,
+            AppError::InvalidQuantity { .. }
+            | AppError::EmptyCart
+            | AppError::InvalidFields { .. }
+            | AppError::ProductUnavailable { .. }
+            | AppError::CurrencyMismatch { .. }
+            | AppError::AmountOverflow
+            | AppError::Validation(_) => 422,
+            AppError::PaymentFailed { .. } => 402,
+            AppError::Unauthorized(_) => 401,
+            AppError::ApprovalRequired(_) | AppError::Forbidden(_) => 403,
+            AppError::Database(_) | AppError::Network(_) | AppError::Internal(_) => 500,
+        }
+    }
+
+    /// Database and internal failures are reported without their cause, which
+    /// may describe the schema or infrastructure
+    pub fn sanitized(self) -> AppError {
+        match self {
+            AppError::Database(_) | AppError::Internal(_) | AppError::Network(_) => {
+                AppError::Internal("Internal server error".to_string())
+            }
+            error => error,
+        }
+    }
+}
+
+fn join_field_errors(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(FieldError::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Body of every API error response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    /// Human readable description, suitable for showing to the cashier
+    pub message: String,
+    /// Stable `code` and structured `details`
+    #[serde(flatten)]
+    pub error: AppError,
+}
+
+impl From<AppError> for ErrorResponse {
+    fn from(error: AppError) -> Self {
+        let error = error.sanitized();
+        Self {
+            message: error.to_string(),
+            error,
+        }
+    }
+}
+
+/// The body is sanitized; the original error rides along in the response
+/// extensions so the server can log what the client isn't told
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for AppError {
+    fn into_response(self) -> axum::response::Response {
+        let status = axum::http::StatusCode::from_u16(self.status_code())
+            .unwrap_or(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (status, axum::Json(ErrorResponse::from(self.clone()))).into_response();
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
 /// Result type alias for convenience
 pub type AppResult<T> = Result<T, AppError>;
\ No newline at end of file
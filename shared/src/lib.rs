@@ -3,11 +3,22 @@
 //! This crate contains common data types, constants, and utilities
 //! shared between the frontend and backend components.
 
+pub mod barcode;
+pub mod currency;
+pub mod dto;
 pub mod types;
 pub mod errors;
 pub mod constants;
+pub mod validation;
+
+#[cfg(feature = "sqlx")]
+mod pg;
 
 // Re-export commonly used types
+pub use barcode::*;
+pub use currency::*;
+pub use dto::*;
 pub use types::*;
 pub use errors::*;
-pub use constants::*;
\ No newline at end of file
+pub use constants::*;
+pub use validation::{FieldError, FieldErrors, Validate};
\ No newline at end of file
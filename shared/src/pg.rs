@@ -0,0 +1,54 @@
+//! Postgres column mappings for the shared enums
+//!
+//! The columns are `VARCHAR`s limited by CHECK constraints to the names
+//! returned by `as_str`/`code`, so they stay readable in SQL and reports. A
+//! value the enum doesn't know fails to decode instead of being guessed at.
+
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type};
+
+use crate::currency::CurrencyCode;
+use crate::types::{OrderStatus, PaymentMethod, Role};
+
+macro_rules! text_column {
+    ($ty:ty, $name:ident) => {
+        impl Type<Postgres> for $ty {
+            fn type_info() -> PgTypeInfo {
+                <&str as Type<Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &PgTypeInfo) -> bool {
+                <&str as Type<Postgres>>::compatible(ty)
+            }
+        }
+
+        impl PgHasArrayType for $ty {
+            fn array_type_info() -> PgTypeInfo {
+                <&str as PgHasArrayType>::array_type_info()
+            }
+
+            fn array_compatible(ty: &PgTypeInfo) -> bool {
+                <&str as PgHasArrayType>::array_compatible(ty)
+            }
+        }
+
+        impl Encode<'_, Postgres> for $ty {
+            fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+                <&str as Encode<Postgres>>::encode(self.$name(), buf)
+            }
+        }
+
+        impl<'r> Decode<'r, Postgres> for $ty {
+            fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+                Ok(<&str as Decode<Postgres>>::decode(value)?.parse()?)
+            }
+        }
+    };
+}
+
+text_column!(OrderStatus, as_str);
+text_column!(PaymentMethod, as_str);
+text_column!(Role, as_str);
+text_column!(CurrencyCode, code);
@@ -3,61 +3,332 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::{Decimal, prelude::ToPrimitive};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 use uuid::Uuid;
 
+use crate::currency::{CurrencyCode, Locale, RoundingMode};
+use crate::errors::{AppError, AppResult};
+
 /// Represents monetary amounts with precision
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+///
+/// Arithmetic only combines amounts of the same currency. The `checked_*`
+/// methods report a currency mismatch or `i64` overflow as an error; the
+/// operators panic on either, like integer overflow in debug builds. There is
+/// no `Ord`: amounts are compared with `checked_cmp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Money {
-    /// Amount in the smallest currency unit (e.g., cents for USD)
+    /// Amount in the smallest currency unit (e.g., cents for USD, yen for JPY)
     pub amount: i64,
     /// Currency code (ISO 4217)
     pub currency: CurrencyCode,
 }
 
 impl Money {
+    /// Create a new Money instance in the given currency
+    pub fn new(amount: i64, currency: CurrencyCode) -> Self {
+        Self { amount, currency }
+    }
+
+    /// Zero in the given currency
+    pub fn zero(currency: CurrencyCode) -> Self {
+        Self::new(0, currency)
+    }
+
     /// Create a new Money instance from cents
     pub fn from_cents(cents: i64) -> Self {
-        Self {
-            amount: cents,
-            currency: CurrencyCode::USD,
-        }
+        Self::new(cents, CurrencyCode::USD)
+    }
+
+    /// Create a new Money instance from an amount in major units (e.g. dollars),
+    /// rounded to the currency's minor unit with `mode`
+    pub fn from_decimal(value: Decimal, currency: CurrencyCode, mode: RoundingMode) -> AppResult<Self> {
+        value
+            .checked_mul(Decimal::from(currency.minor_per_major()))
+            .map(|minor| minor.round_dp_with_strategy(0, mode.strategy()))
+            .and_then(|minor| minor.to_i64())
+            .map(|amount| Self::new(amount, currency))
+            .ok_or(AppError::AmountOverflow)
     }
 
-    /// Create a new Money instance from dollars
-    pub fn from_dollars(dollars: Decimal) -> Self {
-        let cents = (dollars * Decimal::from(100)).to_i64().unwrap_or(0);
-        Self::from_cents(cents)
+    /// Create a new Money instance from dollars, rounding half-cents up
+    pub fn from_dollars(dollars: Decimal) -> AppResult<Self> {
+        Self::from_decimal(dollars, CurrencyCode::USD, RoundingMode::HalfUp)
     }
 
-    /// Get the amount as a decimal (dollars)
+    /// Get the amount as a decimal in major units (e.g. dollars)
     pub fn as_decimal(&self) -> Decimal {
-        Decimal::from(self.amount) / Decimal::from(100)
+        Decimal::new(self.amount, self.currency.minor_units())
+    }
+
+    /// Convert to another currency at `rate` units of `to` per unit of this
+    /// currency, rounding to `to`'s minor unit with `mode`
+    pub fn convert(&self, to: CurrencyCode, rate: Decimal, mode: RoundingMode) -> AppResult<Money> {
+        if rate <= Decimal::ZERO {
+            return Err(AppError::Validation(format!(
+                "Exchange rate must be positive, got {}",
+                rate
+            )));
+        }
+        let value = self.as_decimal().checked_mul(rate).ok_or(AppError::AmountOverflow)?;
+        Money::from_decimal(value, to, mode)
+    }
+
+    /// Format for display in `locale`, e.g. `$1,234.50` or `1.234,50 €`
+    pub fn format(&self, locale: Locale) -> String {
+        locale.format_minor(self.amount, self.currency)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount < 0
+    }
+
+    fn same_currency(&self, other: &Money) -> AppResult<()> {
+        if self.currency != other.currency {
+            return Err(AppError::CurrencyMismatch {
+                expected: self.currency,
+                found: other.currency,
+            });
+        }
+        Ok(())
+    }
+
+    fn with_amount(&self, amount: Option<i64>) -> AppResult<Money> {
+        amount
+            .map(|amount| Money::new(amount, self.currency))
+            .ok_or(AppError::AmountOverflow)
+    }
+
+    /// Add two amounts of the same currency
+    pub fn checked_add(&self, other: Money) -> AppResult<Money> {
+        self.same_currency(&other)?;
+        self.with_amount(self.amount.checked_add(other.amount))
+    }
+
+    /// Compare with an amount of the same currency
+    pub fn checked_cmp(&self, other: &Money) -> AppResult<Ordering> {
+        self.same_currency(other)?;
+        Ok(self.amount.cmp(&other.amount))
+    }
+
+    /// Subtract an amount of the same currency
+    pub fn checked_sub(&self, other: Money) -> AppResult<Money> {
+        self.same_currency(&other)?;
+        self.with_amount(self.amount.checked_sub(other.amount))
+    }
+
+    pub fn checked_neg(&self) -> AppResult<Money> {
+        self.with_amount(self.amount.checked_neg())
+    }
+
+    /// Multiply by a (possibly negative) factor
+    pub fn checked_mul(&self, factor: i64) -> AppResult<Money> {
+        self.with_amount(self.amount.checked_mul(factor))
     }
 
     /// Add two Money amounts
+    ///
+    /// Panics on a currency mismatch or overflow; see [`Money::checked_add`].
     pub fn add(&self, other: Money) -> Money {
-        Money {
-            amount: self.amount + other.amount,
-            currency: self.currency,
-        }
+        *self + other
     }
 
     /// Multiply by quantity
+    ///
+    /// Panics on overflow; see [`Money::checked_mul`].
     pub fn multiply(&self, quantity: u32) -> Money {
-        Money {
-            amount: self.amount * quantity as i64,
-            currency: self.currency,
+        *self * quantity
+    }
+
+    /// `percent` percent of the amount, rounded to a whole minor unit with `mode`
+    pub fn percentage(&self, percent: Decimal, mode: RoundingMode) -> AppResult<Money> {
+        let amount = Decimal::from(self.amount)
+            .checked_mul(percent)
+            .and_then(|value| value.checked_div(Decimal::from(100)))
+            .map(|value| value.round_dp_with_strategy(0, mode.strategy()))
+            .and_then(|value| value.to_i64());
+        self.with_amount(amount)
+    }
+
+    /// Split the amount in proportion to `weights` without losing minor units.
+    ///
+    /// Each share is rounded towards zero and the units left over go one at a
+    /// time to the shares with the largest remainders, earliest first on ties,
+    /// so the shares always add up to the original amount.
+    pub fn allocate(&self, weights: &[u32]) -> AppResult<Vec<Money>> {
+        let total_weight: u128 = weights.iter().map(|&w| w as u128).sum();
+        if total_weight == 0 {
+            return Err(AppError::Validation(
+                "Allocation needs at least one positive weight".to_string(),
+            ));
+        }
+
+        // Allocate the magnitude, then give every share the amount's sign
+        let magnitude = self.amount.unsigned_abs() as u128;
+        let mut shares: Vec<u128> = Vec::with_capacity(weights.len());
+        let mut remainders: Vec<(u128, usize)> = Vec::with_capacity(weights.len());
+        for (index, &weight) in weights.iter().enumerate() {
+            let exact = magnitude * weight as u128;
+            shares.push(exact / total_weight);
+            remainders.push((exact % total_weight, index));
+        }
+
+        let left_over = magnitude - shares.iter().sum::<u128>();
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for &(_, index) in remainders.iter().take(left_over as usize) {
+            shares[index] += 1;
+        }
+
+        Ok(shares
+            .into_iter()
+            .map(|share| {
+                // Every share is at most the magnitude, so it fits back into an i64
+                let share = share as i128;
+                let amount = if self.amount < 0 { -share } else { share };
+                Money::new(amount as i64, self.currency)
+            })
+            .collect())
+    }
+
+    /// Split the amount into `parts` near-equal shares without losing minor units
+    pub fn split(&self, parts: usize) -> AppResult<Vec<Money>> {
+        self.allocate(&vec![1; parts])
+    }
+
+    /// Round to a multiple of `increment` minor units with `mode`
+    pub fn round_to_increment(&self, increment: i64, mode: RoundingMode) -> AppResult<Money> {
+        if increment <= 0 {
+            return Err(AppError::Validation(format!(
+                "Rounding increment must be positive, got {}",
+                increment
+            )));
+        }
+        let steps = (Decimal::from(self.amount) / Decimal::from(increment))
+            .round_dp_with_strategy(0, mode.strategy())
+            .to_i64()
+            .ok_or(AppError::AmountOverflow)?;
+        self.with_amount(steps.checked_mul(increment))
+    }
+}
+
+/// Rounding of cash totals to the smallest coin in circulation, such as 0.05
+/// where pennies have been withdrawn
+///
+/// Only cash payments are rounded; cards settle to the exact minor unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CashRounding {
+    /// Smallest cash amount in minor units; 1 turns rounding off
+    pub increment: i64,
+    pub mode: RoundingMode,
+}
+
+impl Default for CashRounding {
+    fn default() -> Self {
+        Self {
+            increment: 1,
+            mode: RoundingMode::HalfUp,
+        }
+    }
+}
+
+impl CashRounding {
+    pub fn new(increment: i64, mode: RoundingMode) -> AppResult<Self> {
+        if increment <= 0 {
+            return Err(AppError::Validation(format!(
+                "Cash rounding increment must be positive, got {}",
+                increment
+            )));
+        }
+        Ok(Self { increment, mode })
+    }
+
+    /// Round to the nearest 0.05 of the currency, halves away from zero, as in
+    /// Canada: totals ending in 1–2 and 6–7 round down, 3–4 and 8–9 round up
+    pub fn nickel() -> Self {
+        Self {
+            increment: 5,
+            mode: RoundingMode::HalfUp,
         }
     }
+
+    pub fn is_enabled(&self) -> bool {
+        self.increment > 1
+    }
+
+    /// The cash amount due for `total`
+    pub fn round(&self, total: Money) -> AppResult<Money> {
+        total.round_to_increment(self.increment, self.mode)
+    }
+
+    /// What rounding adds to `total`; negative when it rounds down
+    pub fn adjustment(&self, total: Money) -> AppResult<Money> {
+        self.round(total)?.checked_sub(total)
+    }
 }
 
-/// Supported currency codes
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub enum CurrencyCode {
-    USD,
-    EUR,
-    GBP,
-    CAD,
+/// Formats for `en-US`; use [`Money::format`] for other locales
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(Locale::EnUs))
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        self.checked_add(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        self.checked_sub(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        *self = *self - other;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        self.checked_neg().unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl Mul<u32> for Money {
+    type Output = Money;
+
+    fn mul(self, quantity: u32) -> Money {
+        self.checked_mul(quantity as i64)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl Mul<i64> for Money {
+    type Output = Money;
+
+    fn mul(self, factor: i64) -> Money {
+        self.checked_mul(factor).unwrap_or_else(|e| panic!("{}", e))
+    }
 }
 
 /// Product information
@@ -83,6 +354,34 @@ pub struct Category {
     pub sort_order: i32,
 }
 
+/// Group of modifier options offered for a product (e.g. size, milk)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifierGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub is_required: bool,
+    pub min_selections: u32,
+    pub max_selections: u32,
+    pub options: Vec<ModifierOption>,
+}
+
+/// Selectable option within a modifier group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifierOption {
+    pub id: Uuid,
+    pub name: String,
+    pub price_delta: Money,
+}
+
+/// Modifier option chosen for a cart item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CartItemModifier {
+    pub option_id: Uuid,
+    pub group_name: String,
+    pub option_name: String,
+    pub price_delta: Money,
+}
+
 /// Shopping cart item
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CartItem {
@@ -90,6 +389,7 @@ pub struct CartItem {
     pub product_id: Uuid,
     pub product_name: String,
     pub quantity: u32,
+    pub modifiers: Vec<CartItemModifier>,
     pub unit_price: Money,
     pub total_price: Money,
     pub created_at: DateTime<Utc>,
@@ -98,14 +398,23 @@ pub struct CartItem {
 impl CartItem {
     /// Create a new cart item
     pub fn new(product: &Product, quantity: u32) -> Self {
-        let total_price = product.price.multiply(quantity);
+        Self::with_modifiers(product, quantity, Vec::new())
+    }
+
+    /// Create a new cart item with modifiers; their price deltas are added to the unit price
+    pub fn with_modifiers(product: &Product, quantity: u32, modifiers: Vec<CartItemModifier>) -> Self {
+        let unit_price = modifiers
+            .iter()
+            .fold(product.price, |price, m| price.add(m.price_delta));
+        let total_price = unit_price.multiply(quantity);
 
         Self {
             id: Uuid::new_v4(),
             product_id: product.id,
             product_name: product.name.clone(),
             quantity,
-            unit_price: product.price,
+            modifiers,
+            unit_price,
             total_price,
             created_at: Utc::now(),
         }
@@ -114,6 +423,7 @@ impl CartItem {
 
 /// Order status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum OrderStatus {
     Draft,
     Pending,
@@ -123,8 +433,49 @@ pub enum OrderStatus {
     Refunded,
 }
 
+impl OrderStatus {
+    pub const ALL: [OrderStatus; 6] = [
+        OrderStatus::Draft,
+        OrderStatus::Pending,
+        OrderStatus::Processing,
+        OrderStatus::Completed,
+        OrderStatus::Cancelled,
+        OrderStatus::Refunded,
+    ];
+
+    /// Name stored in the database and used on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Draft => "draft",
+            OrderStatus::Pending => "pending",
+            OrderStatus::Processing => "processing",
+            OrderStatus::Completed => "completed",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Refunded => "refunded",
+        }
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for OrderStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OrderStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| AppError::Validation(format!("Unknown order status: {}", s)))
+    }
+}
+
 /// Payment method
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PaymentMethod {
     Cash,
     CreditCard,
@@ -133,6 +484,89 @@ pub enum PaymentMethod {
     Crypto,
 }
 
+impl PaymentMethod {
+    pub const ALL: [PaymentMethod; 5] = [
+        PaymentMethod::Cash,
+        PaymentMethod::CreditCard,
+        PaymentMethod::DebitCard,
+        PaymentMethod::GiftCard,
+        PaymentMethod::Crypto,
+    ];
+
+    /// Name stored in the database and used on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentMethod::Cash => "cash",
+            PaymentMethod::CreditCard => "credit_card",
+            PaymentMethod::DebitCard => "debit_card",
+            PaymentMethod::GiftCard => "gift_card",
+            PaymentMethod::Crypto => "crypto",
+        }
+    }
+}
+
+impl fmt::Display for PaymentMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for PaymentMethod {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PaymentMethod::ALL
+            .into_iter()
+            .find(|method| method.as_str() == s)
+            .ok_or_else(|| AppError::Validation(format!("Unknown payment method: {}", s)))
+    }
+}
+
+/// What a staff member is allowed to do
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Manager,
+    #[default]
+    Cashier,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Admin, Role::Manager, Role::Cashier];
+
+    /// Name stored in the database and used on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Manager => "manager",
+            Role::Cashier => "cashier",
+        }
+    }
+
+    /// Admins can do anything a manager can
+    pub fn is_manager(&self) -> bool {
+        matches!(self, Role::Admin | Role::Manager)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Role::ALL
+            .into_iter()
+            .find(|role| role.as_str() == s)
+            .ok_or_else(|| AppError::Validation(format!("Unknown role: {}", s)))
+    }
+}
+
 /// Complete order information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
@@ -0,0 +1,207 @@
+//! Request validation shared by the backend and the terminal
+//!
+//! Checks collect every problem with a request rather than stopping at the
+//! first, each against the field it came from, so a form can flag them all.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::constants::{
+    MAX_CART_ITEMS, MAX_EMAIL_LENGTH, MAX_ITEM_QUANTITY, MAX_NOTE_LENGTH, MAX_RESTOCK_QUANTITY,
+};
+use crate::dto::CreateOrderRequest;
+use crate::errors::AppError;
+
+/// A problem with one field of a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// Path to the field, e.g. `items[2].quantity`
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Collects field errors while a request is checked
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Record the outcome of one of the checks in this module
+    pub fn check(&mut self, field: impl Into<String>, result: Result<(), String>) {
+        if let Err(message) = result {
+            self.add(field, message);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// `AppError::InvalidFields` when anything was recorded
+    pub fn into_result(self) -> Result<(), AppError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::InvalidFields { errors: self.0 })
+        }
+    }
+}
+
+/// Requests that can check their own fields before they are acted on
+pub trait Validate {
+    fn validate(&self) -> Result<(), AppError>;
+}
+
+/// Quantity of a single order or cart line
+pub fn item_quantity(quantity: i32) -> Result<(), String> {
+    if quantity <= 0 {
+        return Err(format!("must be at least 1, got {}", quantity));
+    }
+    if quantity as u32 > MAX_ITEM_QUANTITY {
+        return Err(format!(
+            "must be at most {}, got {}",
+            MAX_ITEM_QUANTITY, quantity
+        ));
+    }
+    Ok(())
+}
+
+/// Number of distinct lines in a cart or order
+pub fn cart_lines(lines: usize) -> Result<(), String> {
+    if lines > MAX_CART_ITEMS {
+        return Err(format!(
+            "a cart holds at most {} lines, got {}",
+            MAX_CART_ITEMS, lines
+        ));
+    }
+    Ok(())
+}
+
+/// Units received in a single restock
+pub fn restock_quantity(quantity: i32) -> Result<(), String> {
+    if quantity <= 0 {
+        return Err(format!("must be at least 1, got {}", quantity));
+    }
+    if quantity as u32 > MAX_RESTOCK_QUANTITY {
+        return Err(format!(
+            "must be at most {} per restock, got {}",
+            MAX_RESTOCK_QUANTITY, quantity
+        ));
+    }
+    Ok(())
+}
+
+/// Signed change made by a manual stock adjustment
+pub fn adjustment_quantity(delta: i32) -> Result<(), String> {
+    if delta == 0 {
+        return Err("must not be zero".to_string());
+    }
+    if delta.unsigned_abs() > MAX_RESTOCK_QUANTITY {
+        return Err(format!(
+            "must be at most {} units either way, got {}",
+            MAX_RESTOCK_QUANTITY, delta
+        ));
+    }
+    Ok(())
+}
+
+/// A plausible address: one `@`, no whitespace and a dotted domain. Whether it
+/// receives mail is not something a format check can tell.
+pub fn email(email: &str) -> Result<(), String> {
+    if email.len() > MAX_EMAIL_LENGTH {
+        return Err(format!("must be at most {} characters", MAX_EMAIL_LENGTH));
+    }
+    let valid = match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && !email.chars().any(char::is_whitespace)
+                && domain.contains('.')
+                && domain.split('.').all(|label| !label.is_empty())
+        }
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("is not a valid email address: {}", email))
+    }
+}
+
+/// Free-text notes, counted in characters rather than bytes
+pub fn note(note: &str) -> Result<(), String> {
+    let length = note.chars().count();
+    if length > MAX_NOTE_LENGTH {
+        return Err(format!(
+            "must be at most {} characters, got {}",
+            MAX_NOTE_LENGTH, length
+        ));
+    }
+    Ok(())
+}
+
+impl Validate for CreateOrderRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.items.is_empty() {
+            return Err(AppError::EmptyCart);
+        }
+
+        let mut errors = FieldErrors::new();
+        errors.check("items", cart_lines(self.items.len()));
+
+        // Lines are identified as the terminal's cart does: product, variant
+        // and the set of chosen modifier options
+        let mut seen: HashMap<(Uuid, Option<Uuid>, Vec<Uuid>), usize> = HashMap::new();
+        for (index, item) in self.items.iter().enumerate() {
+            errors.check(
+                format!("items[{}].quantity", index),
+                item_quantity(item.quantity),
+            );
+
+            let mut options = item.modifier_option_ids.clone();
+            options.sort();
+            if options.windows(2).any(|pair| pair[0] == pair[1]) {
+                errors.add(
+                    format!("items[{}].modifier_option_ids", index),
+                    "lists the same option more than once",
+                );
+            }
+            if let Some(first) = seen.insert((item.product_id, item.variant_id, options), index) {
+                errors.add(
+                    format!("items[{}]", index),
+                    format!("duplicates items[{}]; combine their quantities", first),
+                );
+            }
+        }
+
+        if let Some(customer_email) = &self.customer_email {
+            errors.check("customer_email", email(customer_email));
+        }
+        if let Some(customer_name) = &self.customer_name {
+            errors.check("customer_name", note(customer_name));
+        }
+        if let Some(notes) = &self.notes {
+            errors.check("notes", note(notes));
+        }
+
+        errors.into_result()
+    }
+}
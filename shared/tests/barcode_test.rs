@@ -0,0 +1,60 @@
+//! Unit tests for barcode parsing
+
+#[cfg(test)]
+mod tests {
+    use shared::*;
+
+    #[test]
+    fn test_gtin_check_digits() {
+        assert!(is_valid_gtin("4006381333931")); // EAN-13
+        assert!(is_valid_gtin("036000291452")); // UPC-A
+        assert!(is_valid_gtin("12345670")); // EAN-8
+        assert!(!is_valid_gtin("4006381333932"));
+        assert!(!is_valid_gtin("40063813339A1"));
+    }
+
+    #[test]
+    fn test_upc_a_and_padded_ean13_normalize_equal() {
+        let upc = parse_scanned_code("036000291452").unwrap();
+        let ean = parse_scanned_code("0036000291452").unwrap();
+        assert_eq!(upc, ean);
+        assert_eq!(
+            upc,
+            ScannedCode::Gtin {
+                gtin: "00036000291452".to_string(),
+                kind: BarcodeKind::UpcA,
+            }
+        );
+    }
+
+    #[test]
+    fn test_ean8_is_not_reinterpreted() {
+        let code = parse_scanned_code("12345670").unwrap();
+        assert_eq!(
+            code,
+            ScannedCode::Gtin {
+                gtin: "00000012345670".to_string(),
+                kind: BarcodeKind::Ean8,
+            }
+        );
+    }
+
+    #[test]
+    fn test_weighed_item_price_extraction() {
+        let code = parse_scanned_code("2012345012509").unwrap();
+        assert_eq!(
+            code,
+            ScannedCode::WeighedItem {
+                item_prefix: "2012345".to_string(),
+                price_cents: 1250,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_scanner_input_rejected() {
+        assert!(parse_scanned_code("1234567890013").is_err());
+        assert!(parse_scanned_code("12345").is_err());
+        assert!(parse_scanned_code("latte").is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! Tests for currency minor units, rounding and locale formatting
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use shared::*;
+
+    #[test]
+    fn test_minor_units() {
+        assert_eq!(CurrencyCode::USD.minor_units(), 2);
+        assert_eq!(CurrencyCode::JPY.minor_units(), 0);
+        assert_eq!(CurrencyCode::KWD.minor_units(), 3);
+        assert_eq!(CurrencyCode::KWD.minor_per_major(), 1000);
+    }
+
+    #[test]
+    fn test_currency_codes_round_trip() {
+        for currency in CurrencyCode::ALL {
+            assert_eq!(currency.code().parse::<CurrencyCode>().unwrap(), currency);
+        }
+        assert!("usd".parse::<CurrencyCode>().is_err());
+    }
+
+    #[test]
+    fn test_from_decimal_uses_minor_units() {
+        let yen = Money::from_decimal(
+            Decimal::new(1500, 0),
+            CurrencyCode::JPY,
+            RoundingMode::HalfUp,
+        );
+        assert_eq!(yen.unwrap().amount, 1500);
+
+        let dinar = Money::from_decimal(
+            Decimal::new(12345, 3),
+            CurrencyCode::KWD,
+            RoundingMode::HalfUp,
+        );
+        assert_eq!(dinar.unwrap().amount, 12345);
+        assert_eq!(
+            Money::new(12345, CurrencyCode::KWD).as_decimal(),
+            Decimal::new(12345, 3)
+        );
+        assert_eq!(
+            Money::new(1500, CurrencyCode::JPY).as_decimal(),
+            Decimal::new(1500, 0)
+        );
+    }
+
+    #[test]
+    fn test_from_decimal_rounding_modes() {
+        // $0.125 sits exactly between 12 and 13 cents
+        let value = Decimal::new(125, 3);
+        let round = |mode| {
+            Money::from_decimal(value, CurrencyCode::USD, mode)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(round(RoundingMode::HalfUp), 13);
+        assert_eq!(round(RoundingMode::HalfDown), 12);
+        assert_eq!(round(RoundingMode::HalfEven), 12);
+        assert_eq!(round(RoundingMode::Down), 12);
+        assert_eq!(round(RoundingMode::Up), 13);
+
+        let negative = |mode| {
+            Money::from_decimal(-value, CurrencyCode::USD, mode)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(negative(RoundingMode::HalfUp), -13);
+        assert_eq!(negative(RoundingMode::Ceiling), -12);
+        assert_eq!(negative(RoundingMode::Floor), -13);
+    }
+
+    #[test]
+    fn test_convert_between_minor_units() {
+        // 1 USD buys 151.37 JPY
+        let yen = Money::from_cents(1050)
+            .convert(
+                CurrencyCode::JPY,
+                Decimal::new(15137, 2),
+                RoundingMode::HalfUp,
+            )
+            .unwrap();
+        assert_eq!(yen, Money::new(1589, CurrencyCode::JPY));
+
+        let dinar = Money::new(1000, CurrencyCode::JPY)
+            .convert(CurrencyCode::KWD, Decimal::new(2, 3), RoundingMode::Down)
+            .unwrap();
+        assert_eq!(dinar, Money::new(2000, CurrencyCode::KWD));
+
+        assert!(Money::from_cents(100)
+            .convert(CurrencyCode::EUR, Decimal::ZERO, RoundingMode::HalfUp)
+            .is_err());
+    }
+
+    #[test]
+    fn test_format_en_us() {
+        assert_eq!(Money::from_cents(123450).format(Locale::EnUs), "$1,234.50");
+        assert_eq!(Money::from_cents(-5).format(Locale::EnUs), "-$0.05");
+        assert_eq!(Money::from_cents(0).to_string(), "$0.00");
+        assert_eq!(
+            Money::new(1234567, CurrencyCode::JPY).format(Locale::JaJp),
+            "¥1,234,567"
+        );
+        assert_eq!(
+            Money::new(1500, CurrencyCode::KWD).format(Locale::EnUs),
+            "KWD\u{a0}1.500"
+        );
+    }
+
+    #[test]
+    fn test_format_european_locales() {
+        let euros = Money::new(123456789, CurrencyCode::EUR);
+        assert_eq!(euros.format(Locale::DeDe), "1.234.567,89\u{a0}€");
+        assert_eq!(
+            euros.format(Locale::FrFr),
+            "1\u{202f}234\u{202f}567,89\u{a0}€"
+        );
+        assert_eq!(
+            Money::new(123450, CurrencyCode::CHF).format(Locale::DeCh),
+            "CHF\u{a0}1’234.50"
+        );
+        assert_eq!(
+            Money::new(-123450, CurrencyCode::CAD).format(Locale::FrCa),
+            "-1\u{a0}234,50\u{a0}$"
+        );
+    }
+
+    #[test]
+    fn test_locale_parsing() {
+        assert_eq!("en-US".parse::<Locale>().unwrap(), Locale::EnUs);
+        assert_eq!("de_ch".parse::<Locale>().unwrap(), Locale::DeCh);
+        assert_eq!(Locale::FrCa.to_string(), "fr-CA");
+        assert!("xx-YY".parse::<Locale>().is_err());
+    }
+}
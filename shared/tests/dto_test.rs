@@ -0,0 +1,84 @@
+//! Tests for the API request and response types
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use shared::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_order_status_wire_names() {
+        assert_eq!(json!(OrderStatus::Cancelled), json!("cancelled"));
+        for status in OrderStatus::ALL {
+            assert_eq!(status.as_str().parse::<OrderStatus>().unwrap(), status);
+        }
+        assert!("Completed".parse::<OrderStatus>().is_err());
+        assert_eq!(json!(PaymentMethod::CreditCard), json!("credit_card"));
+    }
+
+    #[test]
+    fn test_payment_method_and_role_names() {
+        for method in PaymentMethod::ALL {
+            assert_eq!(json!(method), json!(method.as_str()));
+            assert_eq!(method.as_str().parse::<PaymentMethod>().unwrap(), method);
+        }
+        assert!("card".parse::<PaymentMethod>().is_err());
+
+        for role in Role::ALL {
+            assert_eq!(json!(role), json!(role.as_str()));
+            assert_eq!(role.as_str().parse::<Role>().unwrap(), role);
+        }
+        assert!("Manager".parse::<Role>().is_err());
+        assert!(Role::Admin.is_manager());
+        assert!(!Role::default().is_manager());
+    }
+
+    #[test]
+    fn test_product_response_defaults() {
+        let product: ProductResponse = serde_json::from_value(json!({
+            "id": Uuid::nil(),
+            "name": "Latte",
+            "description": null,
+            "price": {"amount": 450, "currency": "CAD"},
+            "category_id": null,
+            "sku": null,
+            "barcode": null,
+            "is_active": true,
+        }))
+        .unwrap();
+        assert_eq!(product.price, Money::new(450, CurrencyCode::CAD));
+        assert!(product.variants.is_empty());
+        assert!(product.available);
+    }
+
+    #[test]
+    fn test_completed_order_flattens_summary() {
+        let total = Money::from_cents(1005);
+        let summary = OrderSummary {
+            id: Uuid::nil(),
+            order_number: "ORD-1".to_string(),
+            customer_name: None,
+            customer_email: None,
+            subtotal: Money::from_cents(930),
+            tax: Money::from_cents(77),
+            cash_rounding: Money::from_cents(-2),
+            total,
+            status: OrderStatus::Completed,
+            payment_method: Some(PaymentMethod::Cash),
+            location_id: None,
+            terminal_id: None,
+            created_at: chrono::Utc::now(),
+            completed_at: None,
+        };
+        let value = json!(CompletedOrderResponse {
+            order: summary,
+            tender: None,
+        });
+        assert_eq!(value["status"], json!("completed"));
+        assert_eq!(value["total"], json!({"amount": 1005, "currency": "USD"}));
+
+        let parsed: CompletedOrderResponse = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.order.total, total);
+        assert!(parsed.tender.is_none());
+    }
+}
@@ -0,0 +1,79 @@
+//! Tests for AppError status codes and JSON error bodies
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use shared::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_status_codes() {
+        assert_eq!(
+            AppError::OrderNotFound { id: Uuid::nil() }.status_code(),
+            404
+        );
+        assert_eq!(AppError::EmptyCart.status_code(), 422);
+        assert_eq!(
+            AppError::PaymentFailed {
+                reason: "declined".into()
+            }
+            .status_code(),
+            402
+        );
+        assert_eq!(
+            AppError::Unauthorized("missing token".into()).status_code(),
+            401
+        );
+        assert_eq!(
+            AppError::Forbidden("manager role required".into()).status_code(),
+            403
+        );
+        assert_eq!(AppError::Database("timeout".into()).status_code(), 500);
+    }
+
+    #[test]
+    fn test_error_body_shape() {
+        let product_id = Uuid::nil();
+        let body = ErrorResponse::from(AppError::InsufficientInventory {
+            product_id,
+            requested: 3,
+            available: 1,
+        });
+        assert_eq!(
+            json!(body),
+            json!({
+                "code": "insufficient_inventory",
+                "message": format!(
+                    "Insufficient inventory for product {}: requested 3, available 1",
+                    product_id
+                ),
+                "details": {"product_id": product_id, "requested": 3, "available": 1},
+            })
+        );
+        assert_eq!(
+            json!(ErrorResponse::from(AppError::EmptyCart))["code"],
+            "empty_cart"
+        );
+    }
+
+    #[test]
+    fn test_internal_errors_are_sanitized() {
+        let body = ErrorResponse::from(AppError::Database(
+            "relation \"orders\" does not exist".into(),
+        ));
+        assert_eq!(
+            body.error,
+            AppError::Internal("Internal server error".into())
+        );
+        assert!(!json!(body).to_string().contains("orders"));
+    }
+
+    #[test]
+    fn test_error_body_round_trips() {
+        let error = AppError::InvalidQuantity { quantity: 0 };
+        let body: ErrorResponse =
+            serde_json::from_value(json!(ErrorResponse::from(error.clone()))).unwrap();
+        assert_eq!(body.error, error);
+        assert_eq!(body.message, error.to_string());
+    }
+}
@@ -0,0 +1,283 @@
+//! Tests for Money arithmetic, allocation, percentages and cash rounding
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use rust_decimal::Decimal;
+    use shared::*;
+
+    fn currency() -> impl Strategy<Value = CurrencyCode> {
+        prop::sample::select(CurrencyCode::ALL.to_vec())
+    }
+
+    #[test]
+    fn test_add_rejects_currency_mismatch() {
+        let usd = Money::from_cents(500);
+        let eur = Money::new(300, CurrencyCode::EUR);
+        assert!(matches!(
+            usd.checked_add(eur),
+            Err(AppError::CurrencyMismatch {
+                expected: CurrencyCode::USD,
+                found: CurrencyCode::EUR,
+            })
+        ));
+        assert!(usd.checked_sub(eur).is_err());
+    }
+
+    #[test]
+    fn test_compare_rejects_currency_mismatch() {
+        let usd = Money::from_cents(500);
+        assert_eq!(
+            usd.checked_cmp(&Money::from_cents(300)).unwrap(),
+            std::cmp::Ordering::Greater
+        );
+        assert!(matches!(
+            usd.checked_cmp(&Money::new(300, CurrencyCode::EUR)),
+            Err(AppError::CurrencyMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        let max = Money::from_cents(i64::MAX);
+        assert!(matches!(
+            max.checked_add(Money::from_cents(1)),
+            Err(AppError::AmountOverflow)
+        ));
+        assert!(matches!(max.checked_mul(2), Err(AppError::AmountOverflow)));
+        assert!(matches!(
+            Money::from_cents(i64::MIN).checked_neg(),
+            Err(AppError::AmountOverflow)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Currency mismatch")]
+    fn test_operator_panics_on_currency_mismatch() {
+        let _ = Money::from_cents(100) + Money::new(100, CurrencyCode::GBP);
+    }
+
+    #[test]
+    fn test_operators() {
+        let mut total = Money::from_cents(450);
+        total += Money::from_cents(60);
+        total -= Money::from_cents(10);
+        assert_eq!(total, Money::from_cents(500));
+        assert_eq!(-total, Money::from_cents(-500));
+        assert_eq!(total * 3u32, Money::from_cents(1500));
+        assert_eq!(total * -2i64, Money::from_cents(-1000));
+    }
+
+    #[test]
+    fn test_percentage_rounding_modes() {
+        // 5% of 50 cents is exactly 2.5 cents
+        let coffee = Money::from_cents(50);
+        let five = Decimal::new(5, 0);
+        assert_eq!(
+            coffee
+                .percentage(five, RoundingMode::HalfUp)
+                .unwrap()
+                .amount,
+            3
+        );
+        assert_eq!(
+            coffee
+                .percentage(five, RoundingMode::HalfEven)
+                .unwrap()
+                .amount,
+            2
+        );
+        assert_eq!(
+            coffee.percentage(five, RoundingMode::Down).unwrap().amount,
+            2
+        );
+    }
+
+    #[test]
+    fn test_percentage_rounds_half_away_from_zero() {
+        let price = Money::from_cents(1050);
+        assert_eq!(
+            price
+                .percentage(Decimal::new(10, 0), RoundingMode::HalfUp)
+                .unwrap()
+                .amount,
+            105
+        );
+        // 8.25% of $10.50 is 86.625 cents
+        assert_eq!(
+            price
+                .percentage(Decimal::new(825, 2), RoundingMode::HalfUp)
+                .unwrap()
+                .amount,
+            87
+        );
+        assert_eq!(
+            Money::from_cents(-1050)
+                .percentage(Decimal::new(825, 2), RoundingMode::HalfUp)
+                .unwrap()
+                .amount,
+            -87
+        );
+        assert!(Money::from_cents(i64::MAX)
+            .percentage(Decimal::new(200, 0), RoundingMode::HalfUp)
+            .is_err());
+    }
+
+    #[test]
+    fn test_split_does_not_lose_cents() {
+        let shares = Money::from_cents(1000).split(3).unwrap();
+        let amounts: Vec<i64> = shares.iter().map(|m| m.amount).collect();
+        assert_eq!(amounts, vec![334, 333, 333]);
+    }
+
+    #[test]
+    fn test_allocate_by_weight() {
+        let shares = Money::from_cents(-100).allocate(&[1, 1, 2]).unwrap();
+        let amounts: Vec<i64> = shares.iter().map(|m| m.amount).collect();
+        assert_eq!(amounts, vec![-25, -25, -50]);
+
+        assert!(Money::from_cents(100).allocate(&[]).is_err());
+        assert!(Money::from_cents(100).allocate(&[0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_cash_rounding_to_nickels() {
+        let rounding = CashRounding::nickel();
+        let rounded: Vec<i64> = (1000..=1010)
+            .map(|cents| rounding.round(Money::from_cents(cents)).unwrap().amount)
+            .collect();
+        assert_eq!(
+            rounded,
+            vec![1000, 1000, 1000, 1005, 1005, 1005, 1005, 1005, 1010, 1010, 1010]
+        );
+        assert_eq!(
+            rounding.adjustment(Money::from_cents(1002)).unwrap(),
+            Money::from_cents(-2)
+        );
+        assert_eq!(
+            rounding.adjustment(Money::new(998, CurrencyCode::CAD)).unwrap(),
+            Money::new(2, CurrencyCode::CAD)
+        );
+    }
+
+    #[test]
+    fn test_cash_rounding_configuration() {
+        assert!(!CashRounding::default().is_enabled());
+        assert_eq!(
+            CashRounding::default()
+                .adjustment(Money::from_cents(1234))
+                .unwrap(),
+            Money::from_cents(0)
+        );
+        assert!(CashRounding::new(0, RoundingMode::HalfUp).is_err());
+
+        let tens_down = CashRounding::new(10, RoundingMode::Down).unwrap();
+        assert_eq!(tens_down.round(Money::from_cents(1299)).unwrap().amount, 1290);
+        assert!(Money::from_cents(i64::MAX)
+            .round_to_increment(10, RoundingMode::Up)
+            .is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_add_then_sub_round_trips(
+            a in -1_000_000_000_000i64..1_000_000_000_000,
+            b in -1_000_000_000_000i64..1_000_000_000_000,
+            currency in currency(),
+        ) {
+            let a = Money::new(a, currency);
+            let b = Money::new(b, currency);
+            prop_assert_eq!(a.checked_add(b).unwrap().checked_sub(b).unwrap(), a);
+            prop_assert_eq!(a.checked_add(b).unwrap(), b.checked_add(a).unwrap());
+        }
+
+        #[test]
+        fn prop_checked_add_matches_i64(a in any::<i64>(), b in any::<i64>()) {
+            let result = Money::from_cents(a).checked_add(Money::from_cents(b));
+            match a.checked_add(b) {
+                Some(sum) => prop_assert_eq!(result.unwrap().amount, sum),
+                None => prop_assert!(matches!(result, Err(AppError::AmountOverflow))),
+            }
+        }
+
+        #[test]
+        fn prop_mismatched_currencies_never_combine(
+            a in any::<i64>(),
+            b in any::<i64>(),
+            first in currency(),
+            second in currency(),
+        ) {
+            prop_assume!(first != second);
+            let a = Money::new(a, first);
+            let b = Money::new(b, second);
+            let is_mismatch = |r: AppResult<Money>| matches!(r, Err(AppError::CurrencyMismatch { .. }));
+            prop_assert!(is_mismatch(a.checked_add(b)));
+            prop_assert!(is_mismatch(a.checked_sub(b)));
+        }
+
+        #[test]
+        fn prop_negation_is_an_involution(a in (i64::MIN + 1)..=i64::MAX) {
+            let money = Money::from_cents(a);
+            prop_assert_eq!(-(-money), money);
+            prop_assert!((money + -money).is_zero());
+        }
+
+        #[test]
+        fn prop_allocation_preserves_total(
+            amount in any::<i64>(),
+            weights in prop::collection::vec(0u32..1000, 1..20),
+            currency in currency(),
+        ) {
+            prop_assume!(weights.iter().any(|&w| w > 0));
+            let total = Money::new(amount, currency);
+            let shares = total.allocate(&weights).unwrap();
+
+            prop_assert_eq!(shares.len(), weights.len());
+            let sum: i128 = shares.iter().map(|m| m.amount as i128).sum();
+            prop_assert_eq!(sum, amount as i128);
+            for (share, &weight) in shares.iter().zip(&weights) {
+                prop_assert_eq!(share.currency, currency);
+                prop_assert!(share.amount == 0 || share.amount.signum() == amount.signum());
+                if weight == 0 {
+                    prop_assert_eq!(share.amount, 0);
+                }
+            }
+        }
+
+        #[test]
+        fn prop_split_shares_differ_by_at_most_one(
+            amount in -1_000_000_000i64..1_000_000_000,
+            parts in 1usize..50,
+        ) {
+            let shares = Money::from_cents(amount).split(parts).unwrap();
+            let largest = shares.iter().map(|m| m.amount.abs()).max().unwrap();
+            let smallest = shares.iter().map(|m| m.amount.abs()).min().unwrap();
+            prop_assert!(largest - smallest <= 1);
+            prop_assert_eq!(shares.iter().map(|m| m.amount).sum::<i64>(), amount);
+        }
+
+        #[test]
+        fn prop_percentage_is_within_half_a_unit(
+            amount in -1_000_000_000i64..1_000_000_000,
+            basis_points in 0i64..20_000,
+        ) {
+            let percent = Decimal::new(basis_points, 2);
+            let exact = Decimal::from(amount) * percent / Decimal::from(100);
+            let rounded = Money::from_cents(amount).percentage(percent, RoundingMode::HalfEven).unwrap();
+            let error = (Decimal::from(rounded.amount) - exact).abs();
+            prop_assert!(error <= Decimal::new(5, 1));
+        }
+
+        #[test]
+        fn prop_cash_rounding_stays_within_half_an_increment(
+            amount in -1_000_000_000i64..1_000_000_000,
+            increment in 1i64..100,
+        ) {
+            let rounding = CashRounding::new(increment, RoundingMode::HalfUp).unwrap();
+            let total = Money::from_cents(amount);
+            let rounded = rounding.round(total).unwrap();
+            prop_assert_eq!(rounded.amount % increment, 0);
+            prop_assert!(2 * rounding.adjustment(total).unwrap().amount.abs() <= increment);
+        }
+    }
+}
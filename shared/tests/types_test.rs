@@ -7,8 +7,12 @@ mod tests {
 
     #[test]
     fn test_money_from_dollars() {
-        let money = Money::from_dollars(Decimal::new(1050, 2)); // $10.50
+        let money = Money::from_dollars(Decimal::new(1050, 2)).unwrap(); // $10.50
         assert_eq!(money.amount, 1050);
+        assert!(matches!(
+            Money::from_dollars(Decimal::MAX),
+            Err(AppError::AmountOverflow)
+        ));
     }
 
     #[test]
@@ -43,4 +47,45 @@ mod tests {
         assert_eq!(cart_item.quantity, 2);
         assert_eq!(cart_item.total_price.amount, 1000);
     }
+
+    #[test]
+    fn test_cart_item_with_modifiers() {
+        let product = Product {
+            id: uuid::Uuid::new_v4(),
+            name: "Latte".to_string(),
+            description: None,
+            price: Money::from_cents(450),
+            category_id: None,
+            is_active: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+
+        let modifiers = vec![
+            CartItemModifier {
+                option_id: uuid::Uuid::new_v4(),
+                group_name: "Milk".to_string(),
+                option_name: "Oat Milk".to_string(),
+                price_delta: Money::from_cents(60),
+            },
+            CartItemModifier {
+                option_id: uuid::Uuid::new_v4(),
+                group_name: "Extra Shots".to_string(),
+                option_name: "Extra Shot".to_string(),
+                price_delta: Money::from_cents(75),
+            },
+        ];
+
+        let cart_item = CartItem::with_modifiers(&product, 2, modifiers);
+        assert_eq!(cart_item.unit_price.amount, 585);
+        assert_eq!(cart_item.total_price.amount, 1170);
+        assert_eq!(cart_item.modifiers.len(), 2);
+    }
+
+    #[test]
+    fn test_currency_code_parsing() {
+        assert_eq!("EUR".parse::<CurrencyCode>().unwrap(), CurrencyCode::EUR);
+        assert_eq!(CurrencyCode::CAD.code(), "CAD");
+        assert!("XYZ".parse::<CurrencyCode>().is_err());
+    }
 }
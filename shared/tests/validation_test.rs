@@ -0,0 +1,174 @@
+//! Tests for request validation
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use shared::validation::{self, FieldError};
+    use shared::*;
+    use uuid::Uuid;
+
+    fn item(product_id: Uuid, quantity: i32) -> OrderItemRequest {
+        OrderItemRequest {
+            product_id,
+            variant_id: None,
+            quantity,
+            modifier_option_ids: Vec::new(),
+        }
+    }
+
+    fn order(items: Vec<OrderItemRequest>) -> CreateOrderRequest {
+        CreateOrderRequest {
+            items,
+            customer_name: None,
+            customer_email: None,
+            notes: None,
+            terminal_id: None,
+        }
+    }
+
+    fn field_errors(result: Result<(), AppError>) -> Vec<String> {
+        match result {
+            Err(AppError::InvalidFields { errors }) => {
+                errors.into_iter().map(|error| error.field).collect()
+            }
+            other => panic!("expected field errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_item_quantity_bounds() {
+        assert!(validation::item_quantity(0).is_err());
+        assert!(validation::item_quantity(-3).is_err());
+        assert!(validation::item_quantity(1).is_ok());
+        assert!(validation::item_quantity(MAX_ITEM_QUANTITY as i32).is_ok());
+        assert!(validation::item_quantity(MAX_ITEM_QUANTITY as i32 + 1).is_err());
+    }
+
+    #[test]
+    fn test_restock_quantity_bounds() {
+        assert!(validation::restock_quantity(0).is_err());
+        assert!(validation::restock_quantity(MAX_RESTOCK_QUANTITY as i32).is_ok());
+        assert!(validation::restock_quantity(MAX_RESTOCK_QUANTITY as i32 + 1).is_err());
+    }
+
+    #[test]
+    fn test_adjustment_quantity_bounds() {
+        assert!(validation::adjustment_quantity(0).is_err());
+        assert!(validation::adjustment_quantity(-3).is_ok());
+        assert!(validation::adjustment_quantity(MAX_RESTOCK_QUANTITY as i32).is_ok());
+        assert!(validation::adjustment_quantity(-(MAX_RESTOCK_QUANTITY as i32) - 1).is_err());
+    }
+
+    #[test]
+    fn test_email_format() {
+        for valid in ["ana@example.com", "a.b+tag@mail.example.co.uk"] {
+            assert!(validation::email(valid).is_ok(), "{}", valid);
+        }
+        for invalid in [
+            "",
+            "ana",
+            "@example.com",
+            "ana@",
+            "ana@example",
+            "ana@@example.com",
+            "ana@example..com",
+            "ana smith@example.com",
+        ] {
+            assert!(validation::email(invalid).is_err(), "{}", invalid);
+        }
+        let long = format!("{}@example.com", "a".repeat(MAX_EMAIL_LENGTH));
+        assert!(validation::email(&long).is_err());
+    }
+
+    #[test]
+    fn test_note_length_counts_characters() {
+        assert!(validation::note(&"é".repeat(MAX_NOTE_LENGTH)).is_ok());
+        assert!(validation::note(&"a".repeat(MAX_NOTE_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_valid_order() {
+        let mut request = order(vec![item(Uuid::new_v4(), 2), item(Uuid::new_v4(), 1)]);
+        request.customer_email = Some("ana@example.com".to_string());
+        assert_eq!(request.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_empty_order_is_an_empty_cart() {
+        assert_eq!(order(Vec::new()).validate(), Err(AppError::EmptyCart));
+    }
+
+    #[test]
+    fn test_order_reports_every_field() {
+        let latte = Uuid::new_v4();
+        let mut request = order(vec![
+            item(latte, 0),
+            item(Uuid::new_v4(), -1),
+            item(latte, 2),
+        ]);
+        request.customer_email = Some("not-an-email".to_string());
+        request.notes = Some("x".repeat(MAX_NOTE_LENGTH + 1));
+
+        assert_eq!(
+            field_errors(request.validate()),
+            vec![
+                "items[0].quantity",
+                "items[1].quantity",
+                "items[2]",
+                "customer_email",
+                "notes"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_lines_ignore_modifier_order() {
+        let latte = Uuid::new_v4();
+        let (oat, vanilla) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut first = item(latte, 1);
+        first.modifier_option_ids = vec![oat, vanilla];
+        let mut second = item(latte, 1);
+        second.modifier_option_ids = vec![vanilla, oat];
+        let plain = item(latte, 1);
+
+        assert_eq!(
+            field_errors(order(vec![first.clone(), plain, second]).validate()),
+            vec!["items[2]"]
+        );
+
+        first.modifier_option_ids.push(oat);
+        assert_eq!(
+            field_errors(order(vec![first]).validate()),
+            vec!["items[0].modifier_option_ids"]
+        );
+    }
+
+    #[test]
+    fn test_order_line_limit() {
+        let items = (0..=MAX_CART_ITEMS)
+            .map(|_| item(Uuid::new_v4(), 1))
+            .collect();
+        assert_eq!(field_errors(order(items).validate()), vec!["items"]);
+    }
+
+    #[test]
+    fn test_field_errors_in_error_body() {
+        let error = AppError::InvalidFields {
+            errors: vec![FieldError {
+                field: "items[0].quantity".to_string(),
+                message: "must be at least 1, got 0".to_string(),
+            }],
+        };
+        assert_eq!(error.status_code(), 422);
+        assert_eq!(
+            json!(ErrorResponse::from(error)),
+            json!({
+                "code": "invalid_fields",
+                "message": "Invalid input: items[0].quantity: must be at least 1, got 0",
+                "details": {"errors": [
+                    {"field": "items[0].quantity", "message": "must be at least 1, got 0"}
+                ]},
+            })
+        );
+    }
+}